@@ -0,0 +1,141 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! On-disk cache of per-directory subtree totals for `--cache`.
+//!
+//! Each entry stores a directory's identity at the time its subtree was
+//! accounted plus the aggregated results of that accounting. On a later run
+//! the subtree can be skipped entirely when the identity still matches;
+//! any mismatch falls back to a full scan of that subtree.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Identity of a directory at the time its subtree was accounted. A cached
+/// subtree is only reused when all of these still match.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DirKey {
+    pub dev_id: u64,
+    pub file_id: u128,
+    pub modified: u64,
+    pub size: u64,
+}
+
+/// The aggregated results of a subtree as previously computed.
+#[derive(Clone, Copy)]
+pub struct DirTotals {
+    pub size: u64,
+    pub blocks: u64,
+    pub inodes: u64,
+}
+
+pub struct DirCache {
+    file: PathBuf,
+    /// Sorted by path, so a subtree's entries form a contiguous range.
+    loaded: BTreeMap<PathBuf, (DirKey, DirTotals)>,
+    /// Entries validated or recomputed during this run; these replace the
+    /// file's contents on [`DirCache::save`], so stale entries age out.
+    current: HashMap<PathBuf, (DirKey, DirTotals)>,
+}
+
+impl DirCache {
+    /// Load the cache from `file`. A missing file yields an empty cache and
+    /// unreadable lines are skipped, so a corrupt or outdated cache merely
+    /// forces a full scan.
+    pub fn load(file: &Path) -> Self {
+        let mut loaded = BTreeMap::new();
+        if let Ok(contents) = fs::read_to_string(file) {
+            for line in contents.lines() {
+                if let Some((path, key, totals)) = parse_line(line) {
+                    loaded.insert(path, (key, totals));
+                }
+            }
+        }
+        Self {
+            file: file.to_path_buf(),
+            loaded,
+            current: HashMap::new(),
+        }
+    }
+
+    /// Previously computed totals for `path`, provided its own identity still
+    /// matches `key` and `verify` confirms every cached directory below it.
+    /// Verifying the descendants catches changes that are only visible in a
+    /// subdirectory's metadata (e.g. a file added deep inside the subtree)
+    /// while still avoiding the scan of the files themselves. A hit carries
+    /// all involved entries over into the next save.
+    pub fn lookup(
+        &mut self,
+        path: &Path,
+        key: &DirKey,
+        verify: impl Fn(&Path, &DirKey) -> bool,
+    ) -> Option<DirTotals> {
+        let &(cached_key, totals) = self.loaded.get(path)?;
+        if cached_key != *key {
+            return None;
+        }
+        let subtree: Vec<_> = self
+            .loaded
+            .range(path.to_path_buf()..)
+            .take_while(|(p, _)| p.starts_with(path))
+            .map(|(p, &entry)| (p.clone(), entry))
+            .collect();
+        if subtree.iter().any(|(p, (k, _))| p != path && !verify(p, k)) {
+            return None;
+        }
+        self.current.extend(subtree);
+        Some(totals)
+    }
+
+    pub fn record(&mut self, path: &Path, key: DirKey, totals: DirTotals) {
+        self.current.insert(path.to_path_buf(), (key, totals));
+    }
+
+    /// Write the entries seen during this run back to the cache file.
+    pub fn save(&self) -> io::Result<()> {
+        let mut out = String::new();
+        for (path, (key, totals)) in &self.current {
+            // the line-based format cannot represent all paths; directories
+            // with such names are simply rescanned every run
+            let Some(path) = path.to_str() else { continue };
+            if path.contains('\n') || path.contains('\t') {
+                continue;
+            }
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                key.dev_id,
+                key.file_id,
+                key.modified,
+                key.size,
+                totals.size,
+                totals.blocks,
+                totals.inodes,
+                path
+            )
+            .unwrap();
+        }
+        fs::write(&self.file, out)
+    }
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, DirKey, DirTotals)> {
+    let mut fields = line.splitn(8, '\t');
+    let key = DirKey {
+        dev_id: fields.next()?.parse().ok()?,
+        file_id: fields.next()?.parse().ok()?,
+        modified: fields.next()?.parse().ok()?,
+        size: fields.next()?.parse().ok()?,
+    };
+    let totals = DirTotals {
+        size: fields.next()?.parse().ok()?,
+        blocks: fields.next()?.parse().ok()?,
+        inodes: fields.next()?.parse().ok()?,
+    };
+    Some((PathBuf::from(fields.next()?), key, totals))
+}
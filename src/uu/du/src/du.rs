@@ -5,15 +5,17 @@
 
 use chrono::{DateTime, Local};
 use clap::{builder::PossibleValue, crate_version, Arg, ArgAction, ArgMatches, Command};
-use glob::Pattern;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::error::Error;
 use std::fmt::Display;
 #[cfg(not(windows))]
 use std::fs::Metadata;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, IsTerminal, Write};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
 #[cfg(not(windows))]
 use std::os::unix::fs::MetadataExt;
 #[cfg(windows)]
@@ -25,11 +27,11 @@ use std::str::FromStr;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, UNIX_EPOCH};
-use uucore::display::{print_verbatim, Quotable};
+use uucore::display::Quotable;
 use uucore::error::{set_exit_code, FromIo, UError, UResult, USimpleError};
 use uucore::line_ending::LineEnding;
 use uucore::parse_glob;
-use uucore::parse_size::{parse_size_u64, ParseSizeError};
+use uucore::parse_size::{parse_block_size, parse_size_u64, BlockSize, ParseSizeError};
 use uucore::shortcut_value_parser::ShortcutValueParser;
 use uucore::{format_usage, help_about, help_section, help_usage, show, show_error, show_warning};
 #[cfg(windows)]
@@ -40,15 +42,27 @@ use windows_sys::Win32::Storage::FileSystem::{
     FILE_STANDARD_INFO,
 };
 
+mod cache;
+#[cfg(feature = "duplicates")]
+mod duplicates;
+pub mod excludes;
+#[cfg(feature = "gitignore")]
+mod gitignore;
+pub mod physical_extents;
+
+use excludes::{is_excluded, ExcludePattern};
+
 mod options {
     pub const HELP: &str = "help";
     pub const NULL: &str = "0";
     pub const ALL: &str = "all";
     pub const APPARENT_SIZE: &str = "apparent-size";
     pub const BLOCK_SIZE: &str = "block-size";
+    pub const BLOCKS: &str = "blocks";
     pub const BYTES: &str = "b";
     pub const TOTAL: &str = "c";
     pub const MAX_DEPTH: &str = "d";
+    pub const MIN_DEPTH: &str = "min-depth";
     pub const HUMAN_READABLE: &str = "h";
     pub const BLOCK_SIZE_1K: &str = "k";
     pub const COUNT_LINKS: &str = "l";
@@ -57,17 +71,34 @@ mod options {
     pub const SUMMARIZE: &str = "s";
     pub const THRESHOLD: &str = "threshold";
     pub const SI: &str = "si";
+    pub const UNIT: &str = "unit";
     pub const TIME: &str = "time";
     pub const TIME_STYLE: &str = "time-style";
     pub const ONE_FILE_SYSTEM: &str = "one-file-system";
+    pub const SAME_FS_AS: &str = "same-fs-as";
+    pub const SKIP_BIND_MOUNTS: &str = "skip-bind-mounts";
     pub const DEREFERENCE: &str = "dereference";
     pub const DEREFERENCE_ARGS: &str = "dereference-args";
     pub const NO_DEREFERENCE: &str = "no-dereference";
     pub const INODES: &str = "inodes";
+    pub const PERCENT: &str = "percent";
     pub const EXCLUDE: &str = "exclude";
     pub const EXCLUDE_FROM: &str = "exclude-from";
+    pub const EXCLUDE_SIZE: &str = "exclude-size";
     pub const FILES0_FROM: &str = "files0-from";
     pub const VERBOSE: &str = "verbose";
+    pub const SORT: &str = "sort";
+    pub const BY_EXTENSION: &str = "by-extension";
+    pub const GENERATE_COMPLETION: &str = "generate-completion";
+    pub const WATCH: &str = "watch";
+    pub const OUTPUT_FILE: &str = "output-file";
+    #[cfg(feature = "gitignore")]
+    pub const RESPECT_GITIGNORE: &str = "respect-gitignore";
+    #[cfg(feature = "duplicates")]
+    pub const DUPLICATES: &str = "duplicates";
+    pub const SHARED_EXTENTS: &str = "shared-extents";
+    pub const SHARED_PERCENT: &str = "shared-percent";
+    pub const CACHE: &str = "cache";
     pub const FILE: &str = "FILE";
 }
 
@@ -75,27 +106,110 @@ const ABOUT: &str = help_about!("du.md");
 const AFTER_HELP: &str = help_section!("after help", "du.md");
 const USAGE: &str = help_usage!("du.md");
 
+/// How the entries of a single directory are ordered during the traversal
+/// (`--sort`). This is the only configurable part of the output ordering;
+/// see [`du`] for the rest of the contract.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EntrySort {
+    /// The order the OS returns the entries in.
+    Os,
+    /// Sorted by file name, making the output reproducible across runs and
+    /// filesystems.
+    Name,
+}
+
+/// Bookkeeping behind the hard-link report printed in verbose mode: the
+/// path that first claimed each inode, and the savings from the links
+/// skipped afterwards.
+#[derive(Default)]
+struct HardLinkStats {
+    first_claim: HashMap<FileInfo, PathBuf>,
+    skipped: u64,
+    saved_bytes: u64,
+}
+
 struct TraversalOptions {
     all: bool,
     separate_dirs: bool,
     one_file_system: bool,
+    /// Restrict the traversal to the filesystem with this device id (`--same-fs-as`).
+    same_fs_dev: Option<u64>,
+    /// Mount points that repeat content reachable elsewhere (`--skip-bind-mounts`).
+    bind_mounts: Vec<PathBuf>,
     dereference: Deref,
     count_links: bool,
     verbose: bool,
-    excludes: Vec<Pattern>,
+    /// Tracks what the inode check deduplicated, for the hard-link report
+    /// in verbose mode.
+    hard_link_stats: Option<RefCell<HardLinkStats>>,
+    /// How the entries of each directory are ordered (`--sort`).
+    sort: EntrySort,
+    excludes: Vec<ExcludePattern>,
+    /// Leave files whose apparent size lies in this range out of the
+    /// accounting (`--exclude-size`).
+    exclude_size: Option<SizeRange>,
+    /// Exclude paths matched by the tree's `.gitignore` files (`--respect-gitignore`).
+    #[cfg(feature = "gitignore")]
+    gitignore: Option<gitignore::Matcher>,
+    /// Collects candidates for the duplicate-file report (`--duplicates`).
+    #[cfg(feature = "duplicates")]
+    duplicates: Option<RefCell<duplicates::Recorder>>,
+    /// Storage ranges already accounted, so shared storage is only counted
+    /// once (`--shared-extents`).
+    seen_extents: Option<RefCell<physical_extents::SeenPhysicalExtents>>,
+    /// Subtree totals from an earlier run, reused for directories whose
+    /// metadata is unchanged (`--cache`).
+    dir_cache: Option<RefCell<cache::DirCache>>,
 }
 
+#[derive(Clone)]
 struct StatPrinter {
     total: bool,
     inodes: bool,
-    max_depth: Option<usize>,
+    /// Print inode counts as a percentage of the filesystem's total inodes
+    /// (`--inodes --percent`).
+    inodes_percent: bool,
+    /// Total inode counts per device id, filled lazily via statvfs.
+    fs_inodes_cache: RefCell<HashMap<u64, Option<u64>>>,
+    /// Print the per-file shared-storage percentage column
+    /// (`--shared-extents --shared-percent`).
+    shared_percent: bool,
+    output_mode: OutputMode,
+    /// Suppress printing (but not accounting) of entries above this depth
+    /// (`--min-depth`).
+    min_depth: Option<usize>,
     threshold: Option<Threshold>,
     apparent_size: bool,
+    /// Print raw allocation counts in this unit (512 or 1024 bytes),
+    /// bypassing `size_format` and the apparent-size logic (`--blocks`).
+    blocks: Option<u64>,
     size_format: SizeFormat,
     time: Option<Time>,
     time_format: String,
     line_ending: LineEnding,
-    summarize: bool,
+    by_extension: bool,
+    /// Write the report here instead of stdout, via a temporary sibling file
+    /// that is renamed into place once complete (`--output-file`).
+    output_file: Option<PathBuf>,
+}
+
+/// Which entries of the traversal get a printed line. Everything is still
+/// scanned and accounted either way; this only decides what to show.
+#[derive(Clone, Copy)]
+enum OutputMode {
+    /// Exactly one line per command line operand (`--summarize`).
+    Summarize,
+    /// Every entry up to the given depth (`--max-depth`, default unlimited).
+    Tree(Option<usize>),
+}
+
+impl OutputMode {
+    fn includes_depth(&self, depth: usize) -> bool {
+        match self {
+            Self::Summarize => depth == 0,
+            Self::Tree(max_depth) => max_depth.map_or(true, |max_depth| depth <= max_depth),
+        }
+    }
 }
 
 #[derive(PartialEq, Clone)]
@@ -116,7 +230,11 @@ enum Time {
 enum SizeFormat {
     HumanDecimal,
     HumanBinary,
-    BlockSize(u64),
+    FixedUnit(uucore::format::human::FixedUnit),
+    BlockSize {
+        size: u64,
+        thousands_separators: bool,
+    },
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
@@ -132,6 +250,9 @@ struct Stat {
     blocks: u64,
     inodes: u64,
     inode: Option<FileInfo>,
+    /// Fraction of this file's storage that was already seen on other files
+    /// (`--shared-extents --shared-percent`).
+    shared_ratio: Option<f64>,
     created: Option<u64>,
     accessed: u64,
     modified: u64,
@@ -168,6 +289,7 @@ impl Stat {
                 blocks: metadata.blocks(),
                 inodes: 1,
                 inode: Some(file_info),
+                shared_ratio: None,
                 created: birth_u64(&metadata),
                 accessed: metadata.atime() as u64,
                 modified: metadata.mtime() as u64,
@@ -186,6 +308,7 @@ impl Stat {
                 blocks: size_on_disk / 1024 * 2,
                 inodes: 1,
                 inode: file_info,
+                shared_ratio: None,
                 created: windows_creation_time_to_unix_time(metadata.creation_time()),
                 accessed: windows_time_to_unix_time(metadata.last_access_time()),
                 modified: windows_time_to_unix_time(metadata.last_write_time()),
@@ -207,6 +330,28 @@ fn windows_creation_time_to_unix_time(win_time: u64) -> Option<u64> {
     (win_time / 10_000_000).checked_sub(11_644_473_600)
 }
 
+/// Total number of inodes of the filesystem containing `path`, or `None` if
+/// it cannot be determined.
+#[cfg(unix)]
+fn filesystem_total_inodes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is a valid
+    // out-pointer for the duration of the call.
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } == 0 {
+        Some(stats.f_files as u64)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn filesystem_total_inodes(_path: &Path) -> Option<u64> {
+    None
+}
+
 #[cfg(not(windows))]
 fn birth_u64(meta: &Metadata) -> Option<u64> {
     meta.created()
@@ -276,28 +421,210 @@ fn get_file_info(path: &Path) -> Option<FileInfo> {
     result
 }
 
-fn read_block_size(s: Option<&str>) -> UResult<u64> {
+/// Return the device id of the filesystem `path` resides on.
+fn device_of(path: &Path) -> UResult<u64> {
+    #[cfg(not(windows))]
+    {
+        let metadata =
+            fs::metadata(path).map_err_context(|| format!("cannot access {}", path.quote()))?;
+        Ok(metadata.dev())
+    }
+    #[cfg(windows)]
+    {
+        get_file_info(path)
+            .map(|info| info.dev_id)
+            .ok_or_else(|| USimpleError::new(1, format!("cannot access {}", path.quote())))
+    }
+}
+
+/// Collect mount points from `/proc/self/mountinfo` whose content is also reachable
+/// through another mount of the same filesystem, i.e. bind mounts of a directory
+/// (root field is not "/") and repeated mounts of the same (device, root) pair.
+/// Traversing these would count the content twice, even with `--one-file-system`
+/// since bind mounts share the device id of their source.
+#[cfg(target_os = "linux")]
+fn read_bind_mount_targets() -> std::io::Result<Vec<PathBuf>> {
+    let mut targets = Vec::new();
+    let mut seen_sources = HashSet::new();
+    for line in fs::read_to_string("/proc/self/mountinfo")?.lines() {
+        let mut fields = line.split(' ');
+        let (Some(dev), Some(root), Some(mount_point)) =
+            (fields.nth(2), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if root != "/" || !seen_sources.insert((dev.to_string(), root.to_string())) {
+            targets.push(PathBuf::from(unescape_mountinfo_path(mount_point)));
+        }
+    }
+    Ok(targets)
+}
+
+/// Decode the octal escapes (`\040` for space etc.) used in `/proc/self/mountinfo`.
+#[cfg(target_os = "linux")]
+fn unescape_mountinfo_path(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let octal: String = chars.by_ref().take(3).collect();
+            if let Ok(code) = u8::from_str_radix(&octal, 8) {
+                result.push(code as char);
+                continue;
+            }
+            result.push('\\');
+            result.push_str(&octal);
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn read_block_size(s: Option<&str>) -> UResult<BlockSize> {
     if let Some(s) = s {
-        parse_size_u64(s)
+        parse_block_size(s)
             .map_err(|e| USimpleError::new(1, format_error_message(&e, s, options::BLOCK_SIZE)))
     } else {
         for env_var in ["DU_BLOCK_SIZE", "BLOCK_SIZE", "BLOCKSIZE"] {
             if let Ok(env_size) = env::var(env_var) {
-                if let Ok(v) = parse_size_u64(&env_size) {
+                if let Ok(v) = parse_block_size(&env_size) {
+                    // the BSD style BLOCKSIZE only understands plain sizes
+                    if env_var == "BLOCKSIZE" && !matches!(v, BlockSize::Bytes { .. }) {
+                        continue;
+                    }
                     return Ok(v);
                 }
             }
         }
-        if env::var("POSIXLY_CORRECT").is_ok() {
-            Ok(512)
+        let size = if env::var("POSIXLY_CORRECT").is_ok() {
+            512
         } else {
-            Ok(1024)
+            1024
+        };
+        Ok(BlockSize::Bytes {
+            size,
+            thousands_separators: false,
+        })
+    }
+}
+
+/// Format `n` with digit grouping, e.g. 1234567 becomes "1,234,567". GNU
+/// consults the locale for the separator; we always use a comma.
+fn separate_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
         }
+        result.push(c);
+    }
+    result
+}
+
+/// Account `stat`'s storage against the extents seen so far: the block count
+/// is reduced by the bytes that other files already claimed, and the shared
+/// fraction is recorded for `--shared-percent`.
+fn account_shared_extents(
+    stat: &mut Stat,
+    seen_extents: &RefCell<physical_extents::SeenPhysicalExtents>,
+) {
+    let Some(info) = stat.inode else {
+        return;
+    };
+    let allocated = stat.blocks * 512;
+    let mut shared = 0;
+    for extent in physical_extents::file_extents(&stat.path, info.dev_id, info.file_id, allocated) {
+        shared += seen_extents
+            .borrow_mut()
+            .get_overlapping_and_insert(&extent);
+    }
+    let shared = shared.min(allocated);
+    stat.shared_ratio = Some(if allocated == 0 {
+        0.0
+    } else {
+        shared as f64 / allocated as f64
+    });
+    stat.blocks -= shared / 512;
+}
+
+/// Modification time of `path` at the finest resolution the platform offers.
+/// The seconds-only timestamp in [`Stat`] is too coarse for cache
+/// invalidation: it misses changes made within the same second.
+fn mtime_for_cache(path: &Path) -> Option<u64> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    #[cfg(not(windows))]
+    {
+        Some((metadata.mtime() as u64).wrapping_mul(1_000_000_000) + metadata.mtime_nsec() as u64)
+    }
+    #[cfg(windows)]
+    {
+        Some(metadata.last_write_time())
+    }
+}
+
+/// The cache identity of the directory `stat` (`--cache`). Only meaningful
+/// before the stat's counters were aggregated with those of its children.
+fn dir_cache_key(stat: &Stat) -> Option<cache::DirKey> {
+    let info = stat.inode?;
+    Some(cache::DirKey {
+        dev_id: info.dev_id,
+        file_id: info.file_id,
+        modified: mtime_for_cache(&stat.path)?,
+        size: stat.blocks,
+    })
+}
+
+/// Whether the cached directory entry at `path` still describes the
+/// directory on disk (`--cache`).
+fn verify_cached_dir(path: &Path, key: &cache::DirKey, options: &TraversalOptions) -> bool {
+    Stat::new(path, options)
+        .ok()
+        .and_then(|stat| dir_cache_key(&stat))
+        .is_some_and(|fresh| fresh == *key)
+}
+
+/// Parse the GRANULARITY argument of `--shared-extents`: "extent" compares
+/// whole extents, a positive number aligns ranges to that block size first.
+fn parse_extent_granularity(s: &str) -> UResult<physical_extents::Granularity> {
+    if s == "extent" {
+        return Ok(physical_extents::Granularity::Extent);
+    }
+    match s.parse::<u64>() {
+        Ok(size) if size > 0 => Ok(physical_extents::Granularity::Block(size)),
+        _ => Err(USimpleError::new(
+            1,
+            format!("invalid argument {} for '--shared-extents'", s.quote()),
+        )),
+    }
+}
+
+/// Parse the UNIT argument of `--blocks`: "st_blocks" and "512" print the
+/// allocation in 512-byte units exactly as the OS reports it, "1024" in
+/// kibibytes.
+fn parse_blocks_unit(s: &str) -> UResult<u64> {
+    match s {
+        "512" | "st_blocks" => Ok(512),
+        "1024" => Ok(1024),
+        _ => Err(USimpleError::new(
+            1,
+            format!("invalid argument {} for '--blocks'", s.quote()),
+        )),
     }
 }
 
 // this takes `my_stat` to avoid having to stat files multiple times.
 #[allow(clippy::cognitive_complexity)]
+/// Walk `my_stat`'s tree depth first and send one line per reported entry
+/// to the printing thread.
+///
+/// The order of those lines is a defined contract that any future traversal
+/// implementation (e.g. a threaded walker) must reproduce: operands appear
+/// in command-line order, every child's line is emitted before its parent's,
+/// and the entries of a single directory follow the order the OS returns
+/// them in — or their name order with `--sort=name`, which makes the whole
+/// output reproducible byte for byte.
 fn du(
     mut my_stat: Stat,
     options: &TraversalOptions,
@@ -309,43 +636,138 @@ fn du(
         let read = match fs::read_dir(&my_stat.path) {
             Ok(read) => read,
             Err(e) => {
-                print_tx.send(Err(e.map_err_context(|| {
+                // A directory that vanished between readdir and the descent
+                // gets the same diagnostic as any other unreachable entry;
+                // either way the rest of the tree is still counted.
+                let context = if e.kind() == std::io::ErrorKind::NotFound {
+                    format!("cannot access {}", my_stat.path.quote())
+                } else {
                     format!("cannot read directory {}", my_stat.path.quote())
-                })))?;
+                };
+                print_tx.send(Err(e.map_err_context(|| context)))?;
                 return Ok(my_stat);
             }
         };
 
-        'file_loop: for f in read {
+        let entries: Box<dyn Iterator<Item = std::io::Result<fs::DirEntry>>> = match options.sort {
+            EntrySort::Os => Box::new(read),
+            EntrySort::Name => {
+                let mut entries: Vec<_> = read.collect();
+                entries.sort_by(|a, b| match (a, b) {
+                    (Ok(a), Ok(b)) => a.file_name().cmp(&b.file_name()),
+                    // read errors first, so they are reported regardless
+                    // of where the iteration stops
+                    (Err(_), Ok(_)) => std::cmp::Ordering::Less,
+                    (Ok(_), Err(_)) => std::cmp::Ordering::Greater,
+                    (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+                });
+                Box::new(entries.into_iter())
+            }
+        };
+
+        'file_loop: for f in entries {
             match f {
                 Ok(entry) => {
                     match Stat::new(&entry.path(), options) {
-                        Ok(this_stat) => {
-                            // We have an exclude list
-                            for pattern in &options.excludes {
-                                // Look at all patterns with both short and long paths
-                                // if we have 'du foo' but search to exclude 'foo/bar'
-                                // we need the full path
-                                if pattern.matches(&this_stat.path.to_string_lossy())
-                                    || pattern.matches(&entry.file_name().into_string().unwrap())
-                                {
-                                    // if the directory is ignored, leave early
+                        Ok(mut this_stat) => {
+                            // We have an exclude list. Match against both the
+                            // short and the full path: if we have 'du foo' but
+                            // search to exclude 'foo/bar' we need the full path.
+                            // The empty check keeps the common case free of the
+                            // lossy conversions; the file name is borrowed from
+                            // the path instead of copied out of the entry.
+                            if !options.excludes.is_empty()
+                                && is_excluded(
+                                    &options.excludes,
+                                    &[
+                                        &this_stat.path.to_string_lossy(),
+                                        &this_stat
+                                            .path
+                                            .file_name()
+                                            .map(|name| name.to_string_lossy())
+                                            .unwrap_or_default(),
+                                    ],
+                                )
+                            {
+                                // if the directory is ignored, leave early
+                                if options.verbose {
+                                    println!("{} ignored", &this_stat.path.quote());
+                                }
+                                // Go to the next file
+                                continue 'file_loop;
+                            }
+
+                            #[cfg(feature = "gitignore")]
+                            if let Some(matcher) = &options.gitignore {
+                                if matcher.is_ignored(&this_stat.path, this_stat.is_dir) {
+                                    if options.verbose {
+                                        println!("{} ignored (gitignore)", this_stat.path.quote());
+                                    }
+                                    continue;
+                                }
+                            }
+
+                            if let Some(range) = &options.exclude_size {
+                                if !this_stat.is_dir && range.contains(this_stat.size) {
+                                    if options.verbose {
+                                        println!("{} ignored (size)", this_stat.path.quote());
+                                    }
+                                    continue;
+                                }
+                            }
+
+                            if let Some(dev) = options.same_fs_dev {
+                                if this_stat.inode.is_some_and(|inode| inode.dev_id != dev) {
+                                    if options.verbose {
+                                        println!(
+                                            "{} ignored (different filesystem)",
+                                            this_stat.path.quote()
+                                        );
+                                    }
+                                    continue;
+                                }
+                            }
+
+                            if this_stat.is_dir && !options.bind_mounts.is_empty() {
+                                let canonical = this_stat
+                                    .path
+                                    .canonicalize()
+                                    .unwrap_or_else(|_| this_stat.path.clone());
+                                if options.bind_mounts.contains(&canonical) {
                                     if options.verbose {
-                                        println!("{} ignored", &this_stat.path.quote());
+                                        println!("{} ignored (bind mount)", this_stat.path.quote());
                                     }
-                                    // Go to the next file
-                                    continue 'file_loop;
+                                    continue;
                                 }
                             }
 
                             if let Some(inode) = this_stat.inode {
                                 if seen_inodes.contains(&inode) {
+                                    if let Some(stats) = &options.hard_link_stats {
+                                        let mut stats = stats.borrow_mut();
+                                        stats.skipped += 1;
+                                        stats.saved_bytes += this_stat.size;
+                                        if let Some(first) = stats.first_claim.get(&inode) {
+                                            println!(
+                                                "{} skipped (hard link of {}, inode {})",
+                                                this_stat.path.quote(),
+                                                first.quote(),
+                                                inode.file_id
+                                            );
+                                        }
+                                    }
                                     if options.count_links {
                                         my_stat.inodes += 1;
                                     }
                                     continue;
                                 }
                                 seen_inodes.insert(inode);
+                                if let Some(stats) = &options.hard_link_stats {
+                                    stats
+                                        .borrow_mut()
+                                        .first_claim
+                                        .insert(inode, this_stat.path.clone());
+                                }
                             }
                             if this_stat.is_dir {
                                 if options.one_file_system {
@@ -358,9 +780,57 @@ fn du(
                                     }
                                 }
 
+                                // captured before the recursion below turns
+                                // the counters into subtree aggregates
+                                let cache_key = options
+                                    .dir_cache
+                                    .as_ref()
+                                    .and_then(|_| dir_cache_key(&this_stat));
+                                if let (Some(dir_cache), Some(key)) =
+                                    (&options.dir_cache, &cache_key)
+                                {
+                                    if let Some(totals) = dir_cache.borrow_mut().lookup(
+                                        &this_stat.path,
+                                        key,
+                                        |p, k| verify_cached_dir(p, k, options),
+                                    ) {
+                                        if options.verbose {
+                                            println!(
+                                                "{} unchanged, subtree reused from cache",
+                                                this_stat.path.quote()
+                                            );
+                                        }
+                                        this_stat.size = totals.size;
+                                        this_stat.blocks = totals.blocks;
+                                        this_stat.inodes = totals.inodes;
+                                        my_stat.size += this_stat.size;
+                                        my_stat.blocks += this_stat.blocks;
+                                        my_stat.inodes += this_stat.inodes;
+                                        print_tx.send(Ok(StatPrintInfo {
+                                            stat: this_stat,
+                                            depth: depth + 1,
+                                        }))?;
+                                        continue;
+                                    }
+                                }
+
                                 let this_stat =
                                     du(this_stat, options, depth + 1, seen_inodes, print_tx)?;
 
+                                if let (Some(dir_cache), Some(key)) =
+                                    (&options.dir_cache, cache_key)
+                                {
+                                    dir_cache.borrow_mut().record(
+                                        &this_stat.path,
+                                        key,
+                                        cache::DirTotals {
+                                            size: this_stat.size,
+                                            blocks: this_stat.blocks,
+                                            inodes: this_stat.inodes,
+                                        },
+                                    );
+                                }
+
                                 if !options.separate_dirs {
                                     my_stat.size += this_stat.size;
                                     my_stat.blocks += this_stat.blocks;
@@ -371,6 +841,15 @@ fn du(
                                     depth: depth + 1,
                                 }))?;
                             } else {
+                                #[cfg(feature = "duplicates")]
+                                if let Some(recorder) = &options.duplicates {
+                                    recorder
+                                        .borrow_mut()
+                                        .record(&this_stat.path, this_stat.size);
+                                }
+                                if let Some(seen_extents) = &options.seen_extents {
+                                    account_shared_extents(&mut this_stat, seen_extents);
+                                }
                                 my_stat.size += this_stat.size;
                                 my_stat.blocks += this_stat.blocks;
                                 my_stat.inodes += 1;
@@ -398,16 +877,20 @@ fn du(
 #[derive(Debug)]
 enum DuError {
     InvalidMaxDepthArg(String),
+    InvalidMinDepthArg(String),
     SummarizeDepthConflict(String),
+    SummarizeAllConflict,
     InvalidTimeStyleArg(String),
     InvalidTimeArg,
     InvalidGlob(String),
+    InvalidUnitArg(String),
 }
 
 impl Display for DuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InvalidMaxDepthArg(s) => write!(f, "invalid maximum depth {}", s.quote()),
+            Self::InvalidMinDepthArg(s) => write!(f, "invalid minimum depth {}", s.quote()),
             Self::SummarizeDepthConflict(s) => {
                 write!(
                     f,
@@ -415,6 +898,9 @@ impl Display for DuError {
                     s.maybe_quote()
                 )
             }
+            Self::SummarizeAllConflict => {
+                write!(f, "cannot both summarize and show all entries")
+            }
             Self::InvalidTimeStyleArg(s) => write!(
                 f,
                 "invalid argument {} for 'time style'
@@ -431,6 +917,12 @@ Try '{} --help' for more information.",
                 "'birth' and 'creation' arguments for --time are not supported on this platform.",
             ),
             Self::InvalidGlob(s) => write!(f, "Invalid exclude syntax: {s}"),
+            Self::InvalidUnitArg(s) => write!(
+                f,
+                "invalid argument {} for '--unit'
+Valid arguments are: 'K', 'M', 'G', 'T', 'P', 'E'",
+                s.quote()
+            ),
         }
     }
 }
@@ -441,31 +933,49 @@ impl UError for DuError {
     fn code(&self) -> i32 {
         match self {
             Self::InvalidMaxDepthArg(_)
+            | Self::InvalidMinDepthArg(_)
             | Self::SummarizeDepthConflict(_)
+            | Self::SummarizeAllConflict
             | Self::InvalidTimeStyleArg(_)
             | Self::InvalidTimeArg
-            | Self::InvalidGlob(_) => 1,
+            | Self::InvalidGlob(_)
+            | Self::InvalidUnitArg(_) => 1,
         }
     }
 }
 
-// Read a file and return each line in a vector of String
-fn file_as_vec(filename: impl AsRef<Path>) -> Vec<String> {
-    let file = File::open(filename).expect("no such file");
-    let buf = BufReader::new(file);
+/// Read exclude patterns from `filename`, one per line, with "-" standing for
+/// stdin. Blank lines and lines starting with '#' are ignored, so
+/// .gitignore-style lists can be reused as is.
+fn read_exclude_file(filename: &str) -> UResult<Vec<String>> {
+    let lines: Vec<String> = if filename == "-" {
+        std::io::stdin()
+            .lock()
+            .lines()
+            .collect::<std::io::Result<_>>()
+    } else {
+        let file = File::open(filename)
+            .map_err_context(|| format!("cannot open {} for reading", filename.quote()))?;
+        BufReader::new(file).lines().collect::<std::io::Result<_>>()
+    }
+    .map_err_context(|| format!("error reading {}", filename.quote()))?;
 
-    buf.lines()
-        .map(|l| l.expect("Could not parse line"))
-        .collect()
+    Ok(lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .collect())
 }
 
 // Given the --exclude-from and/or --exclude arguments, returns the globset lists
 // to ignore the files
-fn build_exclude_patterns(matches: &ArgMatches) -> UResult<Vec<Pattern>> {
-    let exclude_from_iterator = matches
+fn build_exclude_patterns(matches: &ArgMatches) -> UResult<Vec<ExcludePattern>> {
+    let mut exclude_from_lines = Vec::new();
+    for filename in matches
         .get_many::<String>(options::EXCLUDE_FROM)
         .unwrap_or_default()
-        .flat_map(file_as_vec);
+    {
+        exclude_from_lines.extend(read_exclude_file(filename)?);
+    }
 
     let excludes_iterator = matches
         .get_many::<String>(options::EXCLUDE)
@@ -473,12 +983,25 @@ fn build_exclude_patterns(matches: &ArgMatches) -> UResult<Vec<Pattern>> {
         .cloned();
 
     let mut exclude_patterns = Vec::new();
-    for f in excludes_iterator.chain(exclude_from_iterator) {
+    for (f, from_file) in excludes_iterator
+        .map(|f| (f, false))
+        .chain(exclude_from_lines.into_iter().map(|f| (f, true)))
+    {
+        // only lines from a file can be negated; a literal --exclude '!x'
+        // keeps its GNU meaning of a pattern starting with '!'
+        let (pattern_str, negated) = match f.strip_prefix('!') {
+            Some(rest) if from_file => (rest, true),
+            _ => (f.as_str(), false),
+        };
         if matches.get_flag(options::VERBOSE) {
-            println!("adding {:?} to the exclude list ", &f);
+            if negated {
+                println!("adding {:?} to the re-include list ", pattern_str);
+            } else {
+                println!("adding {:?} to the exclude list ", pattern_str);
+            }
         }
-        match parse_glob::from_str(&f) {
-            Ok(glob) => exclude_patterns.push(glob),
+        match parse_glob::from_str(pattern_str) {
+            Ok(pattern) => exclude_patterns.push(ExcludePattern::new(pattern, negated)),
             Err(err) => return Err(DuError::InvalidGlob(err.to_string()).into()),
         }
     }
@@ -490,8 +1013,74 @@ struct StatPrintInfo {
     depth: usize,
 }
 
+/// Where a report goes: stdout, or a temporary file that replaces the
+/// `--output-file` destination atomically once the report is complete, so a
+/// concurrent reader never sees a partial report.
+enum ReportOutput {
+    Stdout(std::io::Stdout),
+    File {
+        writer: BufWriter<File>,
+        temp_path: PathBuf,
+        final_path: PathBuf,
+    },
+}
+
+impl ReportOutput {
+    fn new(output_file: Option<&Path>) -> UResult<Self> {
+        match output_file {
+            None => Ok(Self::Stdout(std::io::stdout())),
+            Some(path) => {
+                // a sibling of the destination, so the final rename cannot
+                // cross a filesystem boundary
+                let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+                temp_name.push(format!(".{}.tmp", std::process::id()));
+                let temp_path = path.with_file_name(temp_name);
+                let file = File::create(&temp_path)
+                    .map_err_context(|| format!("cannot create {}", temp_path.quote()))?;
+                Ok(Self::File {
+                    writer: BufWriter::new(file),
+                    temp_path,
+                    final_path: path.to_path_buf(),
+                })
+            }
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Stdout(out) => out,
+            Self::File { writer, .. } => writer,
+        }
+    }
+
+    /// Publish the finished report: flush the buffer and move the temporary
+    /// file over the destination in a single rename.
+    fn finish(self) -> UResult<()> {
+        match self {
+            Self::Stdout(_) => Ok(()),
+            Self::File {
+                mut writer,
+                temp_path,
+                final_path,
+            } => {
+                writer
+                    .flush()
+                    .map_err_context(|| format!("cannot write {}", temp_path.quote()))?;
+                drop(writer);
+                fs::rename(&temp_path, &final_path)
+                    .map_err_context(|| format!("cannot move report to {}", final_path.quote()))
+            }
+        }
+    }
+}
+
 impl StatPrinter {
     fn choose_size(&self, stat: &Stat) -> u64 {
+        if self.blocks.is_some() {
+            // raw allocation as the OS reports it; converted to the requested
+            // unit in convert_size
+            return stat.blocks * 512;
+        }
         if self.inodes {
             stat.inodes
         } else if self.apparent_size {
@@ -503,7 +1092,17 @@ impl StatPrinter {
         }
     }
 
-    fn print_stats(&self, rx: &mpsc::Receiver<UResult<StatPrintInfo>>) -> UResult<()> {
+    /// Returns the report destination unfinished, so the caller can append
+    /// trailing sections (like the `--duplicates` report) before the
+    /// `--output-file` rename publishes it.
+    fn print_stats(&self, rx: &mpsc::Receiver<UResult<StatPrintInfo>>) -> UResult<ReportOutput> {
+        let mut output = ReportOutput::new(self.output_file.as_deref())?;
+
+        if self.by_extension {
+            self.print_extension_stats(output.writer(), rx)?;
+            return Ok(output);
+        }
+
         let mut grand_total = 0;
         loop {
             let received = rx.recv();
@@ -520,12 +1119,12 @@ impl StatPrinter {
                         if !self
                             .threshold
                             .map_or(false, |threshold| threshold.should_exclude(size))
+                            && self.output_mode.includes_depth(stat_info.depth)
                             && self
-                                .max_depth
-                                .map_or(true, |max_depth| stat_info.depth <= max_depth)
-                            && (!self.summarize || stat_info.depth == 0)
+                                .min_depth
+                                .map_or(true, |min_depth| stat_info.depth >= min_depth)
                         {
-                            self.print_stat(&stat_info.stat, size)?;
+                            self.print_stat(output.writer(), &stat_info.stat, size)?;
                         }
                     }
                     Err(e) => show!(e),
@@ -535,14 +1134,63 @@ impl StatPrinter {
         }
 
         if self.total {
-            print!("{}\ttotal", self.convert_size(grand_total));
-            print!("{}", self.line_ending);
+            write!(output.writer(), "{}\ttotal", self.convert_size(grand_total))?;
+            write!(output.writer(), "{}", self.line_ending)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Aggregate the received file stats per file extension and print a table of
+    /// (size, file count, extension) lines ranked by the aggregated size.
+    fn print_extension_stats(
+        &self,
+        out: &mut dyn Write,
+        rx: &mpsc::Receiver<UResult<StatPrintInfo>>,
+    ) -> UResult<()> {
+        let mut totals: HashMap<String, (u64, u64)> = HashMap::new();
+        loop {
+            match rx.recv() {
+                Ok(Ok(stat_info)) => {
+                    // directories have no meaningful extension, only account for files
+                    if stat_info.stat.is_dir {
+                        continue;
+                    }
+                    let extension = stat_info.stat.path.extension().map_or_else(
+                        || String::from("<none>"),
+                        |e| e.to_string_lossy().to_string(),
+                    );
+                    let entry = totals.entry(extension).or_insert((0, 0));
+                    entry.0 += self.choose_size(&stat_info.stat);
+                    entry.1 += 1;
+                }
+                Ok(Err(e)) => show!(e),
+                Err(_) => break,
+            }
+        }
+
+        let mut totals: Vec<(String, (u64, u64))> = totals.into_iter().collect();
+        // rank by size, tie-break on the extension name for a stable output
+        totals.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)));
+
+        for (extension, (size, count)) in totals {
+            write!(
+                out,
+                "{}\t{}\t{}{}",
+                self.convert_size(size),
+                count,
+                extension,
+                self.line_ending
+            )?;
         }
 
         Ok(())
     }
 
     fn convert_size(&self, size: u64) -> String {
+        if let Some(unit) = self.blocks {
+            return div_ceil(size, unit).to_string();
+        }
         if self.inodes {
             return size.to_string();
         }
@@ -555,22 +1203,63 @@ impl StatPrinter {
                 size,
                 uucore::format::human::SizeFormat::Binary,
             ),
-            SizeFormat::BlockSize(block_size) => div_ceil(size, block_size).to_string(),
+            SizeFormat::FixedUnit(unit) => uucore::format::human::human_readable_fixed(size, unit),
+            SizeFormat::BlockSize {
+                size: block_size,
+                thousands_separators,
+            } => {
+                let scaled = div_ceil(size, block_size);
+                if thousands_separators {
+                    separate_thousands(scaled)
+                } else {
+                    scaled.to_string()
+                }
+            }
         }
     }
 
-    fn print_stat(&self, stat: &Stat, size: u64) -> UResult<()> {
+    /// Total number of inodes of the filesystem containing `stat`, cached
+    /// per device id so statvfs is queried once per filesystem.
+    fn fs_total_inodes(&self, stat: &Stat) -> Option<u64> {
+        let dev_id = stat.inode?.dev_id;
+        *self
+            .fs_inodes_cache
+            .borrow_mut()
+            .entry(dev_id)
+            .or_insert_with(|| filesystem_total_inodes(&stat.path))
+    }
+
+    fn print_stat(&self, out: &mut dyn Write, stat: &Stat, size: u64) -> UResult<()> {
         if let Some(time) = self.time {
             let secs = get_time_secs(time, stat)?;
             let tm = DateTime::<Local>::from(UNIX_EPOCH + Duration::from_secs(secs));
             let time_str = tm.format(&self.time_format).to_string();
-            print!("{}\t{}\t", self.convert_size(size), time_str);
+            write!(out, "{}\t{}\t", self.convert_size(size), time_str)?;
         } else {
-            print!("{}\t", self.convert_size(size));
+            write!(out, "{}\t", self.convert_size(size))?;
         }
 
-        print_verbatim(&stat.path).unwrap();
-        print!("{}", self.line_ending);
+        if self.inodes_percent {
+            match self.fs_total_inodes(stat).filter(|total| *total > 0) {
+                Some(total) => write!(out, "{:.1}%\t", size as f64 * 100.0 / total as f64)?,
+                None => write!(out, "-\t")?,
+            }
+        }
+
+        if self.shared_percent {
+            // directories (and files without extent data) have no ratio
+            match stat.shared_ratio {
+                Some(ratio) => write!(out, "{:.1}%\t", ratio * 100.0)?,
+                None => write!(out, "-\t")?,
+            }
+        }
+
+        // like print_verbatim: pass the path through byte for byte where possible
+        #[cfg(unix)]
+        out.write_all(stat.path.as_os_str().as_bytes())?;
+        #[cfg(not(unix))]
+        write!(out, "{}", stat.path.display())?;
+        write!(out, "{}", self.line_ending)?;
 
         Ok(())
     }
@@ -583,8 +1272,9 @@ pub fn div_ceil(a: u64, b: u64) -> u64 {
     (a + b - 1) / b
 }
 
-// Read file paths from the specified file, separated by null characters
-fn read_files_from(file_name: &str) -> Result<Vec<PathBuf>, std::io::Error> {
+// Open the file (or stdin for "-") a `--files0-from` list is read from,
+// rejecting directories and missing files up front.
+fn open_files0_source(file_name: &str) -> Result<Box<dyn BufRead>, std::io::Error> {
     let reader: Box<dyn BufRead> = if file_name == "-" {
         // Read from standard input
         Box::new(BufReader::new(std::io::stdin()))
@@ -614,24 +1304,109 @@ fn read_files_from(file_name: &str) -> Result<Vec<PathBuf>, std::io::Error> {
         }
     };
 
-    let mut paths = Vec::new();
+    Ok(reader)
+}
 
-    for (i, line) in reader.split(b'\0').enumerate() {
-        let path = line?;
+/// The operands to scan: either the FILE arguments from the command line or
+/// the entries of a `--files0-from` list. The list is streamed entry by
+/// entry while scanning, so it may hold millions of names; only the set of
+/// names already seen is kept, for deduplication.
+enum InputPaths {
+    Args(Vec<PathBuf>),
+    Files0From(String),
+}
 
-        if path.is_empty() {
-            let line_number = i + 1;
-            show_error!("{file_name}:{line_number}: invalid zero-length file name");
-            set_exit_code(1);
-        } else {
-            let p = PathBuf::from(String::from_utf8_lossy(&path).to_string());
-            if !paths.contains(&p) {
-                paths.push(p);
+impl InputPaths {
+    /// Iterate over the operands, opening a `--files0-from` list anew.
+    fn iter(&self) -> UResult<Box<dyn Iterator<Item = PathBuf> + '_>> {
+        match self {
+            Self::Args(paths) => Ok(Box::new(paths.iter().cloned())),
+            Self::Files0From(file_name) => Ok(Box::new(Files0Iter {
+                source: file_name,
+                reader: open_files0_source(file_name)?,
+                line_number: 0,
+                seen: HashSet::new(),
+            })),
+        }
+    }
+}
+
+/// Streaming iterator over the validated, deduplicated entries of a
+/// `--files0-from` list. A read error in the middle of the list is reported
+/// and ends the iteration, it does not take back what was already produced.
+struct Files0Iter<'a> {
+    source: &'a str,
+    reader: Box<dyn BufRead>,
+    line_number: usize,
+    seen: HashSet<PathBuf>,
+}
+
+impl Iterator for Files0Iter<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            let mut entry = Vec::new();
+            match self.reader.read_until(b'\0', &mut entry) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if entry.last() == Some(&b'\0') {
+                        entry.pop();
+                    }
+                    self.line_number += 1;
+                    if let Some(path) = validate_files0_entry(self.source, self.line_number, &entry)
+                    {
+                        if self.seen.insert(path.clone()) {
+                            return Some(path);
+                        }
+                    }
+                }
+                Err(e) => {
+                    show_error!("{}: read error: {e}", self.source.maybe_quote());
+                    set_exit_code(1);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Validate and normalize one entry of a `--files0-from` list, read from the
+/// named file or from `-` (stdin).
+///
+/// Redundant trailing separators are collapsed into a single one, and an
+/// entry that keeps a trailing separator while naming an existing
+/// non-directory is reported right away, with the same `SOURCE:LINE:` prefix
+/// the zero-length check uses, instead of as a stat failure in the middle of
+/// the scan. Returns `None` (with the exit code set) for rejected entries.
+fn validate_files0_entry(source: &str, line_number: usize, entry: &[u8]) -> Option<PathBuf> {
+    if entry.is_empty() {
+        show_error!("{source}:{line_number}: invalid zero-length file name");
+        set_exit_code(1);
+        return None;
+    }
+
+    let mut name = String::from_utf8_lossy(entry).to_string();
+    // "dir///" means the same as "dir/"
+    while name.ends_with("//") {
+        name.pop();
+    }
+
+    if let Some(stripped) = name.strip_suffix('/') {
+        if !stripped.is_empty() {
+            let path = Path::new(stripped);
+            if path.exists() && !path.is_dir() {
+                show_error!(
+                    "{source}:{line_number}: cannot access {}: Not a directory",
+                    name.quote()
+                );
+                set_exit_code(1);
+                return None;
             }
         }
     }
 
-    Ok(paths)
+    Some(PathBuf::from(name))
 }
 
 #[uucore::main]
@@ -639,8 +1414,22 @@ fn read_files_from(file_name: &str) -> Result<Vec<PathBuf>, std::io::Error> {
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
+    if let Some(shell) = matches.get_one::<clap_complete::Shell>(options::GENERATE_COMPLETION) {
+        clap_complete::generate(
+            *shell,
+            &mut uu_app(),
+            uucore::util_name(),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
     let summarize = matches.get_flag(options::SUMMARIZE);
 
+    if summarize && matches.get_flag(options::ALL) {
+        return Err(DuError::SummarizeAllConflict.into());
+    }
+
     let max_depth = parse_depth(
         matches
             .get_one::<String>(options::MAX_DEPTH)
@@ -648,7 +1437,15 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         summarize,
     )?;
 
-    let files = if let Some(file_from) = matches.get_one::<String>(options::FILES0_FROM) {
+    let min_depth = matches
+        .get_one::<String>(options::MIN_DEPTH)
+        .map(|s| {
+            s.parse::<usize>()
+                .map_err(|_| DuError::InvalidMinDepthArg(s.clone()))
+        })
+        .transpose()?;
+
+    let mut input = if let Some(file_from) = matches.get_one::<String>(options::FILES0_FROM) {
         if file_from == "-" && matches.get_one::<String>(options::FILE).is_some() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::Other,
@@ -660,18 +1457,29 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             .into());
         }
 
-        read_files_from(file_from)?
+        // reject directories and unreadable list files before any scanning
+        open_files0_source(file_from)?;
+        InputPaths::Files0From(file_from.clone())
     } else {
-        match matches.get_one::<String>(options::FILE) {
+        InputPaths::Args(match matches.get_one::<String>(options::FILE) {
             Some(_) => matches
                 .get_many::<String>(options::FILE)
                 .unwrap()
                 .map(PathBuf::from)
                 .collect(),
             None => vec![PathBuf::from(".")],
-        }
+        })
     };
 
+    // --dereference-args and the --watch rescans need the whole operand list
+    // in memory; everything else streams a --files0-from list while scanning
+    if matches.get_flag(options::DEREFERENCE_ARGS) || matches.contains_id(options::WATCH) {
+        if let InputPaths::Files0From(_) = &input {
+            let paths: Vec<PathBuf> = input.iter()?.collect();
+            input = InputPaths::Args(paths);
+        }
+    }
+
     let time = matches.contains_id(options::TIME).then(|| {
         match matches.get_one::<String>(options::TIME).map(AsRef::as_ref) {
             None | Some("ctime" | "status") => Time::Modified,
@@ -681,39 +1489,125 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         }
     });
 
-    let size_format = if matches.get_flag(options::HUMAN_READABLE) {
+    let size_format = if let Some(unit) = matches.get_one::<String>(options::UNIT) {
+        SizeFormat::FixedUnit(
+            unit.parse()
+                .map_err(|()| DuError::InvalidUnitArg(unit.clone()))?,
+        )
+    } else if matches.get_flag(options::HUMAN_READABLE) {
         SizeFormat::HumanBinary
     } else if matches.get_flag(options::SI) {
         SizeFormat::HumanDecimal
     } else if matches.get_flag(options::BYTES) {
-        SizeFormat::BlockSize(1)
+        SizeFormat::BlockSize {
+            size: 1,
+            thousands_separators: false,
+        }
     } else if matches.get_flag(options::BLOCK_SIZE_1K) {
-        SizeFormat::BlockSize(1024)
+        SizeFormat::BlockSize {
+            size: 1024,
+            thousands_separators: false,
+        }
     } else if matches.get_flag(options::BLOCK_SIZE_1M) {
-        SizeFormat::BlockSize(1024 * 1024)
+        SizeFormat::BlockSize {
+            size: 1024 * 1024,
+            thousands_separators: false,
+        }
     } else {
-        SizeFormat::BlockSize(read_block_size(
+        match read_block_size(
             matches
                 .get_one::<String>(options::BLOCK_SIZE)
                 .map(AsRef::as_ref),
-        )?)
+        )? {
+            BlockSize::HumanBinary => SizeFormat::HumanBinary,
+            BlockSize::HumanDecimal => SizeFormat::HumanDecimal,
+            BlockSize::Bytes {
+                size,
+                thousands_separators,
+            } => SizeFormat::BlockSize {
+                size,
+                thousands_separators,
+            },
+        }
+    };
+
+    let by_extension = matches.get_flag(options::BY_EXTENSION);
+
+    let same_fs_dev = matches
+        .get_one::<String>(options::SAME_FS_AS)
+        .map(|path| device_of(Path::new(path)))
+        .transpose()?;
+
+    let bind_mounts = if matches.get_flag(options::SKIP_BIND_MOUNTS) {
+        #[cfg(target_os = "linux")]
+        {
+            read_bind_mount_targets()
+                .map_err_context(|| "cannot read /proc/self/mountinfo".to_string())?
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            show_warning!("--skip-bind-mounts is not supported on this platform");
+            Vec::new()
+        }
+    } else {
+        Vec::new()
     };
 
     let traversal_options = TraversalOptions {
-        all: matches.get_flag(options::ALL),
+        // the per-extension aggregation needs to see every file, not just directories
+        all: matches.get_flag(options::ALL) || by_extension,
         separate_dirs: matches.get_flag(options::SEPARATE_DIRS),
         one_file_system: matches.get_flag(options::ONE_FILE_SYSTEM),
+        same_fs_dev,
+        bind_mounts,
         dereference: if matches.get_flag(options::DEREFERENCE) {
             Deref::All
         } else if matches.get_flag(options::DEREFERENCE_ARGS) {
             // We don't care about the cost of cloning as it is rarely used
-            Deref::Args(files.clone())
+            match &input {
+                InputPaths::Args(paths) => Deref::Args(paths.clone()),
+                InputPaths::Files0From(_) => unreachable!("materialized above"),
+            }
         } else {
             Deref::None
         },
         count_links: matches.get_flag(options::COUNT_LINKS),
         verbose: matches.get_flag(options::VERBOSE),
+        hard_link_stats: matches
+            .get_flag(options::VERBOSE)
+            .then(|| RefCell::new(HardLinkStats::default())),
+        sort: match matches.get_one::<String>(options::SORT).map(|s| s.as_str()) {
+            Some("name") => EntrySort::Name,
+            _ => EntrySort::Os,
+        },
         excludes: build_exclude_patterns(&matches)?,
+        exclude_size: matches
+            .get_one::<String>(options::EXCLUDE_SIZE)
+            .map(|s| {
+                SizeRange::from_str(s).map_err(|e| {
+                    USimpleError::new(1, format_error_message(&e, s, options::EXCLUDE_SIZE))
+                })
+            })
+            .transpose()?,
+        #[cfg(feature = "gitignore")]
+        gitignore: matches
+            .get_flag(options::RESPECT_GITIGNORE)
+            .then(gitignore::Matcher::new),
+        #[cfg(feature = "duplicates")]
+        duplicates: matches
+            .get_flag(options::DUPLICATES)
+            .then(duplicates::Recorder::new)
+            .map(RefCell::new),
+        seen_extents: matches
+            .get_one::<String>(options::SHARED_EXTENTS)
+            .map(|granularity| parse_extent_granularity(granularity))
+            .transpose()?
+            .map(physical_extents::SeenPhysicalExtents::new)
+            .map(RefCell::new),
+        dir_cache: matches
+            .get_one::<String>(options::CACHE)
+            .map(|file| cache::DirCache::load(Path::new(file)))
+            .map(RefCell::new),
     };
 
     let time_format = if time.is_some() {
@@ -723,11 +1617,18 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     };
 
     let stat_printer = StatPrinter {
-        max_depth,
+        output_mode: if summarize {
+            OutputMode::Summarize
+        } else {
+            OutputMode::Tree(max_depth)
+        },
+        min_depth,
         size_format,
-        summarize,
         total: matches.get_flag(options::TOTAL),
         inodes: matches.get_flag(options::INODES),
+        inodes_percent: matches.get_flag(options::PERCENT),
+        fs_inodes_cache: RefCell::new(HashMap::new()),
+        shared_percent: matches.get_flag(options::SHARED_PERCENT),
         threshold: matches
             .get_one::<String>(options::THRESHOLD)
             .map(|s| {
@@ -737,9 +1638,17 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             })
             .transpose()?,
         apparent_size: matches.get_flag(options::APPARENT_SIZE) || matches.get_flag(options::BYTES),
+        blocks: matches
+            .get_one::<String>(options::BLOCKS)
+            .map(|unit| parse_blocks_unit(unit))
+            .transpose()?,
         time,
         time_format,
         line_ending: LineEnding::from_zero_flag(matches.get_flag(options::NULL)),
+        by_extension,
+        output_file: matches
+            .get_one::<String>(options::OUTPUT_FILE)
+            .map(PathBuf::from),
     };
 
     if stat_printer.inodes
@@ -748,16 +1657,36 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         show_warning!("options --apparent-size and -b are ineffective with --inodes");
     }
 
-    // Use separate thread to print output, so we can print finished results while computation is still running
-    let (print_tx, rx) = mpsc::channel::<UResult<StatPrintInfo>>();
-    let printing_thread = thread::spawn(move || stat_printer.print_stats(&rx));
+    let watch_interval = match matches.get_one::<String>(options::WATCH) {
+        Some(s) => match s.parse::<f64>() {
+            Ok(secs) if secs > 0.0 && secs.is_finite() => Some(Duration::from_secs_f64(secs)),
+            _ => {
+                return Err(USimpleError::new(
+                    1,
+                    format!("invalid interval {} for '--watch'", s.quote()),
+                ))
+            }
+        },
+        None => None,
+    };
+
+    loop {
+        // Use separate thread to print output, so we can print finished results while computation is still running
+        let (print_tx, rx) = mpsc::channel::<UResult<StatPrintInfo>>();
+        let printer = stat_printer.clone();
+        let printing_thread = thread::spawn(move || printer.print_stats(&rx));
+
+        'loop_file: for path in input.iter()? {
+            let path = &path;
+            #[cfg(feature = "gitignore")]
+            if let Some(matcher) = &traversal_options.gitignore {
+                matcher.set_root(path);
+            }
 
-    'loop_file: for path in files {
-        // Skip if we don't want to ignore anything
-        if !&traversal_options.excludes.is_empty() {
-            let path_string = path.to_string_lossy();
-            for pattern in &traversal_options.excludes {
-                if pattern.matches(&path_string) {
+            // Skip if we don't want to ignore anything
+            if !&traversal_options.excludes.is_empty() {
+                let path_string = path.to_string_lossy();
+                if is_excluded(&traversal_options.excludes, &[&path_string]) {
                     // if the directory is ignored, leave early
                     if traversal_options.verbose {
                         println!("{} ignored", path_string.quote());
@@ -765,23 +1694,85 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
                     continue 'loop_file;
                 }
             }
-        }
 
-        // Check existence of path provided in argument
-        if let Ok(stat) = Stat::new(&path, &traversal_options) {
-            // Kick off the computation of disk usage from the initial path
-            let mut seen_inodes: HashSet<FileInfo> = HashSet::new();
-            if let Some(inode) = stat.inode {
-                seen_inodes.insert(inode);
-            }
-            let stat = du(stat, &traversal_options, 0, &mut seen_inodes, &print_tx)
-                .map_err(|e| USimpleError::new(1, e.to_string()))?;
+            // Check existence of path provided in argument
+            if let Ok(mut stat) = Stat::new(path, &traversal_options) {
+                if let (Some(dev), Some(inode)) = (traversal_options.same_fs_dev, stat.inode) {
+                    if inode.dev_id != dev {
+                        show_warning!("skipping {}: on a different filesystem", path.quote());
+                        continue 'loop_file;
+                    }
+                }
+                #[cfg(feature = "duplicates")]
+                if let Some(recorder) = &traversal_options.duplicates {
+                    if !stat.is_dir {
+                        recorder.borrow_mut().record(&stat.path, stat.size);
+                    }
+                }
+                if let Some(seen_extents) = &traversal_options.seen_extents {
+                    if !stat.is_dir {
+                        account_shared_extents(&mut stat, seen_extents);
+                    }
+                }
 
-            print_tx
-                .send(Ok(StatPrintInfo { stat, depth: 0 }))
-                .map_err(|e| USimpleError::new(1, e.to_string()))?;
-        } else {
-            print_tx
+                // Kick off the computation of disk usage from the initial path
+                let mut seen_inodes: HashSet<FileInfo> = HashSet::new();
+                if let Some(inode) = stat.inode {
+                    seen_inodes.insert(inode);
+                    if let Some(stats) = &traversal_options.hard_link_stats {
+                        stats
+                            .borrow_mut()
+                            .first_claim
+                            .insert(inode, stat.path.clone());
+                    }
+                }
+                let cache_key = match &traversal_options.dir_cache {
+                    Some(_) if stat.is_dir => dir_cache_key(&stat),
+                    _ => None,
+                };
+                let cache_hit = match (&traversal_options.dir_cache, &cache_key) {
+                    (Some(dir_cache), Some(key)) => {
+                        dir_cache.borrow_mut().lookup(&stat.path, key, |p, k| {
+                            verify_cached_dir(p, k, &traversal_options)
+                        })
+                    }
+                    _ => None,
+                };
+                let stat = if let Some(totals) = cache_hit {
+                    if traversal_options.verbose {
+                        println!("{} unchanged, subtree reused from cache", stat.path.quote());
+                    }
+                    stat.size = totals.size;
+                    stat.blocks = totals.blocks;
+                    stat.inodes = totals.inodes;
+                    stat
+                } else {
+                    // A failed send means the printing thread is gone; stop
+                    // scanning and let the join below surface its error
+                    // instead of the meaningless closed-channel one.
+                    let Ok(stat) = du(stat, &traversal_options, 0, &mut seen_inodes, &print_tx)
+                    else {
+                        break 'loop_file;
+                    };
+                    if let (Some(dir_cache), Some(key)) = (&traversal_options.dir_cache, cache_key)
+                    {
+                        dir_cache.borrow_mut().record(
+                            &stat.path,
+                            key,
+                            cache::DirTotals {
+                                size: stat.size,
+                                blocks: stat.blocks,
+                                inodes: stat.inodes,
+                            },
+                        );
+                    }
+                    stat
+                };
+
+                if print_tx.send(Ok(StatPrintInfo { stat, depth: 0 })).is_err() {
+                    break 'loop_file;
+                }
+            } else if print_tx
                 .send(Err(USimpleError::new(
                     1,
                     format!(
@@ -789,15 +1780,53 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
                         path.to_string_lossy().quote()
                     ),
                 )))
-                .map_err(|e| USimpleError::new(1, e.to_string()))?;
+                .is_err()
+            {
+                break 'loop_file;
+            }
         }
-    }
 
-    drop(print_tx);
+        drop(print_tx);
 
-    printing_thread
-        .join()
-        .map_err(|_| USimpleError::new(1, "Printing thread panicked."))??;
+        #[cfg_attr(not(feature = "duplicates"), allow(unused_mut))]
+        let mut report_output = printing_thread
+            .join()
+            .map_err(|_| USimpleError::new(1, "Printing thread panicked."))??;
+
+        #[cfg(feature = "duplicates")]
+        if let Some(recorder) = &traversal_options.duplicates {
+            recorder.borrow_mut().report(report_output.writer())?;
+        }
+
+        report_output.finish()?;
+
+        if let Some(stats) = &traversal_options.hard_link_stats {
+            let stats = stats.borrow();
+            if stats.skipped > 0 {
+                // a diagnostic like the other --verbose messages, so it never
+                // ends up inside an --output-file report
+                eprintln!(
+                    "hard links: {} skipped, {} bytes saved",
+                    stats.skipped, stats.saved_bytes
+                );
+            }
+        }
+
+        if let Some(dir_cache) = &traversal_options.dir_cache {
+            if let Err(e) = dir_cache.borrow().save() {
+                show_warning!("cannot write cache file: {e}");
+            }
+        }
+
+        let Some(interval) = watch_interval else {
+            break;
+        };
+        thread::sleep(interval);
+        // like watch(1), start each refresh on a cleared screen when on a terminal
+        if stat_printer.output_file.is_none() && std::io::stdout().is_terminal() {
+            print!("\x1b[2J\x1b[H");
+        }
+    }
 
     Ok(())
 }
@@ -816,7 +1845,11 @@ fn parse_time_style(s: Option<&str>) -> UResult<&str> {
             "full-iso" => Ok("%Y-%m-%d %H:%M:%S.%f %z"),
             "long-iso" => Ok("%Y-%m-%d %H:%M"),
             "iso" => Ok("%Y-%m-%d"),
-            _ => Err(DuError::InvalidTimeStyleArg(s.into()).into()),
+            _ => match s.strip_prefix('+') {
+                // a custom strftime string, interpreted like date(1) does
+                Some(format) => Ok(format),
+                None => Err(DuError::InvalidTimeStyleArg(s.into()).into()),
+            },
         },
         None => Ok("%Y-%m-%d %H:%M"),
     }
@@ -832,7 +1865,7 @@ fn parse_depth(max_depth_str: Option<&str>, summarize: bool) -> UResult<Option<u
 }
 
 pub fn uu_app() -> Command {
-    Command::new(uucore::util_name())
+    let cmd = Command::new(uucore::util_name())
         .version(crate_version!())
         .about(ABOUT)
         .after_help(AFTER_HELP)
@@ -872,6 +1905,28 @@ pub fn uu_app() -> Command {
                     E.g., '-BM' prints sizes in units of 1,048,576 bytes. See SIZE format below."
                 )
         )
+        .arg(
+            Arg::new(options::BLOCKS)
+                .long(options::BLOCKS)
+                .value_name("UNIT")
+                .help(
+                    "print raw allocation counts as reported by the OS, bypassing all \
+                    size formatting and BLOCK_SIZE environment handling; UNIT is 512, \
+                    1024 or 'st_blocks', useful for debugging discrepancies between \
+                    du and df (a uutils extension)"
+                )
+                .conflicts_with_all([
+                    options::APPARENT_SIZE,
+                    options::BYTES,
+                    options::INODES,
+                    options::HUMAN_READABLE,
+                    options::SI,
+                    options::BLOCK_SIZE,
+                    options::BLOCK_SIZE_1K,
+                    options::BLOCK_SIZE_1M,
+                    options::UNIT,
+                ])
+        )
         .arg(
             Arg::new(options::BYTES)
                 .short('b')
@@ -897,6 +1952,16 @@ pub fn uu_app() -> Command {
                     line argument;  --max-depth=0 is the same as --summarize"
                 )
         )
+        .arg(
+            Arg::new(options::MIN_DEPTH)
+                .long("min-depth")
+                .value_name("N")
+                .help(
+                    "suppress printing of entries fewer than N levels below the \
+                    command line argument; sizes are still counted, mirroring \
+                    find's -mindepth (a uutils extension)"
+                )
+        )
         .arg(
             Arg::new(options::HUMAN_READABLE)
                 .long("human-readable")
@@ -912,6 +1977,17 @@ pub fn uu_app() -> Command {
                 )
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new(options::PERCENT)
+                .long(options::PERCENT)
+                .requires(options::INODES)
+                .help(
+                    "with --inodes, also print each entry's inode usage as a \
+                    percentage of the containing filesystem's total inodes \
+                    (a uutils extension)"
+                )
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             Arg::new(options::BLOCK_SIZE_1K)
                 .short('k')
@@ -981,6 +2057,16 @@ pub fn uu_app() -> Command {
                 .help("like -h, but use powers of 1000 not 1024")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new(options::UNIT)
+                .long(options::UNIT)
+                .value_name("UNIT")
+                .help(
+                    "print all sizes in the single unit UNIT (K, M, G, T, P or E; \
+                    powers of 1024) with one decimal place, so columns of sizes \
+                    share a scale"
+                )
+        )
         .arg(
             Arg::new(options::ONE_FILE_SYSTEM)
                 .short('x')
@@ -988,6 +2074,26 @@ pub fn uu_app() -> Command {
                 .help("skip directories on different file systems")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new(options::SAME_FS_AS)
+                .long(options::SAME_FS_AS)
+                .value_name("PATH")
+                .value_hint(clap::ValueHint::AnyPath)
+                .help(
+                    "skip entries on a different file system than the one PATH \
+                    resides on (option not present in GNU/Coreutils)"
+                )
+        )
+        .arg(
+            Arg::new(options::SKIP_BIND_MOUNTS)
+                .long(options::SKIP_BIND_MOUNTS)
+                .help(
+                    "skip directories that are bind mounts of content reachable \
+                    elsewhere, to avoid counting it twice; Linux only \
+                    (option not present in GNU/Coreutils)"
+                )
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             Arg::new(options::THRESHOLD)
                 .short('t')
@@ -1005,6 +2111,58 @@ pub fn uu_app() -> Command {
                 .help("verbose mode (option not present in GNU/Coreutils)")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new(options::SORT)
+                .long(options::SORT)
+                .value_name("WORD")
+                .value_parser(["none", "name"])
+                .help(
+                    "order the entries of each directory as the OS returns them \
+                    ('none', the default) or by file name ('name'), which makes \
+                    the output reproducible (a uutils extension)",
+                )
+        )
+        .arg(
+            Arg::new(options::WATCH)
+                .long(options::WATCH)
+                .value_name("INTERVAL")
+                .num_args(0..=1)
+                .default_missing_value("1")
+                .require_equals(true)
+                .help(
+                    "repeat the scan every INTERVAL seconds (default 1) and reprint \
+                    the results, clearing the screen between refreshes when printing \
+                    to a terminal (option not present in GNU/Coreutils)"
+                )
+        )
+        .arg(
+            Arg::new(options::OUTPUT_FILE)
+                .long(options::OUTPUT_FILE)
+                .value_name("FILE")
+                .value_hint(clap::ValueHint::FilePath)
+                .help(
+                    "write the report to FILE instead of standard output; the \
+                    report is buffered into a temporary sibling file and moved \
+                    into place once complete, so FILE never holds a partial \
+                    report (a uutils extension)"
+                )
+        )
+        .arg(
+            Arg::new(options::GENERATE_COMPLETION)
+                .long(options::GENERATE_COMPLETION)
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(clap_complete::Shell))
+                .help("print a completion script for SHELL to stdout and exit (a uutils extension)")
+        )
+        .arg(
+            Arg::new(options::BY_EXTENSION)
+                .long(options::BY_EXTENSION)
+                .help(
+                    "aggregate sizes by file extension and print a ranked table of \
+                    size, file count and extension (option not present in GNU/Coreutils)"
+                )
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             Arg::new(options::EXCLUDE)
                 .long(options::EXCLUDE)
@@ -1012,13 +2170,27 @@ pub fn uu_app() -> Command {
                 .help("exclude files that match PATTERN")
                 .action(ArgAction::Append)
         )
+        .arg(
+            Arg::new(options::EXCLUDE_SIZE)
+                .long(options::EXCLUDE_SIZE)
+                .value_name("RANGE")
+                .help(
+                    "do not count files whose apparent size lies in RANGE, given \
+                    as MIN..MAX with either bound optional, e.g. '1G..', '..4K' \
+                    or '1M..1G'; directories are still traversed (a uutils extension)"
+                )
+        )
         .arg(
             Arg::new(options::EXCLUDE_FROM)
                 .short('X')
                 .long("exclude-from")
                 .value_name("FILE")
                 .value_hint(clap::ValueHint::FilePath)
-                .help("exclude files that match any pattern in FILE")
+                .help(
+                    "exclude files that match any pattern in FILE; '-' reads the \
+                    patterns from stdin, blank lines and '#' comments are ignored, \
+                    and a '!pattern' line re-includes earlier matches"
+                )
                 .action(ArgAction::Append)
         )
         .arg(
@@ -1060,7 +2232,70 @@ pub fn uu_app() -> Command {
                 .hide(true)
                 .value_hint(clap::ValueHint::AnyPath)
                 .action(ArgAction::Append)
-        )
+        );
+
+    #[cfg(feature = "gitignore")]
+    let cmd = cmd.arg(
+        Arg::new(options::RESPECT_GITIGNORE)
+            .long(options::RESPECT_GITIGNORE)
+            .help(
+                "exclude files that are ignored by the .gitignore files of the \
+                traversed tree, including nested files and negations \
+                (a uutils extension)",
+            )
+            .action(ArgAction::SetTrue),
+    );
+
+    #[cfg(feature = "duplicates")]
+    let cmd = cmd.arg(
+        Arg::new(options::DUPLICATES)
+            .long(options::DUPLICATES)
+            .help(
+                "after the scan, report groups of files with identical content \
+                (candidates are grouped by size and compared by a fast content \
+                hash) and the bytes wasted by them (a uutils extension)",
+            )
+            .action(ArgAction::SetTrue),
+    );
+
+    cmd.arg(
+        Arg::new(options::SHARED_EXTENTS)
+            .long(options::SHARED_EXTENTS)
+            .value_name("GRANULARITY")
+            .num_args(0..=1)
+            .default_missing_value("extent")
+            .require_equals(true)
+            .help(
+                "count storage shared between files only once; GRANULARITY is \
+                'extent' (the default) to compare whole extents, or a block \
+                size in bytes to compare block-aligned ranges \
+                (a uutils extension)",
+            ),
+    )
+    .arg(
+        Arg::new(options::SHARED_PERCENT)
+            .long(options::SHARED_PERCENT)
+            .requires(options::SHARED_EXTENTS)
+            .help(
+                "with --shared-extents, also print per file what percentage of \
+                its storage was already seen on other files (a uutils extension)",
+            )
+            .action(ArgAction::SetTrue),
+    )
+    .arg(
+        Arg::new(options::CACHE)
+            .long(options::CACHE)
+            .value_name("FILE")
+            .value_hint(clap::ValueHint::FilePath)
+            .conflicts_with(options::SEPARATE_DIRS)
+            .help(
+                "cache per-directory totals in FILE and skip subtrees whose \
+                directory metadata is unchanged since the last run, reusing the \
+                cached sizes; entries below a reused subtree are not printed and \
+                changes that do not update a directory's modification time are \
+                not detected (a uutils extension)",
+            ),
+    )
 }
 
 #[derive(Clone, Copy)]
@@ -1099,6 +2334,48 @@ impl Threshold {
     }
 }
 
+/// The `--exclude-size` filter, written as `MIN..MAX` with either bound
+/// optional. Unlike [`Threshold`], which only hides output rows, files in
+/// this (inclusive) range are left out of the accounting entirely.
+#[derive(Clone, Copy)]
+struct SizeRange {
+    min: u64,
+    max: u64,
+}
+
+impl FromStr for SizeRange {
+    type Err = ParseSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((min, max)) = s.split_once("..") else {
+            return Err(ParseSizeError::ParseFailure(s.to_string()));
+        };
+        if min.is_empty() && max.is_empty() {
+            return Err(ParseSizeError::ParseFailure(s.to_string()));
+        }
+        let min = if min.is_empty() {
+            0
+        } else {
+            parse_size_u64(min)?
+        };
+        let max = if max.is_empty() {
+            u64::MAX
+        } else {
+            parse_size_u64(max)?
+        };
+        if min > max {
+            return Err(ParseSizeError::ParseFailure(s.to_string()));
+        }
+        Ok(Self { min, max })
+    }
+}
+
+impl SizeRange {
+    fn contains(&self, size: u64) -> bool {
+        (self.min..=self.max).contains(&size)
+    }
+}
+
 fn format_error_message(error: &ParseSizeError, s: &str, option: &str) -> String {
     // NOTE:
     // GNU's du echos affected flag, -B or --block-size (-t or --threshold), depending user's selection
@@ -1116,11 +2393,72 @@ mod test_du {
     #[allow(unused_imports)]
     use super::*;
 
+    #[test]
+    fn test_size_range_bounds_are_inclusive_and_optional() {
+        let range = SizeRange::from_str("1K..1M").unwrap();
+        assert!(range.contains(1024));
+        assert!(range.contains(1024 * 1024));
+        assert!(!range.contains(1023));
+        assert!(!range.contains(1024 * 1024 + 1));
+        assert!(SizeRange::from_str("..0").unwrap().contains(0));
+        assert!(SizeRange::from_str("5G..").unwrap().contains(u64::MAX));
+    }
+
+    #[test]
+    fn test_size_range_rejects_malformed_input() {
+        assert!(SizeRange::from_str("..").is_err());
+        assert!(SizeRange::from_str("10").is_err());
+        assert!(SizeRange::from_str("9..2").is_err());
+        assert!(SizeRange::from_str("x..2").is_err());
+    }
+
+    #[test]
+    fn test_validate_files0_entry_collapses_trailing_separators() {
+        assert_eq!(
+            validate_files0_entry("list", 1, b"dir///"),
+            Some(PathBuf::from("dir/"))
+        );
+        assert_eq!(
+            validate_files0_entry("list", 1, b"///"),
+            Some(PathBuf::from("/"))
+        );
+        assert_eq!(
+            validate_files0_entry("list", 1, b"a//b"),
+            Some(PathBuf::from("a//b"))
+        );
+    }
+
     #[test]
     fn test_read_block_size() {
         let test_data = [Some("1024".to_string()), Some("K".to_string()), None];
         for it in &test_data {
-            assert!(matches!(read_block_size(it.as_deref()), Ok(1024)));
+            assert!(matches!(
+                read_block_size(it.as_deref()),
+                Ok(BlockSize::Bytes { size: 1024, .. })
+            ));
         }
+        assert!(matches!(
+            read_block_size(Some("'2K")),
+            Ok(BlockSize::Bytes {
+                size: 2048,
+                thousands_separators: true
+            })
+        ));
+        assert!(matches!(
+            read_block_size(Some("human-readable")),
+            Ok(BlockSize::HumanBinary)
+        ));
+        assert!(matches!(
+            read_block_size(Some("si")),
+            Ok(BlockSize::HumanDecimal)
+        ));
+    }
+
+    #[test]
+    fn test_separate_thousands() {
+        assert_eq!(separate_thousands(0), "0");
+        assert_eq!(separate_thousands(999), "999");
+        assert_eq!(separate_thousands(1000), "1,000");
+        assert_eq!(separate_thousands(1234567), "1,234,567");
     }
 }
@@ -0,0 +1,100 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Checksum-based duplicate-file report (`--duplicates`).
+//!
+//! Candidates are collected during the normal traversal and first grouped by
+//! apparent size, so unique files never have to be read. Only groups with
+//! more than one member are hashed (FNV-1a over the full content) and files
+//! with identical hashes are reported together with the bytes that could be
+//! reclaimed by deduplicating them.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use uucore::display::Quotable;
+use uucore::show_warning;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Collects the regular files seen during traversal and prints the duplicate
+/// groups once the scan is done.
+#[derive(Default)]
+pub struct Recorder {
+    files: Vec<(PathBuf, u64)>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, path: &Path, size: u64) {
+        // empty files are trivially identical and waste nothing
+        if size > 0 {
+            self.files.push((path.to_path_buf(), size));
+        }
+    }
+
+    /// Writes each group of identical files and the total of wasted bytes to
+    /// `out` (the same destination as the size report, so `--output-file`
+    /// captures it too), then forgets the collected candidates (so a
+    /// `--watch` refresh starts from a clean slate).
+    pub fn report(&mut self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut by_size: BTreeMap<u64, Vec<&PathBuf>> = BTreeMap::new();
+        for (path, size) in &self.files {
+            by_size.entry(*size).or_default().push(path);
+        }
+
+        let mut wasted: u64 = 0;
+        for (&size, candidates) in &by_size {
+            if candidates.len() < 2 {
+                continue;
+            }
+            let mut by_hash: BTreeMap<u64, Vec<&PathBuf>> = BTreeMap::new();
+            for &path in candidates {
+                match hash_file(path) {
+                    Ok(hash) => by_hash.entry(hash).or_default().push(path),
+                    Err(e) => show_warning!("cannot read {}: {}", path.quote(), e),
+                }
+            }
+            for group in by_hash.values() {
+                if group.len() < 2 {
+                    continue;
+                }
+                wasted += size * (group.len() as u64 - 1);
+                writeln!(out, "duplicate group ({size} bytes each):")?;
+                for path in group {
+                    writeln!(out, "  {}", path.display())?;
+                }
+            }
+        }
+        writeln!(out, "wasted bytes in duplicates: {wasted}")?;
+
+        self.files.clear();
+        Ok(())
+    }
+}
+
+/// FNV-1a over the whole file content. Fast, dependency-free and good enough
+/// to tell files of equal size apart for a report.
+fn hash_file(path: &Path) -> std::io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buffer = [0u8; 64 * 1024];
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(hash);
+        }
+        for &byte in &buffer[..n] {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
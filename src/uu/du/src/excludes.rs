@@ -0,0 +1,41 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Compiled `--exclude` patterns and the matching engine.
+//!
+//! Matching runs once per directory entry, so the hot path must not
+//! allocate: every pattern is compiled to a [`glob::Pattern`] once up
+//! front and candidates are matched as borrowed strings. Because the last
+//! matching pattern decides, the list is scanned backwards and the scan
+//! stops at the first hit.
+//!
+//! This module is public so the exclude benchmark can drive it directly.
+
+use glob::Pattern;
+
+/// One compiled entry of the exclude list. Negated entries (`!pattern`
+/// lines in an `--exclude-from` file) re-include names matched by an
+/// earlier pattern.
+pub struct ExcludePattern {
+    pattern: Pattern,
+    negated: bool,
+}
+
+impl ExcludePattern {
+    pub fn new(pattern: Pattern, negated: bool) -> Self {
+        Self { pattern, negated }
+    }
+}
+
+/// Whether a name matched by any of `texts` is excluded. The last matching
+/// pattern decides, so a later `!pattern` entry wins over an earlier match,
+/// like in .gitignore files.
+pub fn is_excluded(excludes: &[ExcludePattern], texts: &[&str]) -> bool {
+    excludes
+        .iter()
+        .rev()
+        .find(|exclude| texts.iter().any(|text| exclude.pattern.matches(text)))
+        .is_some_and(|exclude| !exclude.negated)
+}
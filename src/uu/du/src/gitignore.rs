@@ -0,0 +1,136 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Gitignore matching for `du --respect-gitignore`.
+//!
+//! Implements the commonly used subset of gitignore semantics: one
+//! `.gitignore` per directory applying to its subtree, blank lines and `#`
+//! comments, `!` negation with later rules overriding earlier ones, trailing
+//! `/` for directory-only patterns, and anchoring of patterns that contain a
+//! slash. Re-including files below an ignored directory is not supported,
+//! matching git's own behavior. The matcher is only compiled with the
+//! `gitignore` cargo feature (enabled by default).
+
+use glob::{MatchOptions, Pattern};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+struct Rule {
+    pattern: Pattern,
+    /// Patterns containing a slash match relative to the `.gitignore`
+    /// location, all others match any basename below it.
+    anchored: bool,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Decides whether paths are ignored, loading `.gitignore` files lazily as
+/// the traversal descends and caching their parsed rules.
+pub struct Matcher {
+    /// Directory the current traversal started from; `.gitignore` files
+    /// above it are not consulted.
+    root: RefCell<PathBuf>,
+    cache: RefCell<HashMap<PathBuf, Vec<Rule>>>,
+}
+
+impl Matcher {
+    pub fn new() -> Self {
+        Self {
+            root: RefCell::new(PathBuf::new()),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Starts a fresh traversal rooted at `root`, dropping rules cached from
+    /// a previous one (they may be stale, e.g. between `--watch` refreshes).
+    pub fn set_root(&self, root: &Path) {
+        *self.root.borrow_mut() = root.to_path_buf();
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Whether `path` is ignored by the `.gitignore` files between the
+    /// traversal root and the path itself. Deeper files and later rules take
+    /// precedence, a matching negated rule re-includes the path.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let root = self.root.borrow();
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+
+        let mut dirs: Vec<&Path> = Vec::new();
+        let mut current = parent;
+        while current.starts_with(&*root) {
+            dirs.push(current);
+            match current.parent() {
+                Some(p) if !current.as_os_str().is_empty() => current = p,
+                _ => break,
+            }
+        }
+
+        let mut decision = None;
+        let mut cache = self.cache.borrow_mut();
+        for dir in dirs.into_iter().rev() {
+            let rules = cache
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| load_rules(dir));
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            for rule in rules.iter() {
+                if rule.dir_only && !is_dir {
+                    continue;
+                }
+                let matches = if rule.anchored {
+                    rule.pattern.matches_path_with(relative, MATCH_OPTIONS)
+                } else {
+                    path.file_name()
+                        .is_some_and(|name| rule.pattern.matches(&name.to_string_lossy()))
+                };
+                if matches {
+                    decision = Some(!rule.negated);
+                }
+            }
+        }
+        decision == Some(true)
+    }
+}
+
+fn load_rules(dir: &Path) -> Vec<Rule> {
+    let Ok(content) = std::fs::read_to_string(dir.join(".gitignore")) else {
+        return Vec::new();
+    };
+    content.lines().filter_map(parse_rule).collect()
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    let pattern = Pattern::new(line).ok()?;
+    Some(Rule {
+        pattern,
+        anchored,
+        negated,
+        dir_only,
+    })
+}
@@ -0,0 +1,349 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore reflink reflinked FIEMAP
+
+//! Storage-sharing aware accounting for `--shared-extents`.
+//!
+//! [`SeenPhysicalExtents`] records which byte ranges of the underlying
+//! storage were already accounted during a traversal and reports how much of
+//! a newly visited range was seen before, so shared storage is only counted
+//! once.
+//!
+//! On Linux, [`file_extents`] queries the real extent map via the FIEMAP
+//! ioctl, so ranges reflinked between files (btrfs, XFS) are recognized as
+//! shared; when FIEMAP is unavailable it degrades to the logical data ranges
+//! reported by `lseek(SEEK_DATA/SEEK_HOLE)`. The generic backend used
+//! elsewhere approximates a file's layout with a single extent in an
+//! inode-derived address space, which still detects sharing through hard
+//! links on every platform.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::Path;
+
+/// Granularity of the dedup key: whole extents as the backend reports them,
+/// or ranges aligned to a fixed block size (`--shared-extents=SIZE`), which
+/// treats partially overlapping extents as shared per block.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Granularity {
+    Extent,
+    Block(u64),
+}
+
+/// A byte range of storage within an address space. The FIEMAP backend uses
+/// one address space per device holding real physical offsets; the other
+/// backends use one address space per (device, inode) pair.
+pub struct Extent {
+    pub space: (u64, u64),
+    pub range: Range<u64>,
+}
+
+/// The address space of physical offsets on a device.
+fn device_space(dev_id: u64) -> (u64, u64) {
+    (dev_id, 0)
+}
+
+/// An address space private to an inode. The 128 bit windows file index is
+/// folded into the key; inode 0 is never valid, so this cannot collide with
+/// [`device_space`].
+fn inode_space(dev_id: u64, file_id: u128) -> (u64, u64) {
+    (dev_id, (file_id as u64) ^ ((file_id >> 64) as u64))
+}
+
+/// A set of non-overlapping `u64` ranges with a coalescing insert.
+///
+/// Backed by a `BTreeMap` from range start to range end (exclusive), kept
+/// disjoint — the flat alternative to an interval tree: since entries never
+/// overlap, ordering by start is enough for both the overlap query and the
+/// merge. An insert costs O(log n) plus the entries it absorbs, and every
+/// range is absorbed at most once over its lifetime, so any sequence of n
+/// inserts stays within O(n log n) no matter how the ranges overlap.
+#[derive(Default)]
+pub struct IntervalSet {
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl IntervalSet {
+    /// Insert `range` and return how many of its bytes were already covered.
+    /// Exactly overlapping entries are merged; merely adjacent ones are kept
+    /// separate, they share no storage.
+    pub fn insert_and_count_overlap(&mut self, range: Range<u64>) -> u64 {
+        let mut overlap = 0;
+        let mut merged = range.clone();
+
+        // the entry starting before the new range may still reach into it
+        if let Some((&start, &end)) = self.ranges.range(..range.start).next_back() {
+            if end > range.start {
+                overlap += end.min(range.end) - range.start;
+                merged.start = start;
+                merged.end = merged.end.max(end);
+                self.ranges.remove(&start);
+            }
+        }
+        // plus everything starting inside the new range
+        while let Some((&start, &end)) = self.ranges.range(range.start..range.end).next() {
+            overlap += end.min(range.end) - start;
+            merged.end = merged.end.max(end);
+            self.ranges.remove(&start);
+        }
+
+        self.ranges.insert(merged.start, merged.end);
+        overlap
+    }
+
+    /// Number of stored (disjoint) ranges.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+pub struct SeenPhysicalExtents {
+    granularity: Granularity,
+    /// One coalescing range set per address space.
+    ranges: HashMap<(u64, u64), IntervalSet>,
+}
+
+impl SeenPhysicalExtents {
+    pub fn new(granularity: Granularity) -> Self {
+        Self {
+            granularity,
+            ranges: HashMap::new(),
+        }
+    }
+
+    fn align(&self, range: &Range<u64>) -> Range<u64> {
+        match self.granularity {
+            Granularity::Extent => range.clone(),
+            Granularity::Block(size) => {
+                let start = range.start - range.start % size;
+                let end = range
+                    .end
+                    .checked_add(size - 1)
+                    .map_or(u64::MAX, |e| e - e % size);
+                start..end
+            }
+        }
+    }
+
+    /// Returns how many bytes of `extent` were recorded by earlier calls and
+    /// inserts its range, merging overlapping entries.
+    pub fn get_overlapping_and_insert(&mut self, extent: &Extent) -> u64 {
+        let range = self.align(&extent.range);
+        if range.start >= range.end {
+            return 0;
+        }
+        self.ranges
+            .entry(extent.space)
+            .or_default()
+            .insert_and_count_overlap(range)
+    }
+}
+
+/// The extents making up the storage of a file, from the most precise
+/// backend available: the real extent map, the logical data ranges, or the
+/// generic single-extent approximation.
+pub fn file_extents(path: &Path, dev_id: u64, file_id: u128, allocated: u64) -> Vec<Extent> {
+    #[cfg(target_os = "linux")]
+    if let Some(extents) = linux::fiemap_extents(path, dev_id) {
+        return extents;
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(extents) = linux::seek_data_extents(path, dev_id, file_id) {
+        return extents;
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = path;
+    generic_extents(dev_id, file_id, allocated)
+}
+
+/// The generic backend: one extent covering the `allocated` bytes of the
+/// inode, in an address space private to that inode.
+fn generic_extents(dev_id: u64, file_id: u128, allocated: u64) -> Vec<Extent> {
+    vec![Extent {
+        space: inode_space(dev_id, file_id),
+        range: 0..allocated,
+    }]
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{device_space, inode_space, Extent};
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+
+    const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+    const FIEMAP_FLAG_SYNC: u32 = 0x1;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const FIEMAP_EXTENT_UNKNOWN: u32 = 0x2;
+    const FIEMAP_EXTENT_DELALLOC: u32 = 0x4;
+    const FIEMAP_EXTENT_DATA_INLINE: u32 = 0x40;
+
+    /// Extents fetched per ioctl call.
+    const EXTENT_BATCH: usize = 64;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct FiemapExtent {
+        fe_logical: u64,
+        fe_physical: u64,
+        fe_length: u64,
+        fe_reserved64: [u64; 2],
+        fe_flags: u32,
+        fe_reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    struct FiemapRequest {
+        fm_start: u64,
+        fm_length: u64,
+        fm_flags: u32,
+        fm_mapped_extents: u32,
+        fm_extent_count: u32,
+        fm_reserved: u32,
+        fm_extents: [FiemapExtent; EXTENT_BATCH],
+    }
+
+    /// The physical extents of `path` per the FIEMAP ioctl, in the device's
+    /// address space, so ranges reflinked into other files compare as
+    /// shared. `None` when the filesystem does not support the ioctl or an
+    /// extent has no meaningful physical address.
+    pub fn fiemap_extents(path: &Path, dev_id: u64) -> Option<Vec<Extent>> {
+        let file = File::open(path).ok()?;
+        let mut extents = Vec::new();
+        let mut start = 0u64;
+        loop {
+            let mut request = FiemapRequest {
+                fm_start: start,
+                fm_length: u64::MAX - start,
+                fm_flags: FIEMAP_FLAG_SYNC,
+                fm_mapped_extents: 0,
+                fm_extent_count: EXTENT_BATCH as u32,
+                fm_reserved: 0,
+                // SAFETY: FiemapExtent is plain data; zeroes are valid
+                fm_extents: unsafe { std::mem::zeroed() },
+            };
+            // SAFETY: the file descriptor is open and the request points to
+            // a properly sized FIEMAP buffer
+            if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP, &mut request) } != 0 {
+                return None;
+            }
+            if request.fm_mapped_extents == 0 {
+                break;
+            }
+            let mut saw_last = false;
+            for extent in &request.fm_extents[..request.fm_mapped_extents as usize] {
+                if extent.fe_flags
+                    & (FIEMAP_EXTENT_UNKNOWN | FIEMAP_EXTENT_DELALLOC | FIEMAP_EXTENT_DATA_INLINE)
+                    != 0
+                {
+                    return None;
+                }
+                extents.push(Extent {
+                    space: device_space(dev_id),
+                    range: extent.fe_physical..extent.fe_physical.saturating_add(extent.fe_length),
+                });
+                saw_last |= extent.fe_flags & FIEMAP_EXTENT_LAST != 0;
+                start = extent.fe_logical.saturating_add(extent.fe_length);
+            }
+            if saw_last {
+                break;
+            }
+        }
+        Some(extents)
+    }
+
+    /// The logical data ranges of `path` per `lseek(SEEK_DATA/SEEK_HOLE)`,
+    /// in the inode's private address space: sparse regions are skipped, but
+    /// sharing is only recognized between names of the same inode.
+    pub fn seek_data_extents(path: &Path, dev_id: u64, file_id: u128) -> Option<Vec<Extent>> {
+        let file = File::open(path).ok()?;
+        let fd = file.as_raw_fd();
+        let mut extents = Vec::new();
+        let mut offset = 0;
+        loop {
+            // SAFETY: plain lseek calls on an open file descriptor
+            let data = unsafe { libc::lseek(fd, offset, libc::SEEK_DATA) };
+            if data < 0 {
+                // past the last data region
+                if io::Error::last_os_error().raw_os_error() == Some(libc::ENXIO) {
+                    break;
+                }
+                return None;
+            }
+            // SAFETY: see above
+            let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+            if hole < 0 {
+                return None;
+            }
+            extents.push(Extent {
+                space: inode_space(dev_id, file_id),
+                range: data as u64..hole as u64,
+            });
+            offset = hole;
+        }
+        Some(extents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalSet;
+
+    #[test]
+    fn test_disjoint_ranges_do_not_overlap() {
+        let mut set = IntervalSet::default();
+        assert_eq!(set.insert_and_count_overlap(0..10), 0);
+        assert_eq!(set.insert_and_count_overlap(20..30), 0);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_repeated_range_overlaps_fully() {
+        let mut set = IntervalSet::default();
+        assert_eq!(set.insert_and_count_overlap(5..15), 0);
+        assert_eq!(set.insert_and_count_overlap(5..15), 10);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_partial_overlaps_merge_from_both_sides() {
+        let mut set = IntervalSet::default();
+        set.insert_and_count_overlap(0..10);
+        set.insert_and_count_overlap(20..30);
+        // reaches 5 into the first range and 5 into the second
+        assert_eq!(set.insert_and_count_overlap(5..25), 10);
+        assert_eq!(set.len(), 1);
+        // the merged range covers everything
+        assert_eq!(set.insert_and_count_overlap(0..30), 30);
+    }
+
+    #[test]
+    fn test_insert_absorbing_many_entries() {
+        let mut set = IntervalSet::default();
+        for i in 0..100u64 {
+            set.insert_and_count_overlap(i * 10..i * 10 + 5);
+        }
+        assert_eq!(set.len(), 100);
+        // covers all 100 entries of 5 bytes each
+        assert_eq!(set.insert_and_count_overlap(0..1000), 500);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_adjacent_ranges_stay_separate() {
+        let mut set = IntervalSet::default();
+        assert_eq!(set.insert_and_count_overlap(0..10), 0);
+        assert_eq!(set.insert_and_count_overlap(10..20), 0);
+        assert!(!set.is_empty());
+        assert_eq!(set.len(), 2);
+    }
+}
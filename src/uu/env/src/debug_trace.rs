@@ -0,0 +1,109 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Machine readable debug tracing (`--debug-format=json`).
+//!
+//! With `-v --debug-format=json` every debug report becomes one JSON object
+//! per line on stderr instead of the fixed human text, so tooling and
+//! fuzzers can validate the parser and the applied environment changes
+//! mechanically. The layout is part of the extension's interface: every
+//! line carries an `"event"` key naming the step, everything else is
+//! event-specific.
+
+use std::ffi::{OsStr, OsString};
+
+/// Did the command line select the JSON debug format? Decided by a literal
+/// scan, because the `-S` splitting (and its tracing) happens before clap
+/// gets to parse the arguments.
+pub(crate) fn json_format_selected(args: &[OsString]) -> bool {
+    args.iter().any(|arg| arg == "--debug-format=json")
+        || args
+            .windows(2)
+            .any(|pair| pair[0] == "--debug-format" && pair[1] == "json")
+}
+
+/// Escape `s` for use inside a JSON string literal. Invalid UTF-8 is
+/// replaced, not round-tripped; the trace serves diagnostics, it is not a
+/// faithful byte protocol.
+fn escaped(s: &OsStr) -> String {
+    let mut out = String::new();
+    for c in s.to_string_lossy().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn quoted(s: &OsStr) -> String {
+    format!("\"{}\"", escaped(s))
+}
+
+fn array<I, S>(items: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let items: Vec<String> = items.into_iter().map(|s| quoted(s.as_ref())).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// The argument vector as received, the JSON counterpart of the `-vv`
+/// input dump.
+pub(crate) fn emit_input_args(args: &[OsString]) {
+    eprintln!("{{\"event\":\"input-args\",\"args\":{}}}", array(args));
+}
+
+/// One `-S` string and the argument vector it was split into.
+pub(crate) fn emit_split_string(input: &OsStr, argv: &[OsString]) {
+    eprintln!(
+        "{{\"event\":\"split-string\",\"input\":{},\"argv\":{}}}",
+        quoted(input),
+        array(argv)
+    );
+}
+
+/// `-i`/`--ignore-environment` wiped the inherited environment.
+pub(crate) fn emit_clear_env() {
+    eprintln!("{{\"event\":\"clear-env\"}}");
+}
+
+/// A variable was set; `source` names the pipeline step responsible
+/// (`inherit`, `file`, `assignment` or `default`).
+pub(crate) fn emit_set(source: &str, name: &OsStr, value: &OsStr) {
+    eprintln!(
+        "{{\"event\":\"set\",\"source\":\"{source}\",\"name\":{},\"value\":{}}}",
+        quoted(name),
+        quoted(value)
+    );
+}
+
+/// A variable was removed with `-u`/`--unset` (or `--no-proxy-env`).
+pub(crate) fn emit_unset(name: &OsStr) {
+    eprintln!("{{\"event\":\"unset\",\"name\":{}}}", quoted(name));
+}
+
+/// The final exec decision: the resolved program and the full argv,
+/// including a possibly overridden `argv[0]`.
+pub(crate) fn emit_exec(program: &OsStr, argv0: &OsStr, args: &[&OsStr]) {
+    let mut argv = vec![argv0.to_os_string()];
+    argv.extend(args.iter().map(|arg| arg.to_os_string()));
+    eprintln!(
+        "{{\"event\":\"exec\",\"program\":{},\"argv\":{}}}",
+        quoted(program),
+        array(&argv)
+    );
+}
+
+/// A single-value event, e.g. `resolve-command` with the resolved path.
+pub(crate) fn emit_value(event: &str, key: &str, value: &OsStr) {
+    eprintln!("{{\"event\":\"{event}\",\"{key}\":{}}}", quoted(value));
+}
@@ -5,8 +5,13 @@
 
 // spell-checker:ignore (ToDO) chdir execvp progname subcommand subcommands unsets setenv putenv spawnp SIGSEGV SIGBUS sigaction
 
+mod debug_trace;
+mod environment_builder;
+mod gnu_compat;
 pub mod native_int_str;
 pub mod parse_error;
+#[cfg(unix)]
+pub mod pty;
 pub mod split_iterator;
 pub mod string_expander;
 pub mod string_parser;
@@ -14,21 +19,27 @@ pub mod variable_parser;
 
 use clap::builder::ValueParser;
 use clap::{crate_name, crate_version, Arg, ArgAction, Command};
-use ini::Ini;
 use native_int_str::{
-    from_native_int_representation_owned, Convert, NCvt, NativeIntStr, NativeIntString, NativeStr,
+    from_native_int_representation, from_native_int_representation_owned, Convert, NCvt,
+    NativeIntStr, NativeIntString, NativeStr,
 };
 #[cfg(unix)]
-use nix::sys::signal::{raise, sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::sys::signal::{kill, raise, sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
 use std::borrow::Cow;
 use std::env;
 use std::ffi::{OsStr, OsString};
-use std::io::{self, Write};
+use std::fs;
+use std::io::{self, BufRead, Write};
 use std::ops::Deref;
+use std::path::Path;
 
 #[cfg(unix)]
 use std::os::unix::process::{CommandExt, ExitStatusExt};
 use std::process::{self};
+use std::thread;
+use std::time::Duration;
 use uucore::display::Quotable;
 use uucore::error::{ExitCode, UError, UResult, USimpleError, UUsageError};
 use uucore::line_ending::LineEnding;
@@ -40,25 +51,129 @@ const AFTER_HELP: &str = help_section!("after help", "env.md");
 
 const ERROR_MSG_S_SHEBANG: &str = "use -[v]S to pass options in shebang lines";
 
+/// Variables removed by `--no-proxy-env`: the conventional proxy settings in
+/// both their lower-case and upper-case spellings.
+const PROXY_ENV_VARS: &[&str] = &[
+    "http_proxy",
+    "HTTP_PROXY",
+    "https_proxy",
+    "HTTPS_PROXY",
+    "no_proxy",
+    "NO_PROXY",
+];
+
+/// Which optional capabilities are available for this run. With
+/// `POSIXLY_CORRECT` set in the inherited environment, uutils-specific
+/// extensions that change observable behavior are disabled, so scripts
+/// relying on strict POSIX semantics are not surprised by them.
+struct Capabilities {
+    extensions: bool,
+}
+
+impl Capabilities {
+    fn detect() -> Self {
+        Self {
+            extensions: env::var_os("POSIXLY_CORRECT").is_none(),
+        }
+    }
+
+    /// Reject the use of the extension option `--{option}` when extensions
+    /// are disabled.
+    fn require_extension(&self, option: &str) -> UResult<()> {
+        if self.extensions {
+            Ok(())
+        } else {
+            Err(USimpleError::new(
+                125,
+                format!("--{option} is a uutils extension and is disabled because POSIXLY_CORRECT is set"),
+            ))
+        }
+    }
+}
+
 struct Options<'a> {
     ignore_env: bool,
     line_ending: LineEnding,
-    running_directory: Option<&'a OsStr>,
+    /// The `--chdir` directories, applied in the given order; every later
+    /// entry is resolved relative to the earlier ones.
+    running_directories: Vec<&'a OsStr>,
+    /// Resolve a relative COMMAND against the `--chdir` directory before the
+    /// PATH lookup (`--resolve-command`).
+    resolve_command: bool,
+    /// Print the resulting working directory instead of the environment when
+    /// no COMMAND is given (`--pwd`).
+    print_pwd: bool,
     files: Vec<&'a OsStr>,
     unsets: Vec<&'a OsStr>,
+    /// Variables to carry over from the original environment even with `-i`
+    /// (`--inherit`).
+    inherits: Vec<&'a OsStr>,
+    /// Strip dynamic loader variables from the resulting environment
+    /// (`--secure`).
+    secure: bool,
+    /// Additional variable names `--secure` strips (`--secure-deny`).
+    secure_deny: Vec<&'a OsStr>,
     sets: Vec<(Cow<'a, OsStr>, Cow<'a, OsStr>)>,
+    defaults: Vec<(Cow<'a, OsStr>, Cow<'a, OsStr>)>,
     program: Vec<&'a OsStr>,
     argv0: Option<&'a OsStr>,
+    login: bool,
+    pty: bool,
+    try_interpreter: Option<&'a OsStr>,
+    retry: Option<(u32, Duration)>,
+    /// Restart COMMAND whenever this file changes (`--watch-file`).
+    watch_file: Option<&'a OsStr>,
+    /// Also write the final environment to this file descriptor before
+    /// running COMMAND (`--print-env-fd`).
+    print_env_fd: Option<u32>,
 }
 
 // print name=value env pairs on screen
 // if null is true, separate pairs with a \0, \n otherwise
-fn print_env(line_ending: LineEnding) {
-    let stdout_raw = io::stdout();
-    let mut stdout = stdout_raw.lock();
-    for (n, v) in env::vars() {
-        write!(stdout, "{}={}{}", n, v, line_ending).unwrap();
+fn print_env(line_ending: LineEnding, environ_sort_check: bool) -> UResult<()> {
+    gnu_compat::print_environment(line_ending, environ_sort_check)
+        .map_err(|e| USimpleError::new(125, format!("write error: {e}")))
+}
+
+/// Dump the final environment to the given file descriptor before COMMAND
+/// runs (`--print-env-fd`). The descriptor is closed once the dump is
+/// complete: a supervisor reading the other end sees EOF instead of waiting
+/// for the child to exit.
+#[cfg(unix)]
+fn write_env_to_fd(fd: u32, line_ending: LineEnding) -> UResult<()> {
+    use std::os::fd::BorrowedFd;
+
+    let mut dump = Vec::new();
+    gnu_compat::write_environment(&mut dump, line_ending)
+        .map_err(|e| USimpleError::new(125, format!("cannot write environment to fd {fd}: {e}")))?;
+    // SAFETY: the descriptor number comes from the command line; nothing in
+    // this process owns it, so borrowing it cannot alias a handle std still
+    // uses (descriptors below 3 are rejected by the parser). A number that is
+    // not open at all only makes the write fail with EBADF.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd as i32) };
+    let mut rest = dump.as_slice();
+    while !rest.is_empty() {
+        match nix::unistd::write(borrowed, rest) {
+            Ok(written) => rest = &rest[written..],
+            Err(nix::errno::Errno::EINTR) => (),
+            Err(e) => {
+                return Err(USimpleError::new(
+                    125,
+                    format!("cannot write environment to fd {fd}: {e}"),
+                ));
+            }
+        }
     }
+    let _ = nix::unistd::close(fd as i32);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_env_to_fd(_fd: u32, _line_ending: LineEnding) -> UResult<()> {
+    Err(USimpleError::new(
+        2,
+        "--print-env-fd is currently not supported on this platform",
+    ))
 }
 
 fn parse_name_value_opt<'a>(opts: &mut Options<'a>, opt: &'a OsStr) -> UResult<bool> {
@@ -76,7 +191,9 @@ fn parse_name_value_opt<'a>(opts: &mut Options<'a>, opt: &'a OsStr) -> UResult<b
 }
 
 fn parse_program_opt<'a>(opts: &mut Options<'a>, opt: &'a OsStr) -> UResult<()> {
-    if opts.line_ending == LineEnding::Nul {
+    // with --print-env-fd the NUL-delimited output has its own destination,
+    // so -0 no longer conflicts with running a command
+    if opts.line_ending == LineEnding::Nul && opts.print_env_fd.is_none() {
         Err(UUsageError::new(
             125,
             "cannot specify --null (-0) with command".to_string(),
@@ -87,32 +204,6 @@ fn parse_program_opt<'a>(opts: &mut Options<'a>, opt: &'a OsStr) -> UResult<()>
     }
 }
 
-fn load_config_file(opts: &mut Options) -> UResult<()> {
-    // NOTE: config files are parsed using an INI parser b/c it's available and compatible with ".env"-style files
-    //   ... * but support for actual INI files, although working, is not intended, nor claimed
-    for &file in &opts.files {
-        let conf = if file == "-" {
-            let stdin = io::stdin();
-            let mut stdin_locked = stdin.lock();
-            Ini::read_from(&mut stdin_locked)
-        } else {
-            Ini::load_from_file(file)
-        };
-
-        let conf =
-            conf.map_err(|e| USimpleError::new(1, format!("{}: {}", file.maybe_quote(), e)))?;
-
-        for (_, prop) in &conf {
-            // ignore all INI section lines (treat them as comments)
-            for (key, value) in prop.iter() {
-                env::set_var(key, value);
-            }
-        }
-    }
-
-    Ok(())
-}
-
 pub fn uu_app() -> Command {
     Command::new(crate_name!())
         .version(crate_version!())
@@ -136,7 +227,42 @@ pub fn uu_app() -> Command {
                 .value_name("DIR")
                 .value_parser(ValueParser::os_string())
                 .value_hint(clap::ValueHint::DirPath)
-                .help("change working directory to DIR"),
+                .action(ArgAction::Append)
+                .help(
+                    "change working directory to DIR; may be repeated, every \
+                    additional DIR is resolved relative to the previous one",
+                ),
+        )
+        .arg(
+            Arg::new("resolve-command")
+                .long("resolve-command")
+                .help(
+                    "resolve a relative COMMAND against the directory selected \
+                    with --chdir before the PATH lookup, so 'env -C DIR CMD' \
+                    runs DIR/CMD when it exists (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-environ-sort-check")
+                .long("no-environ-sort-check")
+                .help(
+                    "when printing the environment, do not verify that the \
+                    platform reports the entries in raw environ order; the \
+                    printed output itself always matches GNU env byte for \
+                    byte (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pwd")
+                .long("pwd")
+                .help(
+                    "print the working directory a command would run in (after \
+                    applying --chdir) instead of the environment; only valid \
+                    without a COMMAND (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("null")
@@ -170,6 +296,91 @@ pub fn uu_app() -> Command {
                 .value_parser(ValueParser::os_string())
                 .help("remove variable from the environment"),
         )
+        .arg(
+            Arg::new("no-proxy-env")
+                .long("no-proxy-env")
+                .help(
+                    "remove the conventional proxy variables (http_proxy, \
+                https_proxy, no_proxy and their upper-case variants) from the \
+                environment, as if each was passed to --unset (a uutils \
+                extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("default")
+                .long("default")
+                .value_name("NAME=VALUE")
+                .action(ArgAction::Append)
+                .value_parser(ValueParser::os_string())
+                .help(
+                    "set NAME to VALUE only if NAME is not already present in the \
+                environment after all removals and assignments were applied \
+                (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new("inherit")
+                .long("inherit")
+                .value_name("NAME")
+                .action(ArgAction::Append)
+                .value_parser(ValueParser::os_string())
+                .help(
+                    "carry the variable NAME over from the original environment \
+                even with --ignore-environment; may be repeated (a uutils \
+                extension)",
+                ),
+        )
+        .arg(
+            Arg::new("secure")
+                .long("secure")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "strip dynamic loader variables (LD_*, DYLD_*) from the \
+                resulting environment, with a warning for each stripped \
+                variable; useful when launching less-trusted commands (a \
+                uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new("secure-deny")
+                .long("secure-deny")
+                .value_name("NAME")
+                .action(ArgAction::Append)
+                .value_parser(ValueParser::os_string())
+                .requires("secure")
+                .help(
+                    "additionally strip the variable NAME with --secure; may \
+                be repeated (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new("command-var")
+                .long("command-var")
+                .value_name("NAME")
+                .value_parser(ValueParser::os_string())
+                .help(
+                    "read the command to run from the environment variable NAME, \
+                splitting its value like --split-string; NAME is looked up after \
+                all removals and assignments were applied, so it may itself be \
+                passed as NAME=VALUE or removed with -i/-u (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new("check-env")
+                .long("check-env")
+                .value_name("MODE")
+                .num_args(0..=1)
+                .default_missing_value("warn")
+                .require_equals(true)
+                .value_parser(["warn", "fail"])
+                .help(
+                    "scan the inherited environment for entries that are not \
+                representable (empty names, names containing '=', names or values \
+                that are not valid UTF-8) and report them; with --check-env=fail \
+                exit with status 125 if any such entry exists (a uutils extension)",
+                ),
+        )
         .arg(
             Arg::new("debug")
                 .short('v')
@@ -185,6 +396,36 @@ pub fn uu_app() -> Command {
                 .action(ArgAction::Set)
                 .value_parser(ValueParser::os_string())
                 .help("process and split S into separate arguments; used to pass multiple arguments on shebang lines")
+        ).arg(
+            Arg::new("debug-format")
+                .long("debug-format")
+                .value_name("FORMAT")
+                .value_parser(["human", "json"])
+                .help(
+                    "print the debug reports of -v as JSON lines on stderr instead \
+                of the fixed human text: parsing steps, the resulting argv, the \
+                applied variable changes and the final exec decision \
+                (a uutils extension)",
+                ),
+        ).arg(
+            Arg::new("split-string-expand") // like -S, handled before clap parsing; listed for help and POSIX gating
+                .long("split-string-expand")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "in later -S strings, also expand a word-leading ~/ to $HOME and \
+                $$ to the process id, useful for shebang lines on systems without a \
+                shell (a uutils extension)",
+                ),
+        ).arg(
+            Arg::new("shebang") // rewritten before clap parsing, listed here for the help information output
+                .long("shebang")
+                .value_name("LINE")
+                .action(ArgAction::Set)
+                .value_parser(ValueParser::os_string())
+                .help("treat LINE exactly like the kernel treats everything after the \
+                       interpreter of a shebang line: a single argument with spaces kept \
+                       intact and a trailing newline removed; must be the first argument \
+                       (a uutils extension)")
         ).arg(
             Arg::new("argv0")
                 .overrides_with("argv0")
@@ -196,6 +437,88 @@ pub fn uu_app() -> Command {
                 .help("Override the zeroth argument passed to the command being executed. \
                        Without this option a default value of `command` is used.")
         )
+        .arg(
+            Arg::new("login")
+                .short('l')
+                .long("login")
+                .help(
+                    "prepend '-' to the zeroth argument of COMMAND, like 'su -', \
+                so shells started through env behave as login shells \
+                (a uutils extension; unix only)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("try-interpreter")
+                .long("try-interpreter")
+                .value_name("INTERPRETER")
+                .num_args(0..=1)
+                .default_missing_value("/bin/sh")
+                .require_equals(true)
+                .value_parser(ValueParser::os_string())
+                .help(
+                    "if the command cannot be executed because it lacks the execute \
+                permission, detect the interpreter from its shebang line and run it \
+                explicitly; INTERPRETER is used for scripts without a shebang line \
+                (a uutils extension)",
+                )
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("pty")
+                .long("pty")
+                .help(
+                    "run COMMAND attached to a freshly allocated pseudo terminal \
+                (a uutils extension; unix only)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("retry")
+                .long("retry")
+                .value_name("N[,DELAY]")
+                .require_equals(true)
+                .help(
+                    "if COMMAND exits with a nonzero status, run it again, up to N \
+                more times, waiting DELAY seconds (fractions allowed) between \
+                attempts; the status of the last attempt is reported \
+                (a uutils extension)",
+                )
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("watch-file")
+                .long("watch-file")
+                .value_name("FILE")
+                .value_parser(ValueParser::os_string())
+                .help(
+                    "run COMMAND and restart it whenever FILE changes; a burst of \
+                writes triggers a single restart, and the environment (including \
+                any --file files) is rebuilt before each restart \
+                (a uutils extension)",
+                )
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("print-env-fd")
+                .long("print-env-fd")
+                .value_name("FD")
+                .value_parser(clap::value_parser!(u32).range(3..=i32::MAX as i64))
+                .help(
+                    "write the final environment to file descriptor FD before \
+                running COMMAND, delimited like the stdout listing (NUL with \
+                --null); FD is closed after the dump, and --null may then be \
+                combined with a command (a uutils extension)",
+                )
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("generate-completion")
+                .long("generate-completion")
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(clap_complete::Shell))
+                .help("print a completion script for SHELL to stdout and exit (a uutils extension)"),
+        )
         .arg(
             Arg::new("vars")
                 .action(ArgAction::Append)
@@ -204,7 +527,20 @@ pub fn uu_app() -> Command {
 }
 
 pub fn parse_args_from_str(text: &NativeIntStr) -> UResult<Vec<NativeIntString>> {
-    split_iterator::split(text).map_err(|e| match e {
+    parse_args_from_str_with_options(text, false, false)
+}
+
+/// Like [`parse_args_from_str`], but with `trace` set every `${VAR}`
+/// expansion is reported on stderr (name, source and resulting value), so the
+/// produced argv can be retraced under `-vvS`; `expand_extras` additionally
+/// expands a word-leading `~/` to `$HOME` and `$$` to the process id
+/// (`--split-string-expand`).
+pub fn parse_args_from_str_with_options(
+    text: &NativeIntStr,
+    trace: bool,
+    expand_extras: bool,
+) -> UResult<Vec<NativeIntString>> {
+    split_iterator::split_with_options(text, trace, expand_extras).map_err(|e| match e {
         parse_error::ParseError::BackslashCNotAllowedInDoubleQuotes { pos: _ } => {
             USimpleError::new(125, "'\\c' must not appear in double-quoted -S string")
         }
@@ -220,6 +556,11 @@ pub fn parse_args_from_str(text: &NativeIntStr) -> UResult<Vec<NativeIntString>>
         parse_error::ParseError::ParsingOfVariableNameFailed { pos, msg } => {
             USimpleError::new(125, format!("variable name issue (at {}): {}", pos, msg,))
         }
+        parse_error::ParseError::VariableIsMissing {
+            pos: _,
+            varname,
+            msg,
+        } => USimpleError::new(125, format!("{}: {}", varname, msg)),
         _ => USimpleError::new(125, format!("Error: {:?}", e)),
     })
 }
@@ -236,19 +577,36 @@ fn check_and_handle_string_args(
     prefix_to_test: &str,
     all_args: &mut Vec<std::ffi::OsString>,
     do_debug_print_args: Option<&Vec<OsString>>,
+    expand_extras: bool,
+    json: bool,
 ) -> UResult<bool> {
     let native_arg = NCvt::convert(arg);
     if let Some(remaining_arg) = native_arg.strip_prefix(&*NCvt::convert(prefix_to_test)) {
         if let Some(input_args) = do_debug_print_args {
-            debug_print_args(input_args); // do it here, such that its also printed when we get an error/panic during parsing
+            // do it here, such that its also printed when we get an error/panic during parsing
+            if json {
+                debug_trace::emit_input_args(input_args);
+            } else {
+                debug_print_args(input_args);
+            }
         }
 
-        let arg_strings = parse_args_from_str(remaining_arg)?;
-        all_args.extend(
-            arg_strings
-                .into_iter()
-                .map(from_native_int_representation_owned),
-        );
+        // the expansion trace accompanies the input dump of `-vv`; with the
+        // JSON format one split-string event replaces the per-expansion lines
+        let arg_strings = parse_args_from_str_with_options(
+            remaining_arg,
+            do_debug_print_args.is_some() && !json,
+            expand_extras,
+        )?;
+        let arg_strings: Vec<OsString> = arg_strings
+            .into_iter()
+            .map(from_native_int_representation_owned)
+            .collect();
+        if json && do_debug_print_args.is_some() {
+            let input = from_native_int_representation(Cow::Borrowed(remaining_arg));
+            debug_trace::emit_split_string(&input, &arg_strings);
+        }
+        all_args.extend(arg_strings);
 
         Ok(true)
     } else {
@@ -256,11 +614,45 @@ fn check_and_handle_string_args(
     }
 }
 
+/// Rewrite `env --shebang LINE ...` into the argument vector the kernel
+/// builds for an `#!/usr/bin/env LINE` script: everything after the
+/// interpreter stays one single argument, with spaces kept intact and a
+/// trailing newline removed, so a shebang line can be tested by pasting it
+/// on the command line. Only recognized as the first argument, before any
+/// shell-style splitting happens.
+fn apply_shebang_emulation(mut args: Vec<OsString>) -> UResult<Vec<OsString>> {
+    if args.get(1).map_or(true, |arg| arg != "--shebang") {
+        return Ok(args);
+    }
+    Capabilities::detect().require_extension("shebang")?;
+    if args.len() < 3 {
+        return Err(USimpleError::new(
+            125,
+            "missing shebang line after '--shebang'",
+        ));
+    }
+    let mut line = args[2].clone();
+    if let Some(stripped) = line.to_str().and_then(|s| s.strip_suffix('\n')) {
+        line = OsString::from(stripped);
+    }
+    let mut rewritten = Vec::with_capacity(args.len() - 1);
+    rewritten.push(args[0].clone());
+    rewritten.push(line);
+    rewritten.extend(args.drain(3..));
+    Ok(rewritten)
+}
+
 #[derive(Default)]
 struct EnvAppData {
     do_debug_printing: bool,
     do_input_debug_printing: Option<bool>,
     had_string_argument: bool,
+    /// `--debug-format=json` was seen; debug reports become JSON lines
+    /// (a uutils extension).
+    debug_json: bool,
+    /// `--split-string-expand` was seen; later `-S` strings also expand a
+    /// word-leading `~/` and `$$` (a uutils extension).
+    split_string_expand: bool,
 }
 
 impl EnvAppData {
@@ -278,14 +670,45 @@ impl EnvAppData {
     ) -> UResult<Vec<std::ffi::OsString>> {
         let mut all_args: Vec<std::ffi::OsString> = Vec::new();
         for arg in original_args {
+            let expand = self.split_string_expand;
             match arg {
-                b if check_and_handle_string_args(b, "--split-string", &mut all_args, None)? => {
+                // processed in argument order, so it only affects later -S
+                // strings; clap still sees it for --help and POSIX gating
+                b if b == "--split-string-expand" => {
+                    self.split_string_expand = true;
+                    all_args.push(arg.clone());
+                }
+                b if check_and_handle_string_args(
+                    b,
+                    "--split-string",
+                    &mut all_args,
+                    None,
+                    expand,
+                    self.debug_json,
+                )? =>
+                {
                     self.had_string_argument = true;
                 }
-                b if check_and_handle_string_args(b, "-S", &mut all_args, None)? => {
+                b if check_and_handle_string_args(
+                    b,
+                    "-S",
+                    &mut all_args,
+                    None,
+                    expand,
+                    self.debug_json,
+                )? =>
+                {
                     self.had_string_argument = true;
                 }
-                b if check_and_handle_string_args(b, "-vS", &mut all_args, None)? => {
+                b if check_and_handle_string_args(
+                    b,
+                    "-vS",
+                    &mut all_args,
+                    None,
+                    expand,
+                    self.debug_json,
+                )? =>
+                {
                     self.do_debug_printing = true;
                     self.had_string_argument = true;
                 }
@@ -294,6 +717,8 @@ impl EnvAppData {
                     "-vvS",
                     &mut all_args,
                     Some(original_args),
+                    expand,
+                    self.debug_json,
                 )? =>
                 {
                     self.do_debug_printing = true;
@@ -314,6 +739,9 @@ impl EnvAppData {
         original_args: impl uucore::Args,
     ) -> Result<(Vec<OsString>, clap::ArgMatches), Box<dyn UError>> {
         let original_args: Vec<OsString> = original_args.collect();
+        let original_args = apply_shebang_emulation(original_args)?;
+        // decided before clap runs, so the -S tracing can already use it
+        self.debug_json = debug_trace::json_format_selected(&original_args);
         let args = self.process_all_string_arguments(&original_args)?;
         let app = uu_app();
         let matches = app
@@ -340,36 +768,93 @@ impl EnvAppData {
     fn run_env(&mut self, original_args: impl uucore::Args) -> UResult<()> {
         let (original_args, matches) = self.parse_arguments(original_args)?;
 
+        if let Some(shell) = matches.get_one::<clap_complete::Shell>("generate-completion") {
+            clap_complete::generate(
+                *shell,
+                &mut uu_app(),
+                uucore::util_name(),
+                &mut io::stdout(),
+            );
+            return Ok(());
+        }
+
         self.do_debug_printing = self.do_debug_printing || (0 != matches.get_count("debug"));
         self.do_input_debug_printing = self
             .do_input_debug_printing
             .or(Some(matches.get_count("debug") >= 2));
         if let Some(value) = self.do_input_debug_printing {
             if value {
-                debug_print_args(&original_args);
+                if self.debug_json {
+                    debug_trace::emit_input_args(&original_args);
+                } else {
+                    debug_print_args(&original_args);
+                }
                 self.do_input_debug_printing = Some(false);
             }
         }
 
-        let mut opts = make_options(&matches)?;
+        let capabilities = Capabilities::detect();
 
-        apply_change_directory(&opts)?;
+        // recognized by apply_shebang_emulation before clap runs; if it made
+        // it into the matches it was given in the wrong position
+        if matches.contains_id("shebang") {
+            return Err(USimpleError::new(
+                125,
+                "'--shebang' must be the first argument",
+            ));
+        }
 
-        // NOTE: we manually set and unset the env vars below rather than using Command::env() to more
-        //       easily handle the case where no command is given
+        if let Some(mode) = matches.get_one::<String>("check-env") {
+            capabilities.require_extension("check-env")?;
+            check_inherited_env_vars(mode)?;
+        }
 
-        apply_removal_of_all_env_vars(&opts);
+        let mut opts = make_options(&matches, &capabilities)?;
 
-        // load .env-style config file prior to those given on the command-line
-        load_config_file(&mut opts)?;
+        apply_change_directory(&opts)?;
 
-        apply_unset_env_vars(&opts)?;
+        // NOTE: the env vars are manually set and unset rather than using
+        // Command::env() to more easily handle the case where no command is
+        // given; see the environment_builder module for the pipeline order
+        environment_builder::build(&opts, self.do_debug_printing && self.debug_json)?;
 
-        apply_specified_env_vars(&opts);
+        // resolved here so that it sees the environment built up above
+        if matches.contains_id("command-var") {
+            capabilities.require_extension("command-var")?;
+        }
+        let command_var_args = resolve_command_var(&matches, &opts)?;
+        opts.program
+            .extend(command_var_args.iter().map(|s| s.as_os_str()));
 
         if opts.program.is_empty() {
-            // no program provided, so just dump all env vars to stdout
-            print_env(opts.line_ending);
+            if opts.watch_file.is_some() {
+                return Err(UUsageError::new(
+                    125,
+                    "must specify command with --watch-file".to_string(),
+                ));
+            }
+            if opts.print_env_fd.is_some() {
+                return Err(UUsageError::new(
+                    125,
+                    "must specify command with --print-env-fd".to_string(),
+                ));
+            }
+            if opts.print_pwd {
+                // print the directory a command would run in instead of the
+                // environment listing
+                let dir = env::current_dir()
+                    .map_err(|e| USimpleError::new(125, format!("cannot determine pwd: {e}")))?;
+                let mut stdout = io::stdout().lock();
+                write!(stdout, "{}{}", dir.display(), opts.line_ending).unwrap();
+            } else {
+                // no program provided, so just dump all env vars to stdout
+                print_env(opts.line_ending, !matches.get_flag("no-environ-sort-check"))?;
+            }
+        } else if opts.print_pwd {
+            return Err(UUsageError::new(
+                125,
+                "cannot specify command with --pwd".to_string(),
+            ));
         } else {
             return self.run_program(opts, self.do_debug_printing);
         }
@@ -382,29 +867,39 @@ impl EnvAppData {
         opts: Options<'_>,
         do_debug_printing: bool,
     ) -> Result<(), Box<dyn UError>> {
-        let prog = Cow::from(opts.program[0]);
+        let mut prog = Cow::from(opts.program[0]);
         #[cfg(unix)]
         let mut arg0 = prog.clone();
         #[cfg(not(unix))]
         let arg0 = prog.clone();
         let args = &opts.program[1..];
 
-        /*
-         * On Unix-like systems Command::status either ends up calling either fork or posix_spawnp
-         * (which ends up calling clone). Keep using the current process would be ideal, but the
-         * standard library contains many checks and fail-safes to ensure the process ends up being
-         * created. This is much simpler than dealing with the hassles of calling execvp directly.
-         */
-        let mut cmd = process::Command::new(&*prog);
-        cmd.args(args);
+        let debug_json = self.debug_json;
+
+        // the spawned program sees the original name as argv[0] either way
+        if opts.resolve_command {
+            if let Some(resolved) = resolve_relative_command(&prog) {
+                if do_debug_printing {
+                    if debug_json {
+                        debug_trace::emit_value("resolve-command", "path", &resolved);
+                    } else {
+                        eprintln!("resolved:  {}", resolved.quote());
+                    }
+                }
+                prog = Cow::Owned(resolved);
+            }
+        }
 
         if let Some(_argv0) = opts.argv0 {
             #[cfg(unix)]
             {
-                cmd.arg0(_argv0);
                 arg0 = Cow::Borrowed(_argv0);
                 if do_debug_printing {
-                    eprintln!("argv0:     {}", arg0.quote());
+                    if debug_json {
+                        debug_trace::emit_value("argv0", "value", &arg0);
+                    } else {
+                        eprintln!("argv0:     {}", arg0.quote());
+                    }
                 }
             }
 
@@ -415,16 +910,131 @@ impl EnvAppData {
             ));
         }
 
+        if opts.login {
+            #[cfg(unix)]
+            {
+                let mut login_arg0 = OsString::from("-");
+                login_arg0.push(&*arg0);
+                arg0 = Cow::Owned(login_arg0);
+            }
+
+            #[cfg(not(unix))]
+            return Err(USimpleError::new(
+                2,
+                "--login is currently not supported on this platform",
+            ));
+        }
+
+        /*
+         * On Unix-like systems Command::status either ends up calling either fork or posix_spawnp
+         * (which ends up calling clone). Keep using the current process would be ideal, but the
+         * standard library contains many checks and fail-safes to ensure the process ends up being
+         * created. This is much simpler than dealing with the hassles of calling execvp directly.
+         *
+         * The command is rebuilt for every attempt because `pty::run_on_pty`
+         * consumes the command it runs.
+         */
+        let build_command = || {
+            let mut cmd = process::Command::new(&*prog);
+            cmd.args(args);
+            #[cfg(unix)]
+            cmd.arg0(&*arg0);
+            cmd
+        };
+
         if do_debug_printing {
-            eprintln!("executing: {}", prog.maybe_quote());
-            let arg_prefix = "   arg";
-            eprintln!("{}[{}]= {}", arg_prefix, 0, arg0.quote());
-            for (i, arg) in args.iter().enumerate() {
-                eprintln!("{}[{}]= {}", arg_prefix, i + 1, arg.quote());
+            if debug_json {
+                debug_trace::emit_exec(&prog, &arg0, args);
+            } else {
+                eprintln!("executing: {}", prog.maybe_quote());
+                let arg_prefix = "   arg";
+                eprintln!("{}[{}]= {}", arg_prefix, 0, arg0.quote());
+                for (i, arg) in args.iter().enumerate() {
+                    eprintln!("{}[{}]= {}", arg_prefix, i + 1, arg.quote());
+                }
             }
         }
 
-        match cmd.status() {
+        #[cfg(not(unix))]
+        if opts.pty {
+            return Err(USimpleError::new(
+                2,
+                "--pty is currently not supported on this platform",
+            ));
+        }
+
+        // dumped before the first attempt, once the environment is final
+        if let Some(fd) = opts.print_env_fd {
+            write_env_to_fd(fd, opts.line_ending)?;
+        }
+
+        if let Some(watch_file) = opts.watch_file {
+            return watch_and_rerun(&opts, watch_file, &build_command);
+        }
+
+        let run_attempt = || {
+            let mut cmd = build_command();
+            #[cfg(unix)]
+            let status = if opts.pty {
+                pty::run_on_pty(cmd)
+            } else {
+                cmd.status()
+            };
+            #[cfg(windows)]
+            let status = status_in_job_object(cmd);
+            #[cfg(not(any(unix, windows)))]
+            let status = cmd.status();
+
+            // give scripts lacking the execute permission a second chance with an
+            // explicitly invoked interpreter
+            match status {
+                Err(err)
+                    if err.kind() == io::ErrorKind::PermissionDenied
+                        && opts.try_interpreter.is_some() =>
+                {
+                    match interpreter_command(&prog, args, opts.try_interpreter.unwrap()) {
+                        Some(mut interpreter_cmd) => {
+                            if do_debug_printing {
+                                if debug_json {
+                                    debug_trace::emit_value(
+                                        "exec-interpreter",
+                                        "program",
+                                        interpreter_cmd.get_program(),
+                                    );
+                                } else {
+                                    eprintln!(
+                                        "executing with interpreter: {}",
+                                        interpreter_cmd.get_program().quote()
+                                    );
+                                }
+                            }
+                            interpreter_cmd.status()
+                        }
+                        None => Err(err),
+                    }
+                }
+                other => other,
+            }
+        };
+
+        let (mut retries_left, retry_delay) = opts.retry.unwrap_or((0, Duration::ZERO));
+        let status = loop {
+            let status = run_attempt();
+            match &status {
+                Ok(exit) if !exit.success() && retries_left > 0 => {
+                    retries_left -= 1;
+                    if do_debug_printing {
+                        eprintln!("command failed, retrying ({retries_left} retries left)");
+                    }
+                    if !retry_delay.is_zero() {
+                        thread::sleep(retry_delay);
+                    }
+                }
+                _ => break status,
+            }
+        };
+
+        match status {
             Ok(exit) if !exit.success() => {
                 #[cfg(unix)]
                 if let Some(exit_code) = exit.code() {
@@ -470,38 +1080,325 @@ impl EnvAppData {
     }
 }
 
-fn apply_removal_of_all_env_vars(opts: &Options<'_>) {
-    // remove all env vars if told to ignore presets
-    if opts.ignore_env {
-        for (ref name, _) in env::vars_os() {
-            env::remove_var(name);
+/// How often `--watch-file` polls the watched file; a change only counts
+/// once the file has kept still for one whole interval, so a burst of writes
+/// causes a single restart.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The watched file's current fingerprint. `None` while the file is missing,
+/// which happens transiently during an editor's write-then-rename cycle.
+fn watch_fingerprint(path: &OsStr) -> Option<(std::time::SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Stop the previous command before a `--watch-file` restart: ask with
+/// `SIGTERM` first and escalate to a hard kill if it has not exited after a
+/// grace period of two seconds.
+fn stop_watched_child(child: &mut process::Child) {
+    #[cfg(unix)]
+    {
+        let _ = kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM);
+        for _ in 0..20 {
+            if matches!(child.try_wait(), Ok(Some(_))) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Supervise COMMAND for `--watch-file`: poll the watched file and whenever
+/// it changes, stop the running command, rebuild the environment (re-reading
+/// the `--file` files) and start a fresh one. A command that exits on its own
+/// is left alone until the next change; env itself runs until terminated and
+/// only returns on an error.
+fn watch_and_rerun(
+    opts: &Options<'_>,
+    watch_file: &OsStr,
+    build_command: &dyn Fn() -> process::Command,
+) -> UResult<()> {
+    let mut last_seen = watch_fingerprint(watch_file);
+    if last_seen.is_none() {
+        return Err(USimpleError::new(
+            125,
+            format!("cannot watch {}: no such file", watch_file.quote()),
+        ));
+    }
+
+    let spawn = || {
+        let mut cmd = build_command();
+        cmd.spawn().map_err(|err| {
+            let code = if err.kind() == io::ErrorKind::NotFound {
+                127
+            } else {
+                126
+            };
+            USimpleError::new(code, format!("{}: {err}", cmd.get_program().quote()))
+        })
+    };
+
+    let mut child = spawn()?;
+    loop {
+        thread::sleep(WATCH_POLL_INTERVAL);
+        // reap a command that finished on its own so it does not linger
+        let _ = child.try_wait();
+        let current = watch_fingerprint(watch_file);
+        if current == last_seen {
+            continue;
+        }
+        // debounce: only act once the file has stopped changing
+        let mut settled = current;
+        loop {
+            thread::sleep(WATCH_POLL_INTERVAL);
+            let next = watch_fingerprint(watch_file);
+            if next == settled {
+                break;
+            }
+            settled = next;
+        }
+        last_seen = settled;
+        stop_watched_child(&mut child);
+        // with `-i` the rebuilt environment is exactly what a fresh
+        // invocation would see; without it, variables no longer set by the
+        // files keep their previous values
+        environment_builder::build(opts, false)?;
+        child = spawn()?;
+    }
+}
+
+/// Build the command running `prog` through an explicit interpreter, for use
+/// when `prog` itself lacks the execute permission (`--try-interpreter`).
+///
+/// The interpreter is taken from the shebang line of the script if there is
+/// one, otherwise the configured `fallback` interpreter is used. `None` is
+/// returned if `prog` cannot be read as a script at all.
+/// Run the command inside a kill-on-close job object, so that all of its
+/// descendants are terminated along with env. Windows has no process groups
+/// that die together, so without the job a crashing or killed env would leak
+/// the command's whole process tree. Job creation is best effort: on systems
+/// where it fails the command still runs, just without the cleanup guarantee.
+#[cfg(windows)]
+fn status_in_job_object(mut cmd: process::Command) -> io::Result<process::ExitStatus> {
+    let job = uucore::job_object::KillOnCloseJob::new().ok();
+    let mut child = cmd.spawn()?;
+    if let Some(job) = &job {
+        let _ = job.assign(&child);
+    }
+    child.wait()
+}
+
+fn interpreter_command(
+    prog: &OsStr,
+    args: &[&OsStr],
+    fallback: &OsStr,
+) -> Option<process::Command> {
+    let file = fs::File::open(prog).ok()?;
+    let mut first_line = String::new();
+    io::BufReader::new(file).read_line(&mut first_line).ok()?;
+
+    let mut cmd;
+    if let Some(shebang) = first_line.strip_prefix("#!") {
+        // shebang semantics: the interpreter plus at most one argument
+        let mut parts = shebang.trim().splitn(2, char::is_whitespace);
+        cmd = process::Command::new(parts.next()?);
+        if let Some(interpreter_arg) = parts.next() {
+            cmd.arg(interpreter_arg.trim());
         }
+    } else {
+        cmd = process::Command::new(fallback);
     }
+    cmd.arg(prog).args(args);
+    Some(cmd)
+}
+
+/// Report inherited environment entries that cannot be represented faithfully
+/// (`--check-env`). Such entries typically stem from broken CI setups and make
+/// later exec calls fail in hard to debug ways. In "fail" mode any finding
+/// aborts with status 125 instead of running the command.
+fn check_inherited_env_vars(mode: &str) -> UResult<()> {
+    let mut problems = 0;
+    for (name, value) in env::vars_os() {
+        let mut issues: Vec<&str> = Vec::new();
+        if name.is_empty() {
+            issues.push("empty variable name");
+        } else if name.to_string_lossy().contains('=') {
+            issues.push("variable name contains '='");
+        }
+        if name.to_str().is_none() {
+            issues.push("variable name is not valid UTF-8");
+        }
+        if value.to_str().is_none() {
+            issues.push("value is not valid UTF-8");
+        }
+        for issue in &issues {
+            show_warning!("invalid environment entry {}: {}", name.quote(), issue);
+        }
+        problems += issues.len();
+    }
+    if mode == "fail" && problems > 0 {
+        return Err(USimpleError::new(
+            125,
+            format!(
+                "inherited environment contains {problems} invalid entr{}",
+                if problems == 1 { "y" } else { "ies" }
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the `N[,DELAY]` argument of `--retry`: a retry count plus an
+/// optional delay in seconds between attempts, fractions allowed.
+fn parse_retry_spec(spec: &str) -> UResult<(u32, Duration)> {
+    let (count, delay) = match spec.split_once(',') {
+        Some((count, delay)) => (count, Some(delay)),
+        None => (spec, None),
+    };
+    let count = count
+        .parse::<u32>()
+        .map_err(|_| USimpleError::new(125, format!("invalid retry count {}", count.quote())))?;
+    let delay = match delay {
+        Some(delay) => delay
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+            .ok_or_else(|| {
+                USimpleError::new(125, format!("invalid retry delay {}", delay.quote()))
+            })?,
+        None => Duration::ZERO,
+    };
+    Ok((count, delay))
 }
 
-fn make_options(matches: &clap::ArgMatches) -> UResult<Options<'_>> {
+fn make_options<'a>(
+    matches: &'a clap::ArgMatches,
+    capabilities: &Capabilities,
+) -> UResult<Options<'a>> {
     let ignore_env = matches.get_flag("ignore-environment");
     let line_ending = LineEnding::from_zero_flag(matches.get_flag("null"));
-    let running_directory = matches.get_one::<OsString>("chdir").map(|s| s.as_os_str());
+    let running_directories = match matches.get_many::<OsString>("chdir") {
+        Some(v) => v.map(|s| s.as_os_str()).collect(),
+        None => Vec::with_capacity(0),
+    };
+    let resolve_command = matches.get_flag("resolve-command");
+    if resolve_command {
+        capabilities.require_extension("resolve-command")?;
+    }
+    let print_pwd = matches.get_flag("pwd");
+    if print_pwd {
+        capabilities.require_extension("pwd")?;
+    }
     let files = match matches.get_many::<OsString>("file") {
         Some(v) => v.map(|s| s.as_os_str()).collect(),
         None => Vec::with_capacity(0),
     };
-    let unsets = match matches.get_many::<OsString>("unset") {
+    let mut unsets: Vec<&OsStr> = match matches.get_many::<OsString>("unset") {
+        Some(v) => v.map(|s| s.as_os_str()).collect(),
+        None => Vec::with_capacity(0),
+    };
+    if matches.get_flag("no-proxy-env") {
+        capabilities.require_extension("no-proxy-env")?;
+        unsets.extend(PROXY_ENV_VARS.iter().map(OsStr::new));
+    }
+    let inherits: Vec<&OsStr> = match matches.get_many::<OsString>("inherit") {
+        Some(v) => {
+            capabilities.require_extension("inherit")?;
+            v.map(|s| s.as_os_str()).collect()
+        }
+        None => Vec::with_capacity(0),
+    };
+    if matches.get_flag("split-string-expand") {
+        capabilities.require_extension("split-string-expand")?;
+    }
+    if matches.get_one::<String>("debug-format").is_some() {
+        capabilities.require_extension("debug-format")?;
+    }
+    let secure = matches.get_flag("secure");
+    if secure {
+        capabilities.require_extension("secure")?;
+    }
+    let secure_deny: Vec<&OsStr> = match matches.get_many::<OsString>("secure-deny") {
         Some(v) => v.map(|s| s.as_os_str()).collect(),
         None => Vec::with_capacity(0),
     };
     let argv0 = matches.get_one::<OsString>("argv0").map(|s| s.as_os_str());
+    let login = matches.get_flag("login");
+    if login {
+        capabilities.require_extension("login")?;
+    }
+    let pty = matches.get_flag("pty");
+    if pty {
+        capabilities.require_extension("pty")?;
+    }
+    let try_interpreter = matches
+        .get_one::<OsString>("try-interpreter")
+        .map(|s| s.as_os_str());
+    if try_interpreter.is_some() {
+        capabilities.require_extension("try-interpreter")?;
+    }
+    let retry = matches
+        .get_one::<String>("retry")
+        .map(|spec| parse_retry_spec(spec))
+        .transpose()?;
+    if retry.is_some() {
+        capabilities.require_extension("retry")?;
+    }
+    let watch_file = matches
+        .get_one::<OsString>("watch-file")
+        .map(|s| s.as_os_str());
+    if watch_file.is_some() {
+        capabilities.require_extension("watch-file")?;
+    }
+    let print_env_fd = matches.get_one::<u32>("print-env-fd").copied();
+    if print_env_fd.is_some() {
+        capabilities.require_extension("print-env-fd")?;
+    }
+
+    let mut defaults = Vec::new();
+    if let Some(values) = matches.get_many::<OsString>("default") {
+        capabilities.require_extension("default")?;
+        for opt in values {
+            let wrap = NativeStr::new(opt.as_os_str());
+            match wrap.split_once(&'=') {
+                Some((name, value)) => defaults.push((name, value)),
+                None => {
+                    return Err(USimpleError::new(
+                        125,
+                        format!(
+                            "invalid argument {} for '--default': expected NAME=VALUE",
+                            opt.quote()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
 
     let mut opts = Options {
         ignore_env,
         line_ending,
-        running_directory,
+        running_directories,
+        resolve_command,
+        print_pwd,
         files,
         unsets,
+        inherits,
+        secure,
+        secure_deny,
         sets: vec![],
+        defaults,
         program: vec![],
         argv0,
+        login,
+        pty,
+        try_interpreter,
+        retry,
+        watch_file,
+        print_env_fd,
     };
 
     let mut begin_prog_opts = false;
@@ -528,80 +1425,80 @@ fn make_options(matches: &clap::ArgMatches) -> UResult<Options<'_>> {
     Ok(opts)
 }
 
-fn apply_unset_env_vars(opts: &Options<'_>) -> Result<(), Box<dyn UError>> {
-    for name in &opts.unsets {
-        let native_name = NativeStr::new(name);
-        if name.is_empty()
-            || native_name.contains(&'\0').unwrap()
-            || native_name.contains(&'=').unwrap()
-        {
-            return Err(USimpleError::new(
-                125,
-                format!("cannot unset {}: Invalid argument", name.quote()),
-            ));
-        }
+/// Resolve `--command-var`: look up the named variable and split its value
+/// like a `--split-string` argument. The lookup happens against the already
+/// modified environment, so the variable may itself be passed as NAME=VALUE
+/// (even together with -i) or removed with -u.
+fn resolve_command_var(matches: &clap::ArgMatches, opts: &Options<'_>) -> UResult<Vec<OsString>> {
+    let Some(name) = matches.get_one::<OsString>("command-var") else {
+        return Ok(Vec::new());
+    };
+    if !opts.program.is_empty() {
+        return Err(USimpleError::new(
+            125,
+            "cannot specify both --command-var and a command",
+        ));
+    }
+    if opts.line_ending == LineEnding::Nul && opts.print_env_fd.is_none() {
+        return Err(UUsageError::new(
+            125,
+            "cannot specify --null (-0) with command".to_string(),
+        ));
+    }
+    let Some(value) = env::var_os(name) else {
+        return Err(USimpleError::new(
+            125,
+            format!("variable {} is not set for '--command-var'", name.quote()),
+        ));
+    };
+    let native_value = NCvt::convert(value.as_os_str());
+    let args: Vec<OsString> = parse_args_from_str(&native_value)?
+        .into_iter()
+        .map(from_native_int_representation_owned)
+        .collect();
+    if args.is_empty() {
+        return Err(USimpleError::new(
+            125,
+            format!("variable {} does not contain a command", name.quote()),
+        ));
+    }
+    Ok(args)
+}
 
-        env::remove_var(name);
+/// Resolve a relative COMMAND against the current working directory, i.e.
+/// the directory selected with `--chdir` (`--resolve-command`). Returns
+/// `None` when the command is absolute or no such file exists, in which case
+/// the usual PATH lookup applies unchanged.
+fn resolve_relative_command(prog: &OsStr) -> Option<OsString> {
+    let path = Path::new(prog);
+    if path.is_absolute() {
+        return None;
     }
-    Ok(())
+    let candidate = env::current_dir().ok()?.join(path);
+    candidate.is_file().then(|| candidate.into_os_string())
 }
 
 fn apply_change_directory(opts: &Options<'_>) -> Result<(), Box<dyn UError>> {
-    // GNU env tests this behavior
-    if opts.program.is_empty() && opts.running_directory.is_some() {
+    // GNU env tests this behavior; `--pwd` is the one print mode that makes
+    // sense without a command
+    if opts.program.is_empty() && !opts.running_directories.is_empty() && !opts.print_pwd {
         return Err(UUsageError::new(
             125,
             "must specify command with --chdir (-C)".to_string(),
         ));
     }
 
-    if let Some(d) = opts.running_directory {
-        match env::set_current_dir(d) {
-            Ok(()) => d,
-            Err(error) => {
-                return Err(USimpleError::new(
-                    125,
-                    format!("cannot change directory to {}: {error}", d.quote()),
-                ));
-            }
-        };
+    for d in &opts.running_directories {
+        env::set_current_dir(d).map_err(|error| {
+            USimpleError::new(
+                125,
+                format!("cannot change directory to {}: {error}", d.quote()),
+            )
+        })?;
     }
     Ok(())
 }
 
-fn apply_specified_env_vars(opts: &Options<'_>) {
-    // set specified env vars
-    for (name, val) in &opts.sets {
-        /*
-         * set_var panics if name is an empty string
-         * set_var internally calls setenv (on unix at least), while GNU env calls putenv instead.
-         *
-         * putenv returns successfully if provided with something like "=a" and modifies the environ
-         * variable to contain "=a" inside it, effectively modifying the process' current environment
-         * to contain a malformed string in it. Using GNU's implementation, the command `env =a`
-         * prints out the malformed string and even invokes the child process with that environment.
-         * This can be seen by using `env -i =a env` or `env -i =a cat /proc/self/environ`
-         *
-         * POSIX.1-2017 doesn't seem to mention what to do if the string is malformed (at least
-         * not in "Chapter 8, Environment Variables" or in the definition for environ and various
-         * exec*'s or in the description of env in the "Shell & Utilities" volume).
-         *
-         * It also doesn't specify any checks for putenv before modifying the environ variable, which
-         * is likely why glibc doesn't do so. However, the first set_var argument cannot point to
-         * an empty string or a string containing '='.
-         *
-         * There is no benefit in replicating GNU's env behavior, since it will only modify the
-         * environment in weird ways
-         */
-
-        if name.is_empty() {
-            show_warning!("no name specified for value {}", val.quote());
-            continue;
-        }
-        env::set_var(name, val);
-    }
-}
-
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     EnvAppData::default().run_env(args)
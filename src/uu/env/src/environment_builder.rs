@@ -0,0 +1,212 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore (ToDO) unsets setenv putenv
+
+//! Building the environment of the command (or of the printed listing).
+//!
+//! Every option that shapes the environment feeds into one fixed pipeline,
+//! applied by [`build`] in this order:
+//!
+//! 1. the values named by `--inherit` are read from the original environment,
+//! 2. `-i`/`--ignore-environment` removes everything,
+//! 3. the `--inherit` values are put back,
+//! 4. `-f`/`--file` configuration files are loaded,
+//! 5. `-u`/`--unset` (and `--no-proxy-env`) removals are applied,
+//! 6. the positional `NAME=VALUE` assignments are set,
+//! 7. `--default` fills in variables that are still missing,
+//! 8. the `--secure` policy filter strips denied variables.
+//!
+//! So an assignment always beats an inherited or file-provided value, an
+//! unset always beats an inherit, `--default` never overrides anything, and
+//! nothing gets a denied variable past `--secure`.
+
+use crate::native_int_str::NativeStr;
+use crate::Options;
+use ini::Ini;
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use uucore::display::Quotable;
+use uucore::error::{UError, UResult, USimpleError};
+use uucore::show_warning;
+
+/// Run the whole pipeline described in the module documentation. With
+/// `trace` set, every applied change is reported as a JSON line on stderr
+/// (`-v --debug-format=json`).
+pub(crate) fn build(opts: &Options, trace: bool) -> UResult<()> {
+    // Snapshot before any removal, so `--inherit` works together with `-i`
+    // no matter which of the two comes first on the command line.
+    let inherited: Vec<(OsString, OsString)> = opts
+        .inherits
+        .iter()
+        .filter_map(|&name| env::var_os(name).map(|value| (name.to_owned(), value)))
+        .collect();
+
+    apply_removal_of_all_env_vars(opts, trace);
+
+    for (name, value) in inherited {
+        if trace {
+            crate::debug_trace::emit_set("inherit", &name, &value);
+        }
+        env::set_var(name, value);
+    }
+
+    load_config_files(opts, trace)?;
+
+    apply_unset_env_vars(opts, trace)?;
+
+    apply_specified_env_vars(opts, trace);
+
+    apply_default_env_vars(opts, trace);
+
+    apply_secure_policy(opts, trace);
+
+    Ok(())
+}
+
+fn apply_removal_of_all_env_vars(opts: &Options<'_>, trace: bool) {
+    // remove all env vars if told to ignore presets
+    if opts.ignore_env {
+        if trace {
+            crate::debug_trace::emit_clear_env();
+        }
+        for (ref name, _) in env::vars_os() {
+            env::remove_var(name);
+        }
+    }
+}
+
+fn load_config_files(opts: &Options, trace: bool) -> UResult<()> {
+    // NOTE: config files are parsed using an INI parser b/c it's available and compatible with ".env"-style files
+    //   ... * but support for actual INI files, although working, is not intended, nor claimed
+    for &file in &opts.files {
+        let conf = if file == "-" {
+            let stdin = io::stdin();
+            let mut stdin_locked = stdin.lock();
+            Ini::read_from(&mut stdin_locked)
+        } else {
+            Ini::load_from_file(file)
+        };
+
+        let conf =
+            conf.map_err(|e| USimpleError::new(1, format!("{}: {}", file.maybe_quote(), e)))?;
+
+        for (_, prop) in &conf {
+            // ignore all INI section lines (treat them as comments)
+            for (key, value) in prop.iter() {
+                if trace {
+                    crate::debug_trace::emit_set("file", key.as_ref(), value.as_ref());
+                }
+                env::set_var(key, value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_unset_env_vars(opts: &Options<'_>, trace: bool) -> Result<(), Box<dyn UError>> {
+    for name in &opts.unsets {
+        let native_name = NativeStr::new(name);
+        if name.is_empty()
+            || native_name.contains(&'\0').unwrap()
+            || native_name.contains(&'=').unwrap()
+        {
+            return Err(USimpleError::new(
+                125,
+                format!("cannot unset {}: Invalid argument", name.quote()),
+            ));
+        }
+
+        if trace {
+            crate::debug_trace::emit_unset(name);
+        }
+        env::remove_var(name);
+    }
+    Ok(())
+}
+
+fn apply_specified_env_vars(opts: &Options<'_>, trace: bool) {
+    // set specified env vars
+    for (name, val) in &opts.sets {
+        /*
+         * set_var panics if name is an empty string
+         * set_var internally calls setenv (on unix at least), while GNU env calls putenv instead.
+         *
+         * putenv returns successfully if provided with something like "=a" and modifies the environ
+         * variable to contain "=a" inside it, effectively modifying the process' current environment
+         * to contain a malformed string in it. Using GNU's implementation, the command `env =a`
+         * prints out the malformed string and even invokes the child process with that environment.
+         * This can be seen by using `env -i =a env` or `env -i =a cat /proc/self/environ`
+         *
+         * POSIX.1-2017 doesn't seem to mention what to do if the string is malformed (at least
+         * not in "Chapter 8, Environment Variables" or in the definition for environ and various
+         * exec*'s or in the description of env in the "Shell & Utilities" volume).
+         *
+         * It also doesn't specify any checks for putenv before modifying the environ variable, which
+         * is likely why glibc doesn't do so. However, the first set_var argument cannot point to
+         * an empty string or a string containing '='.
+         *
+         * There is no benefit in replicating GNU's env behavior, since it will only modify the
+         * environment in weird ways
+         */
+
+        if name.is_empty() {
+            show_warning!("no name specified for value {}", val.quote());
+            continue;
+        }
+        if trace {
+            crate::debug_trace::emit_set("assignment", name, val);
+        }
+        env::set_var(name, val);
+    }
+}
+
+/// The variable names `--secure` strips, as prefixes: everything the dynamic
+/// loader reads on glibc (`LD_*`) and on macOS (`DYLD_*`).
+const SECURE_DENY_PREFIXES: &[&str] = &["LD_", "DYLD_"];
+
+/// Strip dynamic loader variables (and the names from `--secure-deny`) from
+/// the resulting environment, warning for each stripped variable. This runs
+/// last so it also catches values smuggled in through a config file, an
+/// assignment or `--default`.
+fn apply_secure_policy(opts: &Options<'_>, trace: bool) {
+    if !opts.secure {
+        return;
+    }
+    for (name, _) in env::vars_os() {
+        let denied = name.to_str().is_some_and(|name| {
+            SECURE_DENY_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        }) || opts.secure_deny.iter().any(|&deny| deny == name);
+        if denied {
+            show_warning!("--secure: not passing {} to the command", name.quote());
+            if trace {
+                crate::debug_trace::emit_value("secure-strip", "name", &name);
+            }
+            env::remove_var(&name);
+        }
+    }
+}
+
+/// Apply the `--default` assignments. They run after all removals and
+/// assignments, so they only fill in variables that are still missing and
+/// never override an explicitly requested value.
+fn apply_default_env_vars(opts: &Options<'_>, trace: bool) {
+    for (name, val) in &opts.defaults {
+        if name.is_empty() {
+            show_warning!("no name specified for value {}", val.quote());
+            continue;
+        }
+        if env::var_os(name).is_none() {
+            if trace {
+                crate::debug_trace::emit_set("default", name, val);
+            }
+            env::set_var(name, val);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! GNU `env` output-compatibility quirks.
+//!
+//! When invoked without a command, GNU env walks the raw `environ` array and
+//! prints every entry verbatim. Matching that output byte for byte needs more
+//! than `std::env::vars()`:
+//!
+//! * entries appear in `environ` order; nothing is sorted,
+//! * values pass through unmodified, including embedded newlines and bytes
+//!   that are not valid UTF-8 (where `std::env::vars()` would panic),
+//! * malformed entries the standard library hides -- an entry without any
+//!   `'='`, or one starting with `'='` (an empty variable name inherited from
+//!   the parent) -- are printed as-is instead of being skipped,
+//! * no quoting or escaping is applied.
+//!
+//! Each of these quirks is implemented below for unix; on other platforms the
+//! standard library view is the best approximation available.
+
+use std::io::{self, Write};
+
+use uucore::line_ending::LineEnding;
+use uucore::show_warning;
+
+/// All environment entries as raw byte strings, in `environ` order.
+#[cfg(unix)]
+fn raw_entries() -> Vec<Vec<u8>> {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    extern "C" {
+        static environ: *const *const c_char;
+    }
+
+    let mut entries = Vec::new();
+    // SAFETY: environ is the process environment set up by the C runtime; it
+    // is a NULL-terminated array of NUL-terminated strings. Nothing mutates
+    // it concurrently here (set_var callers have finished by the time the
+    // environment is printed).
+    unsafe {
+        let mut ptr = environ;
+        while !ptr.is_null() && !(*ptr).is_null() {
+            entries.push(CStr::from_ptr(*ptr).to_bytes().to_vec());
+            ptr = ptr.add(1);
+        }
+    }
+    entries
+}
+
+#[cfg(not(unix))]
+fn raw_entries() -> Vec<Vec<u8>> {
+    std::env::vars_os()
+        .map(|(name, value)| {
+            let mut entry = name.to_string_lossy().into_owned().into_bytes();
+            entry.push(b'=');
+            entry.extend_from_slice(value.to_string_lossy().as_bytes());
+            entry
+        })
+        .collect()
+}
+
+/// Compare the raw `environ` view against what `std::env::vars_os()` exposes
+/// and warn when the platform hides or reorders entries, since consumers of
+/// the printed environment may then disagree with other tools.
+#[cfg(unix)]
+fn sort_check(entries: &[Vec<u8>]) {
+    use std::os::unix::ffi::OsStrExt;
+
+    let std_view: Vec<Vec<u8>> = std::env::vars_os()
+        .map(|(name, value)| {
+            let mut entry = name.as_bytes().to_vec();
+            entry.push(b'=');
+            entry.extend_from_slice(value.as_bytes());
+            entry
+        })
+        .collect();
+    if std_view != *entries {
+        show_warning!(
+            "environment contains entries that the platform hides or reorders; \
+            they were printed in raw environ order"
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn sort_check(_entries: &[Vec<u8>]) {}
+
+/// Print the environment the way GNU env does when no command is given. With
+/// `check` set, additionally verify that the standard library agrees with the
+/// raw view (see [`sort_check`]); `--no-environ-sort-check` skips that.
+pub(crate) fn print_environment(line_ending: LineEnding, check: bool) -> io::Result<()> {
+    let entries = raw_entries();
+    if check {
+        sort_check(&entries);
+    }
+    let stdout_raw = io::stdout();
+    let mut stdout = stdout_raw.lock();
+    write_entries(&mut stdout, &entries, line_ending)
+}
+
+/// Write the environment in the same raw form to an arbitrary writer, for
+/// `--print-env-fd`; no sort check here, that concerns the stdout listing.
+pub(crate) fn write_environment(out: &mut impl Write, line_ending: LineEnding) -> io::Result<()> {
+    write_entries(out, &raw_entries(), line_ending)
+}
+
+fn write_entries(
+    out: &mut impl Write,
+    entries: &[Vec<u8>],
+    line_ending: LineEnding,
+) -> io::Result<()> {
+    for entry in entries {
+        out.write_all(entry)?;
+        write!(out, "{line_ending}")?;
+    }
+    out.flush()
+}
@@ -29,6 +29,12 @@ pub enum ParseError {
         pos: usize,
         msg: String,
     },
+    /// A `${VAR:?message}` expansion hit an unset or empty variable.
+    VariableIsMissing {
+        pos: usize,
+        varname: String,
+        msg: String,
+    },
     InternalError {
         pos: usize,
         sub_err: string_parser::Error,
@@ -0,0 +1,113 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore (vars) openpty winsize TIOCSCTTY TIOCGWINSZ setsid
+
+//! Run a command attached to a freshly allocated pseudo terminal.
+//!
+//! This is a uutils extension: `env --pty COMMAND` forces COMMAND onto an
+//! interactive terminal even when `env` itself runs in a pipeline. The
+//! parent process proxies its own stdin to the pty master and the child's
+//! output from the pty master to its own stdout.
+
+use nix::pty::{openpty, OpenptyResult, Winsize};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus};
+use std::thread;
+
+/// Default size of the allocated pseudo terminal if the size of the
+/// controlling terminal of `env` itself cannot be determined.
+const DEFAULT_WINSIZE: Winsize = Winsize {
+    ws_row: 24,
+    ws_col: 80,
+    ws_xpixel: 0,
+    ws_ypixel: 0,
+};
+
+/// Return the window size of the terminal `env` is running on, falling back
+/// to [`DEFAULT_WINSIZE`] when stdin is not a terminal.
+fn current_winsize() -> Winsize {
+    let mut winsize = DEFAULT_WINSIZE;
+    // SAFETY: the ioctl only writes into the provided winsize struct.
+    let result =
+        unsafe { nix::libc::ioctl(nix::libc::STDIN_FILENO, nix::libc::TIOCGWINSZ, &mut winsize) };
+    if result != 0 || winsize.ws_col == 0 || winsize.ws_row == 0 {
+        winsize = DEFAULT_WINSIZE;
+    }
+    winsize
+}
+
+/// Spawn `cmd` with all three standard streams attached to the slave side of
+/// a new pseudo terminal, proxy stdio and wait for the child to exit.
+///
+/// The pty becomes the controlling terminal of the child, so job control and
+/// `/dev/tty` behave as on a real terminal.
+pub fn run_on_pty(mut cmd: Command) -> io::Result<ExitStatus> {
+    let OpenptyResult { master, slave } = openpty(&current_winsize(), None)?;
+
+    cmd.stdin(File::from(slave.try_clone()?));
+    cmd.stdout(File::from(slave.try_clone()?));
+    cmd.stderr(File::from(slave));
+
+    // SAFETY: the closure only calls async-signal-safe libc functions and
+    // does not allocate.
+    unsafe {
+        cmd.pre_exec(|| {
+            if nix::libc::setsid() < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if nix::libc::ioctl(0, nix::libc::TIOCSCTTY, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = cmd.spawn()?;
+    // close the slave fds held by the Command, otherwise reading from the
+    // master would never report EOF after the child exited
+    drop(cmd);
+
+    // Proxy our stdin to the child's terminal. The thread is deliberately
+    // not joined: it usually blocks in the read on stdin and the process
+    // teardown on exit takes care of it.
+    let mut master_write = File::from(master.try_clone()?);
+    thread::Builder::new()
+        .name("pty_stdin_proxy".to_string())
+        .spawn(move || {
+            let mut stdin = io::stdin();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let count = match stdin.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(count) => count,
+                };
+                if master_write.write_all(&buffer[..count]).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    // Proxy the child's terminal output to our stdout on the main thread.
+    let mut master_read = File::from(master);
+    let mut stdout = io::stdout();
+    let mut buffer = [0u8; 4096];
+    loop {
+        match master_read.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => {
+                stdout.write_all(&buffer[..count])?;
+                stdout.flush()?;
+            }
+            // EIO is reported when the child closed the slave side.
+            Err(e) if e.raw_os_error() == Some(nix::libc::EIO) => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    child.wait()
+}
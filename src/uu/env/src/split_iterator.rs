@@ -19,6 +19,9 @@
 #![forbid(unsafe_code)]
 
 use std::borrow::Cow;
+use std::ffi::OsStr;
+
+use uucore::display::Quotable;
 
 use crate::native_int_str::from_native_int_representation;
 use crate::native_int_str::NativeCharInt;
@@ -27,7 +30,7 @@ use crate::native_int_str::NativeIntString;
 use crate::parse_error::ParseError;
 use crate::string_expander::StringExpander;
 use crate::string_parser::StringParser;
-use crate::variable_parser::VariableParser;
+use crate::variable_parser::{VariableOperator, VariableParser};
 
 const BACKSLASH: char = '\\';
 const DOUBLE_QUOTES: char = '\"';
@@ -52,13 +55,24 @@ const ASCII_WHITESPACE_CHARS: [char; 6] = [' ', '\t', '\r', '\n', '\x0B', '\x0C'
 pub struct SplitIterator<'a> {
     expander: StringExpander<'a>,
     words: Vec<Vec<NativeCharInt>>,
+    /// Report every `${VAR}` expansion on stderr (`-vv` debugging).
+    trace: bool,
+    /// Expand a word-leading `~/` to `$HOME` and `$$` to the process id
+    /// (`--split-string-expand`, a uutils extension).
+    expand_extras: bool,
 }
 
 impl<'a> SplitIterator<'a> {
     pub fn new(s: &'a NativeIntStr) -> Self {
+        Self::with_options(s, false, false)
+    }
+
+    pub fn with_options(s: &'a NativeIntStr, trace: bool, expand_extras: bool) -> Self {
         Self {
             expander: StringExpander::new(s),
             words: Vec::new(),
+            trace,
+            expand_extras,
         }
     }
 
@@ -99,23 +113,104 @@ impl<'a> SplitIterator<'a> {
             parser: self.get_parser_mut(),
         };
 
-        let (name, default) = var_parse.parse_variable()?;
+        let var_pos = var_parse.parser.get_peek_position();
+        let (name, expansion) = var_parse.parse_variable()?;
 
         let varname_os_str_cow = from_native_int_representation(Cow::Borrowed(name));
-        let value = std::env::var_os(varname_os_str_cow);
-        match (&value, default) {
-            (None, None) => {} // do nothing, just replace it with ""
-            (Some(value), _) => {
-                self.expander.put_string(value);
-            }
-            (None, Some(default)) => {
-                self.expander.put_native_string(default);
-            }
+        let value = std::env::var_os(&varname_os_str_cow);
+
+        // which substitution the operator picks, so the trace and the output
+        // always agree
+        enum Substitution<'s> {
+            Value(&'s OsStr),
+            Word(&'s NativeIntStr),
+            Empty,
+        }
+
+        let substitution = match expansion {
+            None => match &value {
+                Some(value) => Substitution::Value(value),
+                None => Substitution::Empty,
+            },
+            Some((VariableOperator::UseDefault { colon }, word)) => match &value {
+                Some(value) if !(colon && value.is_empty()) => Substitution::Value(value),
+                _ => Substitution::Word(word),
+            },
+            Some((VariableOperator::UseAlternative { colon }, word)) => match &value {
+                Some(value) if !(colon && value.is_empty()) => Substitution::Word(word),
+                _ => Substitution::Empty,
+            },
+            Some((VariableOperator::ErrorIfMissing, word)) => match &value {
+                Some(value) if !value.is_empty() => Substitution::Value(value),
+                _ => {
+                    let msg = from_native_int_representation(Cow::Borrowed(word));
+                    return Err(ParseError::VariableIsMissing {
+                        pos: var_pos,
+                        varname: varname_os_str_cow.to_string_lossy().into_owned(),
+                        msg: if msg.is_empty() {
+                            "parameter null or not set".into()
+                        } else {
+                            msg.to_string_lossy().into_owned()
+                        },
+                    });
+                }
+            },
+        };
+
+        if self.trace {
+            let (source, traced_value) = match &substitution {
+                Substitution::Value(value) => ("environment", value.quote().to_string()),
+                Substitution::Word(word) => (
+                    "default",
+                    from_native_int_representation(Cow::Borrowed(word))
+                        .quote()
+                        .to_string(),
+                ),
+                Substitution::Empty => ("unset", "''".to_string()),
+            };
+            eprintln!(
+                "expanding ${{{}}} ({source}) -> {traced_value}",
+                varname_os_str_cow.to_string_lossy()
+            );
+        }
+
+        match substitution {
+            Substitution::Value(value) => self.expander.put_string(value),
+            Substitution::Word(word) => self.expander.put_native_string(word),
+            Substitution::Empty => {} // do nothing, just replace it with ""
         };
 
         Ok(())
     }
 
+    /// Is the parser standing on a `$$` sequence?
+    fn at_dollar_dollar(&self) -> bool {
+        let parser = self.get_parser();
+        matches!(
+            parser.peek_char_at_pointer(parser.get_peek_position() + 1),
+            Ok(DOLLAR)
+        )
+    }
+
+    /// Replace `$$` with the current process id, like a shell would.
+    fn substitute_process_id(&mut self) -> Result<(), ParseError> {
+        self.skip_one()?;
+        self.skip_one()?;
+        self.expander.put_string(std::process::id().to_string());
+        Ok(())
+    }
+
+    /// Replace a word-leading `~/` with `$HOME`; any other `~` stays
+    /// literal, as does a `~/` when `HOME` is not set.
+    fn substitute_tilde(&mut self) -> Result<(), ParseError> {
+        self.skip_one()?;
+        match (self.get_current_char(), std::env::var_os("HOME")) {
+            (Some('/'), Some(home)) => self.expander.put_string(home),
+            _ => self.push_char_to_word('~'),
+        }
+        Ok(())
+    }
+
     fn check_and_replace_ascii_escape_code(&mut self, c: char) -> Result<bool, ParseError> {
         if let Some(replace) = REPLACEMENTS.iter().find(|&x| x.0 == c) {
             self.skip_one()?;
@@ -158,6 +253,10 @@ impl<'a> SplitIterator<'a> {
                 Some(c) if ASCII_WHITESPACE_CHARS.contains(&c) => {
                     self.skip_one()?;
                 }
+                Some('~') if self.expand_extras => {
+                    self.substitute_tilde()?;
+                    self.state_unquoted()?;
+                }
                 Some(_) => {
                     // Don't consume char. Will be done in unquoted state.
                     self.state_unquoted()?;
@@ -194,6 +293,9 @@ impl<'a> SplitIterator<'a> {
                     self.push_word_to_words();
                     return Err(ParseError::ReachedEnd);
                 }
+                Some(DOLLAR) if self.expand_extras && self.at_dollar_dollar() => {
+                    self.substitute_process_id()?;
+                }
                 Some(DOLLAR) => {
                     self.substitute_variable()?;
                 }
@@ -308,6 +410,9 @@ impl<'a> SplitIterator<'a> {
                         c: '"',
                     })
                 }
+                Some(DOLLAR) if self.expand_extras && self.at_dollar_dollar() => {
+                    self.substitute_process_id()?;
+                }
                 Some(DOLLAR) => {
                     self.substitute_variable()?;
                 }
@@ -370,6 +475,14 @@ impl<'a> SplitIterator<'a> {
 }
 
 pub fn split(s: &NativeIntStr) -> Result<Vec<NativeIntString>, ParseError> {
-    let splitted_args = SplitIterator::new(s).split()?;
+    split_with_options(s, false, false)
+}
+
+pub fn split_with_options(
+    s: &NativeIntStr,
+    trace: bool,
+    expand_extras: bool,
+) -> Result<Vec<NativeIntString>, ParseError> {
+    let splitted_args = SplitIterator::with_options(s, trace, expand_extras).split()?;
     Ok(splitted_args)
 }
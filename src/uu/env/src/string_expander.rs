@@ -10,8 +10,10 @@ use std::{
 };
 
 use crate::{
-    native_int_str::{to_native_int_representation, NativeCharInt, NativeIntStr},
-    string_parser::{Chunk, Error, StringParser},
+    native_int_str::{
+        get_single_native_int_value, to_native_int_representation, NativeCharInt, NativeIntStr,
+    },
+    string_parser::{Error, StringParser},
 };
 
 /// This class makes parsing and word collection more convenient.
@@ -62,19 +64,18 @@ impl<'a> StringExpander<'a> {
     }
 
     pub fn take_one(&mut self) -> Result<(), Error> {
-        let chunks = self.parser.consume_one_ascii_or_all_non_ascii()?;
-        for chunk in chunks {
-            match chunk {
-                Chunk::InvalidEncoding(invalid) => self.output.extend(invalid),
-                Chunk::ValidSingleIntChar((_c, ni)) => self.output.push(ni),
-            }
-        }
+        let range = self.parser.consume_one_ascii_or_all_non_ascii()?;
+        self.output.extend_from_slice(self.parser.substring(&range));
         Ok(())
     }
 
     pub fn put_one_char(&mut self, c: char) {
-        let os_str = OsString::from(c.to_string());
-        self.put_string(os_str);
+        if let Some(ni) = get_single_native_int_value(&c) {
+            self.output.push(ni);
+        } else {
+            let os_str = OsString::from(c.to_string());
+            self.put_string(os_str);
+        }
     }
 
     pub fn put_string<S: AsRef<OsStr>>(&mut self, os_str: S) {
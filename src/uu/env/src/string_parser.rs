@@ -6,7 +6,7 @@
 // spell-checker:ignore (words) splitted FFFD
 #![forbid(unsafe_code)]
 
-use std::{borrow::Cow, ffi::OsStr};
+use std::{borrow::Cow, ffi::OsStr, ops::Range};
 
 use crate::native_int_str::{
     from_native_int_representation, get_char_from_native_int, get_single_native_int_value,
@@ -37,21 +37,18 @@ pub enum Chunk<'a> {
 /// This class makes parsing a OsString char by char more convenient.
 ///
 /// It also allows to capturing of intermediate positions for later splitting.
+///
+/// Internally, only a byte offset into the input is tracked. All slicing
+/// is derived from that offset on demand, so advancing the parser is O(1)
+/// and never rescans the already consumed part of the input.
 pub struct StringParser<'a> {
     input: &'a NativeIntStr,
     pointer: usize,
-    remaining: &'a NativeIntStr,
 }
 
 impl<'a> StringParser<'a> {
     pub fn new(input: &'a NativeIntStr) -> Self {
-        let mut instance = Self {
-            input,
-            pointer: 0,
-            remaining: input,
-        };
-        instance.set_pointer(0);
-        instance
+        Self { input, pointer: 0 }
     }
 
     pub fn new_at(input: &'a NativeIntStr, pos: usize) -> Self {
@@ -126,23 +123,27 @@ impl<'a> StringParser<'a> {
         Ok(chunk)
     }
 
-    pub fn consume_one_ascii_or_all_non_ascii(&mut self) -> Result<Vec<Chunk<'a>>, Error> {
-        let mut result = Vec::<Chunk<'a>>::new();
+    /// Consumes one ASCII char or all consecutive non-ASCII chunks up to
+    /// the next ASCII char or the end of input.
+    ///
+    /// The consumed chunks are not collected. Instead the consumed range
+    /// is returned, so the caller can extract it in one piece via
+    /// [`StringParser::substring`] if needed. This keeps consuming
+    /// allocation free, which matters for very long `-S` strings.
+    pub fn consume_one_ascii_or_all_non_ascii(&mut self) -> Result<Range<usize>, Error> {
+        let start = self.pointer;
         loop {
-            let data = self.consume_chunk()?;
-            let was_ascii = if let Chunk::ValidSingleIntChar((c, _ni)) = &data {
-                c.is_ascii()
-            } else {
-                false
-            };
-            result.push(data);
+            let chunk = self.consume_chunk()?;
+            let was_ascii = matches!(&chunk, Chunk::ValidSingleIntChar((c, _ni)) if c.is_ascii());
             if was_ascii {
-                return Ok(result);
+                return Ok(start..self.pointer);
             }
 
             match self.peek_chunk() {
-                Some(Chunk::ValidSingleIntChar((c, _ni))) if c.is_ascii() => return Ok(result),
-                None => return Ok(result),
+                Some(Chunk::ValidSingleIntChar((c, _ni))) if c.is_ascii() => {
+                    return Ok(start..self.pointer)
+                }
+                None => return Ok(start..self.pointer),
                 _ => {}
             }
         }
@@ -155,7 +156,9 @@ impl<'a> StringParser<'a> {
 
     pub fn skip_until_char_or_end(&mut self, c: char) {
         let native_rep = get_single_native_int_value(&c).unwrap();
-        let pos = self.remaining.iter().position(|x| *x == native_rep);
+        let pos = self.input[self.pointer..]
+            .iter()
+            .position(|x| *x == native_rep);
 
         if let Some(pos) = pos {
             self.set_pointer(self.pointer + pos);
@@ -171,12 +174,10 @@ impl<'a> StringParser<'a> {
     }
 
     pub fn peek_remaining(&self) -> Cow<'a, OsStr> {
-        from_native_int_representation(Cow::Borrowed(self.remaining))
+        from_native_int_representation(Cow::Borrowed(&self.input[self.pointer..]))
     }
 
     pub fn set_pointer(&mut self, new_pointer: usize) {
         self.pointer = new_pointer;
-        let (_before, after) = self.input.split_at(self.pointer);
-        self.remaining = after;
     }
 }
@@ -7,6 +7,24 @@ use std::ops::Range;
 
 use crate::{native_int_str::NativeIntStr, parse_error::ParseError, string_parser::StringParser};
 
+/// How `${VAR<op>word}` treats the variable's state, following the POSIX
+/// shell forms. The `colon` variants additionally treat a set-but-empty
+/// variable as if it was unset.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VariableOperator {
+    /// `-` and `:-` (and the legacy bare `:`): substitute WORD when the
+    /// variable is missing.
+    UseDefault { colon: bool },
+    /// `+` and `:+`: substitute WORD when the variable is present.
+    UseAlternative { colon: bool },
+    /// `:?`: fail with WORD as the error message when unset or empty.
+    ErrorIfMissing,
+}
+
+/// An expansion operator plus the WORD it applies to, e.g. the `:-fallback`
+/// part of `${VAR:-fallback}`.
+pub type VariableExpansion<'a> = (VariableOperator, &'a NativeIntStr);
+
 pub struct VariableParser<'a, 'b> {
     pub parser: &'b mut StringParser<'a>,
 }
@@ -34,12 +52,13 @@ impl<'a, 'b> VariableParser<'a, 'b> {
 
     fn parse_braced_variable_name(
         &mut self,
-    ) -> Result<(&'a NativeIntStr, Option<&'a NativeIntStr>), ParseError> {
+    ) -> Result<(&'a NativeIntStr, Option<VariableExpansion<'a>>), ParseError> {
         let pos_start = self.parser.get_peek_position();
 
         self.check_variable_name_start()?;
 
-        let (varname_end, default_end);
+        let varname_end;
+        let operator;
         loop {
             match self.get_current_char() {
                 None => {
@@ -51,55 +70,85 @@ impl<'a, 'b> VariableParser<'a, 'b> {
                 }
                 Some(':') => {
                     varname_end = self.parser.get_peek_position();
-                    loop {
-                        match self.get_current_char() {
-                            None => {
-                                return Err(ParseError::ParsingOfVariableNameFailed {
-                                    pos: self.parser.get_peek_position(),
-                                    msg: "Missing closing brace after default value".into() })
-                            },
-                            Some('}') => {
-                                default_end = Some(self.parser.get_peek_position());
-                                self.skip_one()?;
-                                break
-                            },
-                            Some(_) => {
-                                self.skip_one()?;
-                            },
+                    self.skip_one()?;
+                    operator = match self.get_current_char() {
+                        Some('-') => {
+                            self.skip_one()?;
+                            VariableOperator::UseDefault { colon: true }
                         }
-                    }
+                        Some('+') => {
+                            self.skip_one()?;
+                            VariableOperator::UseAlternative { colon: true }
+                        }
+                        Some('?') => {
+                            self.skip_one()?;
+                            VariableOperator::ErrorIfMissing
+                        }
+                        // legacy form: `${VAR:word}` behaves like `${VAR:-word}`
+                        _ => VariableOperator::UseDefault { colon: true },
+                    };
                     break;
                 },
-                Some('}') => {
+                Some(c @ ('-' | '+')) => {
                     varname_end = self.parser.get_peek_position();
-                    default_end = None;
                     self.skip_one()?;
+                    operator = if c == '-' {
+                        VariableOperator::UseDefault { colon: false }
+                    } else {
+                        VariableOperator::UseAlternative { colon: false }
+                    };
                     break;
                 },
+                Some('}') => {
+                    varname_end = self.parser.get_peek_position();
+                    self.skip_one()?;
+                    let varname = self.parser.substring(&Range {
+                        start: pos_start,
+                        end: varname_end,
+                    });
+                    return Ok((varname, None));
+                },
                 Some(c) => {
                     return Err(ParseError::ParsingOfVariableNameFailed {
                         pos: self.parser.get_peek_position(),
-                        msg: format!("Unexpected character: '{}', expected a closing brace ('}}') or colon (':')", c)
+                        msg: format!("Unexpected character: '{}', expected a closing brace ('}}') or an operator (':', '-', '+')", c)
                     })
                 },
             };
         }
 
-        let default_opt = if let Some(default_end) = default_end {
-            Some(self.parser.substring(&Range {
-                start: varname_end + 1,
-                end: default_end,
-            }))
-        } else {
-            None
-        };
+        let word_start = self.parser.get_peek_position();
+        let word_end;
+        loop {
+            match self.get_current_char() {
+                None => {
+                    return Err(ParseError::ParsingOfVariableNameFailed {
+                        pos: self.parser.get_peek_position(),
+                        msg: "Missing closing brace after default value".into(),
+                    })
+                }
+                Some('}') => {
+                    word_end = self.parser.get_peek_position();
+                    self.skip_one()?;
+                    break;
+                }
+                Some(_) => {
+                    self.skip_one()?;
+                }
+            }
+        }
+
+        let word = self.parser.substring(&Range {
+            start: word_start,
+            end: word_end,
+        });
 
         let varname = self.parser.substring(&Range {
             start: pos_start,
             end: varname_end,
         });
 
-        Ok((varname, default_opt))
+        Ok((varname, Some((operator, word))))
     }
 
     fn parse_unbraced_variable_name(&mut self) -> Result<&'a NativeIntStr, ParseError> {
@@ -136,10 +185,10 @@ impl<'a, 'b> VariableParser<'a, 'b> {
 
     pub fn parse_variable(
         &mut self,
-    ) -> Result<(&'a NativeIntStr, Option<&'a NativeIntStr>), ParseError> {
+    ) -> Result<(&'a NativeIntStr, Option<VariableExpansion<'a>>), ParseError> {
         self.skip_one()?;
 
-        let (name, default) = match self.get_current_char() {
+        let (name, expansion) = match self.get_current_char() {
             None => {
                 return Err(ParseError::ParsingOfVariableNameFailed {
                     pos: self.parser.get_peek_position(),
@@ -153,6 +202,6 @@ impl<'a, 'b> VariableParser<'a, 'b> {
             Some(_) => (self.parse_unbraced_variable_name()?, None),
         };
 
-        Ok((name, default))
+        Ok((name, expansion))
     }
 }
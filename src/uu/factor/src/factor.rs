@@ -7,41 +7,81 @@
 
 use std::collections::BTreeMap;
 use std::io::BufRead;
-use std::io::{self, stdin, stdout, Write};
+use std::io::{self, stdin, stdout, IsTerminal, Write};
+use std::time::Instant;
 
 use clap::{crate_version, Arg, ArgAction, Command};
 use num_bigint::BigUint;
-use num_traits::FromPrimitive;
+use num_traits::{FromPrimitive, ToPrimitive};
 use uucore::display::Quotable;
 use uucore::error::{set_exit_code, FromIo, UResult, USimpleError};
-use uucore::{format_usage, help_about, help_usage, show_error, show_warning};
+use uucore::{format_usage, help_about, help_usage, show_error};
 
 const ABOUT: &str = help_about!("factor.md");
 const USAGE: &str = help_usage!("factor.md");
 
+mod prechecks;
+mod range;
+mod repl;
+pub mod rho;
+pub mod trial;
+
 mod options {
     pub static EXPONENTS: &str = "exponents";
     pub static HELP: &str = "help";
     pub static NUMBER: &str = "NUMBER";
+    pub static ALGORITHM: &str = "algorithm";
+    pub static RANGE: &str = "range";
+    pub static SMOOTH_BOUND: &str = "smooth-bound";
+    pub static TIMING: &str = "timing";
 }
 
 fn print_factors_str(
     num_str: &str,
     w: &mut io::BufWriter<impl io::Write>,
     print_exponents: bool,
+    timing: bool,
+    smooth_bound: Option<u64>,
+    algorithm: Algorithm,
 ) -> UResult<()> {
     let rx = num_str.trim().parse::<num_bigint::BigUint>();
     let Ok(x) = rx else {
-        // return Ok(). it's non-fatal and we should try the next number.
-        show_warning!("{}: {}", num_str.maybe_quote(), rx.unwrap_err());
+        // non-fatal: report the token like GNU does and try the next number
+        show_error!("{} is not a valid positive integer", num_str.quote());
         set_exit_code(1);
         return Ok(());
     };
 
-    let (factorization, remaining) = if x > BigUint::from_u32(1).unwrap() {
-        num_prime::nt_funcs::factors(x.clone(), None)
-    } else {
+    if let Some(bound) = smooth_bound {
+        let verdict = if prechecks::is_smooth(&x, bound) {
+            "smooth"
+        } else {
+            "not smooth"
+        };
+        writeln!(w, "{x}: {verdict}").map_err_context(|| "write error".into())?;
+        return Ok(());
+    }
+
+    // inputs beyond 64 bits can take long enough in the backend that the
+    // small factors are worth seeing immediately
+    if x.to_u64().is_none() {
+        return stream_big_factors(&x, w, print_exponents, timing, algorithm);
+    }
+
+    // time only the factorization itself, not the parsing or the output
+    let timing_start = timing.then(Instant::now);
+
+    let (factorization, remaining) = if x <= BigUint::from_u32(1).unwrap() {
         (BTreeMap::new(), None)
+    } else if let Some((base, exp)) = prechecks::perfect_power(&x) {
+        // factor only the much smaller base and scale the exponents
+        let (mut factorization, remaining) = factor_general(&base, algorithm);
+        for exponent in factorization.values_mut() {
+            *exponent *= exp;
+        }
+        (factorization, remaining)
+    } else {
+        factor_general(&x, algorithm)
     };
 
     if let Some(_remaining) = remaining {
@@ -51,31 +91,167 @@ fn print_factors_str(
         ));
     }
 
-    write_result(w, x, factorization, print_exponents).map_err_context(|| "write error".into())?;
+    let timing_micros = timing_start.map(|start| start.elapsed().as_micros());
+    write_result(w, x, factorization, print_exponents, timing_micros)
+        .map_err_context(|| "write error".into())?;
 
     Ok(())
 }
 
+/// The backend finishing off the cofactor that trial division leaves behind
+/// (`--algorithm`). `Rho` is our own Brent-cycle Pollard rho and the default
+/// for 64-bit inputs; `NumPrime` is the general machinery of the num_prime
+/// crate, which escalates to ECM-class algorithms and always handles inputs
+/// beyond 64 bits.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Auto,
+    Rho,
+    NumPrime,
+}
+
+impl Algorithm {
+    fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("rho") => Self::Rho,
+            Some("num-prime") => Self::NumPrime,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Factor `x > 1`: trial division strips the small factors first, so the
+/// selected backend only sees the (often prime) cofactor.
+fn factor_general(
+    x: &BigUint,
+    algorithm: Algorithm,
+) -> (BTreeMap<BigUint, usize>, Option<Vec<BigUint>>) {
+    if let Some(n) = x.to_u64() {
+        let (small, cofactor) = trial::partial_factor(n);
+        let mut factorization: BTreeMap<BigUint, usize> = small
+            .into_iter()
+            .map(|(factor, exponent)| (BigUint::from_u64(factor).unwrap(), exponent))
+            .collect();
+        let mut remaining = None;
+        if cofactor > 1 {
+            if algorithm == Algorithm::NumPrime {
+                let (completed, rest) =
+                    num_prime::nt_funcs::factors(BigUint::from_u64(cofactor).unwrap(), None);
+                for (factor, exponent) in completed {
+                    *factorization.entry(factor).or_insert(0) += exponent;
+                }
+                remaining = rest;
+            } else {
+                for (factor, exponent) in rho::factor(cofactor) {
+                    *factorization
+                        .entry(BigUint::from_u64(factor).unwrap())
+                        .or_insert(0) += exponent;
+                }
+            }
+        }
+        (factorization, remaining)
+    } else {
+        // beyond 64 bits only num_prime applies, whatever was selected
+        num_prime::nt_funcs::factors(x.clone(), None)
+    }
+}
+
 fn write_result(
     w: &mut io::BufWriter<impl Write>,
     x: BigUint,
     factorization: BTreeMap<BigUint, usize>,
     print_exponents: bool,
+    timing_micros: Option<u128>,
 ) -> io::Result<()> {
     write!(w, "{x}:")?;
     for (factor, n) in factorization {
-        if print_exponents {
-            if n > 1 {
-                write!(w, " {}^{}", factor, n)?;
-            } else {
-                write!(w, " {}", factor)?;
+        write_factor(w, &factor, n, print_exponents)?;
+    }
+    if let Some(micros) = timing_micros {
+        write!(w, "\t{micros}us")?;
+    }
+    writeln!(w)?;
+    w.flush()
+}
+
+/// Write one factor (as `factor^exponent` with `--exponents`) of the current
+/// output line.
+fn write_factor(
+    w: &mut io::BufWriter<impl Write>,
+    factor: &BigUint,
+    exponent: usize,
+    print_exponents: bool,
+) -> io::Result<()> {
+    if print_exponents {
+        if exponent > 1 {
+            write!(w, " {factor}^{exponent}")
+        } else {
+            write!(w, " {factor}")
+        }
+    } else {
+        w.write_all(format!(" {factor}").repeat(exponent).as_bytes())
+    }
+}
+
+/// Factor an input beyond 64 bits, streaming every factor as soon as it is
+/// known instead of assembling the whole line first; the backend can take a
+/// long time on such inputs, and the cheap trial-division factors are often
+/// the interesting ones. Trial division reports its factors in increasing
+/// order and the backend only sees the remaining cofactor, so the line still
+/// comes out sorted.
+fn stream_big_factors(
+    x: &BigUint,
+    w: &mut io::BufWriter<impl Write>,
+    print_exponents: bool,
+    timing: bool,
+    algorithm: Algorithm,
+) -> UResult<()> {
+    let timing_start = timing.then(Instant::now);
+
+    write!(w, "{x}:").map_err_context(|| "write error".into())?;
+    w.flush().map_err_context(|| "write error".into())?;
+
+    let (small, rest) = trial::partial_factor_big(x);
+    for (factor, exponent) in small {
+        let factor = BigUint::from_u64(factor).unwrap();
+        write_factor(w, &factor, exponent, print_exponents)
+            .and_then(|()| w.flush())
+            .map_err_context(|| "write error".into())?;
+    }
+
+    let mut remaining = None;
+    if rest > BigUint::from_u32(1).unwrap() {
+        let (factorization, incomplete) = if let Some((base, exp)) = prechecks::perfect_power(&rest)
+        {
+            let (mut factorization, incomplete) = factor_general(&base, algorithm);
+            for exponent in factorization.values_mut() {
+                *exponent *= exp;
             }
+            (factorization, incomplete)
         } else {
-            w.write_all(format!(" {}", factor).repeat(n).as_bytes())?;
+            factor_general(&rest, algorithm)
+        };
+        remaining = incomplete;
+        for (factor, exponent) in factorization {
+            write_factor(w, &factor, exponent, print_exponents)
+                .and_then(|()| w.flush())
+                .map_err_context(|| "write error".into())?;
         }
     }
-    writeln!(w)?;
-    w.flush()
+
+    if let Some(micros) = timing_start.map(|start| start.elapsed().as_micros()) {
+        write!(w, "\t{micros}us").map_err_context(|| "write error".into())?;
+    }
+    writeln!(w).map_err_context(|| "write error".into())?;
+    w.flush().map_err_context(|| "write error".into())?;
+
+    if remaining.is_some() {
+        return Err(USimpleError::new(
+            1,
+            "Factorization incomplete. Remainders exists.",
+        ));
+    }
+    Ok(())
 }
 
 #[uucore::main]
@@ -84,15 +260,70 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
 
     // If matches find --exponents flag than variable print_exponents is true and p^e output format will be used.
     let print_exponents = matches.get_flag(options::EXPONENTS);
+    let timing = matches.get_flag(options::TIMING);
+    let smooth_bound = matches.get_one::<u64>(options::SMOOTH_BOUND).copied();
+    let algorithm = Algorithm::from_name(
+        matches
+            .get_one::<String>(options::ALGORITHM)
+            .map(|s| s.as_str()),
+    );
 
     let stdout = stdout();
     // We use a smaller buffer here to pass a gnu test. 4KiB appears to be the default pipe size for bash.
     let mut w = io::BufWriter::with_capacity(4 * 1024, stdout.lock());
 
-    if let Some(values) = matches.get_many::<String>(options::NUMBER) {
+    if let Some(range) = matches.get_one::<String>(options::RANGE) {
+        let (start, end) = range::parse(range).map_err(|e| USimpleError::new(1, e))?;
+        range::run(
+            start,
+            end,
+            |cofactor, factorization| {
+                // The sieve removed all small primes; finish off what is left.
+                let (completed, _remaining) = num_prime::nt_funcs::factors(cofactor, None);
+                for (factor, n) in completed {
+                    *factorization.entry(factor).or_insert(0) += n;
+                }
+            },
+            |x, factorization| {
+                let factorization = factorization
+                    .into_iter()
+                    .map(|(factor, n)| (BigUint::from_u64(factor).unwrap(), n))
+                    .collect();
+                write_result(
+                    &mut w,
+                    BigUint::from_u64(x).unwrap(),
+                    factorization,
+                    print_exponents,
+                    None,
+                )
+                .map_err_context(|| "write error".into())
+            },
+        )?;
+    } else if let Some(values) = matches.get_many::<String>(options::NUMBER) {
         for number in values {
-            print_factors_str(number, &mut w, print_exponents)?;
+            print_factors_str(
+                number,
+                &mut w,
+                print_exponents,
+                timing,
+                smooth_bound,
+                algorithm,
+            )?;
         }
+    } else if stdin().is_terminal() {
+        repl::run(|line| {
+            for number in line.split_whitespace() {
+                print_factors_str(
+                    number,
+                    &mut w,
+                    print_exponents,
+                    timing,
+                    smooth_bound,
+                    algorithm,
+                )?;
+            }
+            w.flush().map_err_context(|| "write error".into())
+        })?;
     } else {
         let stdin = stdin();
         let lines = stdin.lock().lines();
@@ -100,7 +331,14 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             match line {
                 Ok(line) => {
                     for number in line.split_whitespace() {
-                        print_factors_str(number, &mut w, print_exponents)?;
+                        print_factors_str(
+                            number,
+                            &mut w,
+                            print_exponents,
+                            timing,
+                            smooth_bound,
+                            algorithm,
+                        )?;
                     }
                 }
                 Err(e) => {
@@ -135,6 +373,49 @@ pub fn uu_app() -> Command {
                 .help("Print factors in the form p^e")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::RANGE)
+                .long(options::RANGE)
+                .value_name("A..B")
+                .conflicts_with(options::NUMBER)
+                .help(
+                    "Factor every integer from A to B (inclusive) using a windowed \
+                    sieve (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::ALGORITHM)
+                .long(options::ALGORITHM)
+                .value_name("NAME")
+                .value_parser(["auto", "rho", "num-prime"])
+                .help(
+                    "select the backend that factors the cofactor left by trial \
+                    division: our Pollard rho, the num_prime machinery, or an \
+                    automatic choice; a debug aid (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::SMOOTH_BOUND)
+                .long(options::SMOOTH_BOUND)
+                .value_name("B")
+                .value_parser(clap::value_parser!(u64).range(2..))
+                .conflicts_with(options::RANGE)
+                .help(
+                    "report for each number only whether it is B-smooth, i.e. \
+                    has no prime factor greater than B, instead of factoring it \
+                    (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::TIMING)
+                .long(options::TIMING)
+                .conflicts_with(options::RANGE)
+                .help(
+                    "append the time spent factoring each number as a trailing \
+                    column, in microseconds (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::HELP)
                 .long(options::HELP)
@@ -0,0 +1,120 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Cheap pre-checks layered in front of the general factorization
+//! machinery: perfect-power detection, and the B-smoothness test behind
+//! `--smooth-bound`.
+
+use num_bigint::BigUint;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+
+/// Decompose `x` into `base^exp` with the largest possible exponent, if
+/// `x >= 4` is a perfect power; the returned base is then not a perfect
+/// power itself. One `nth_root`/`pow` round trip decides each candidate
+/// exponent, so the check costs O(log x) root extractions — negligible next
+/// to factoring, while factoring only the much smaller base.
+pub fn perfect_power(x: &BigUint) -> Option<(BigUint, usize)> {
+    let bits = x.bits();
+    // a perfect power with a base of at least 2 needs more than `exp` bits
+    for exp in (2..bits).rev() {
+        let Ok(exp) = u32::try_from(exp) else {
+            continue;
+        };
+        let root = x.nth_root(exp);
+        if &root.pow(exp) == x {
+            return Some((root, exp as usize));
+        }
+    }
+    None
+}
+
+/// Is `x` `bound`-smooth, i.e. free of prime factors greater than `bound`?
+/// Numbers up to 1 are trivially smooth. Divides out the candidates up to
+/// the bound instead of factoring completely, so a verdict never depends on
+/// the hardness of some large cofactor.
+pub fn is_smooth(x: &BigUint, bound: u64) -> bool {
+    let mut x = x.clone();
+    let mut divisor = 2u64;
+    loop {
+        // whatever is left has no factor larger than itself
+        if x.to_u64().is_some_and(|rest| rest <= bound) {
+            return true;
+        }
+        if divisor > bound {
+            return false;
+        }
+        let d = BigUint::from_u64(divisor).unwrap();
+        while (&x % &d).is_zero() {
+            x /= &d;
+        }
+        divisor += if divisor == 2 { 1 } else { 2 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    /// The largest prime factor of `n`, by exhaustive trial division.
+    fn largest_prime_factor(mut n: u64) -> u64 {
+        let mut largest = 1;
+        let mut d = 2;
+        while d * d <= n {
+            while n % d == 0 {
+                largest = largest.max(d);
+                n /= d;
+            }
+            d += 1;
+        }
+        if n > 1 {
+            largest = largest.max(n);
+        }
+        largest
+    }
+
+    #[test]
+    fn test_small_numbers_are_no_perfect_powers() {
+        for n in 0u32..4 {
+            assert_eq!(perfect_power(&BigUint::from(n)), None, "{n}");
+        }
+    }
+
+    #[test]
+    fn test_largest_exponent_wins() {
+        // 64 = 2^6, not 8^2 or 4^3
+        assert_eq!(
+            perfect_power(&BigUint::from(64u32)),
+            Some((BigUint::from(2u32), 6))
+        );
+    }
+
+    quickcheck! {
+        fn constructed_powers_are_detected(base: u8, exp: u8) -> bool {
+            let base = u64::from(base) + 2;
+            let exp = u32::from(exp % 5) + 2;
+            let x = BigUint::from(base).pow(exp);
+            match perfect_power(&x) {
+                Some((b, e)) => b.pow(u32::try_from(e).unwrap()) == x && e >= 2,
+                None => false,
+            }
+        }
+
+        fn detected_powers_round_trip(n: u64) -> bool {
+            let x = BigUint::from(n);
+            match perfect_power(&x) {
+                Some((b, e)) => e >= 2 && b.pow(u32::try_from(e).unwrap()) == x,
+                None => true,
+            }
+        }
+
+        fn smoothness_matches_the_largest_prime_factor(n: u64, bound: u64) -> bool {
+            // kept small so the reference trial division stays fast
+            let n = n % 1_000_000;
+            let bound = bound % 1_000 + 2;
+            is_smooth(&BigUint::from(n), bound) == (largest_prime_factor(n) <= bound)
+        }
+    }
+}
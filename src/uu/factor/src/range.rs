@@ -0,0 +1,240 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Factoring of whole ranges of integers (`--range A..B`).
+//!
+//! Instead of factoring every number of the range independently, a windowed
+//! sieve strips all small prime factors from a block of consecutive numbers
+//! at once. Only the (rare) cofactors that survive the sieve are handed to
+//! the general factoring routine. Memory use is bounded by the window size,
+//! so arbitrarily large ranges can be streamed.
+
+use std::collections::BTreeMap;
+
+use uucore::error::UResult;
+
+/// Largest prime removed by the sieve. Any cofactor left after sieving is
+/// either 1, a prime, or a product of primes above this limit.
+const SMALL_PRIME_LIMIT: u64 = 1 << 16;
+
+/// Numbers processed per sieve window.
+const WINDOW_SIZE: u64 = 1 << 16;
+
+/// Parses `A..B` into an inclusive pair of bounds.
+pub fn parse(s: &str) -> Result<(u64, u64), String> {
+    let parse_bound = |bound: &str| {
+        bound
+            .parse::<u64>()
+            .map_err(|_| format!("invalid range '{s}': expected A..B with unsigned integers"))
+    };
+    let Some((start, end)) = s.split_once("..") else {
+        return Err(format!("invalid range '{s}': expected A..B"));
+    };
+    let (start, end) = (parse_bound(start)?, parse_bound(end)?);
+    if start > end {
+        return Err(format!("invalid range '{s}': {start} is larger than {end}"));
+    }
+    Ok((start, end))
+}
+
+/// Factors every integer in `start..=end`, in order, calling `on_result`
+/// with the number and its factorization (empty for 0 and 1). Cofactors not
+/// handled by the sieve are completed by `complete`, which receives the
+/// cofactor and appends its factors to the map.
+pub fn run(
+    start: u64,
+    end: u64,
+    complete: impl Fn(u64, &mut BTreeMap<u64, usize>),
+    mut on_result: impl FnMut(u64, BTreeMap<u64, usize>) -> UResult<()>,
+) -> UResult<()> {
+    let small_primes = sieve_small_primes();
+
+    let mut lo = start;
+    loop {
+        let hi = lo.saturating_add(WINDOW_SIZE - 1).min(end);
+        let len = (hi - lo + 1) as usize;
+        let mut residuals: Vec<u64> = (lo..=hi).collect();
+        // 0 and 1 have no prime factorization; a residual of 1 keeps both
+        // the sieve and the completion step away from them.
+        if lo == 0 {
+            residuals[0] = 1;
+        }
+        let mut factorizations: Vec<BTreeMap<u64, usize>> = vec![BTreeMap::new(); len];
+
+        for &p in &small_primes {
+            // First multiple of p at or after the window start.
+            let rem = lo % p;
+            let Some(mut multiple) = (if rem == 0 {
+                Some(lo)
+            } else {
+                lo.checked_add(p - rem)
+            }) else {
+                continue;
+            };
+            while multiple <= hi {
+                let idx = (multiple - lo) as usize;
+                let mut exponent = 0;
+                while residuals[idx] % p == 0 {
+                    residuals[idx] /= p;
+                    exponent += 1;
+                }
+                if exponent > 0 {
+                    factorizations[idx].insert(p, exponent);
+                }
+                let Some(next) = multiple.checked_add(p) else {
+                    break;
+                };
+                multiple = next;
+            }
+        }
+
+        for (idx, mut factorization) in factorizations.into_iter().enumerate() {
+            let residual = residuals[idx];
+            if residual > 1 {
+                complete(residual, &mut factorization);
+            }
+            on_result(lo + idx as u64, factorization)?;
+        }
+
+        if hi == end {
+            return Ok(());
+        }
+        lo = hi + 1;
+    }
+}
+
+/// Returns all primes below [`SMALL_PRIME_LIMIT`] via a plain sieve of
+/// Eratosthenes.
+fn sieve_small_primes() -> Vec<u64> {
+    let limit = SMALL_PRIME_LIMIT as usize;
+    let mut is_prime = vec![true; limit];
+    is_prime[0] = false;
+    is_prime[1] = false;
+    for n in 2..limit {
+        if is_prime[n] {
+            for multiple in (n * n..limit).step_by(n) {
+                is_prime[multiple] = false;
+            }
+        }
+    }
+    (2..limit)
+        .filter(|&n| is_prime[n])
+        .map(|n| n as u64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{quickcheck, TestResult};
+
+    /// Factors a single number through the sieve path, completing leftover
+    /// cofactors with `num_prime` exactly like `--range` does.
+    fn factorization(n: u64) -> BTreeMap<u64, usize> {
+        let mut result = BTreeMap::new();
+        run(
+            n,
+            n,
+            |cofactor, factorization| {
+                let (completed, _remaining) = num_prime::nt_funcs::factors(cofactor, None);
+                for (factor, count) in completed {
+                    *factorization.entry(factor).or_insert(0) += count;
+                }
+            },
+            |_, factorization| {
+                result = factorization;
+                Ok(())
+            },
+        )
+        .unwrap();
+        result
+    }
+
+    fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+        ((u128::from(a) * u128::from(b)) % u128::from(m)) as u64
+    }
+
+    fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+        let mut result = 1;
+        base %= m;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = mul_mod(result, base, m);
+            }
+            base = mul_mod(base, base, m);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Deterministic Miller-Rabin primality test, independent of `num_prime`.
+    /// This witness set is known to be exact for all 64-bit inputs.
+    fn is_prime(n: u64) -> bool {
+        const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+        if n < 2 {
+            return false;
+        }
+        for p in WITNESSES {
+            if n == p {
+                return true;
+            }
+            if n % p == 0 {
+                return false;
+            }
+        }
+        let trailing = (n - 1).trailing_zeros();
+        let d = (n - 1) >> trailing;
+        'witness: for a in WITNESSES {
+            let mut x = pow_mod(a, d, n);
+            if x == 1 || x == n - 1 {
+                continue;
+            }
+            for _ in 1..trailing {
+                x = mul_mod(x, x, n);
+                if x == n - 1 {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    quickcheck! {
+        fn factorization_matches_num_prime(n: u64) -> TestResult {
+            if n < 2 {
+                return TestResult::discard();
+            }
+            let (expected, remaining) = num_prime::nt_funcs::factors(n, None);
+            if remaining.is_some() {
+                return TestResult::failed();
+            }
+            TestResult::from_bool(factorization(n) == expected)
+        }
+
+        fn factors_multiply_back_to_input(n: u64) -> TestResult {
+            if n < 2 {
+                return TestResult::discard();
+            }
+            let mut product = 1u64;
+            for (factor, exponent) in factorization(n) {
+                for _ in 0..exponent {
+                    let Some(next) = product.checked_mul(factor) else {
+                        return TestResult::failed();
+                    };
+                    product = next;
+                }
+            }
+            TestResult::from_bool(product == n)
+        }
+
+        fn reported_factors_are_prime(n: u64) -> TestResult {
+            if n < 2 {
+                return TestResult::discard();
+            }
+            TestResult::from_bool(factorization(n).keys().all(|&factor| is_prime(factor)))
+        }
+    }
+}
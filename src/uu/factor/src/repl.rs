@@ -0,0 +1,180 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore termios tcgetattr tcsetattr TCSANOW
+
+//! Interactive prompt used when `factor` is started without number
+//! arguments and stdin is a terminal. One number per line is read,
+//! factorized immediately and the result printed before the next prompt.
+//! A small readline-style editor provides backspace, Ctrl-U and an
+//! in-process history reachable with the up/down arrow keys; Ctrl-D on
+//! an empty line exits. When stdin is a pipe the caller keeps the plain
+//! batch loop instead.
+
+#[cfg(unix)]
+use std::io::Read;
+use std::io::{self, Write};
+
+use uucore::error::{FromIo, UResult};
+
+const PROMPT: &str = "> ";
+
+#[cfg(unix)]
+mod raw_mode {
+    use std::io;
+    use std::os::fd::AsFd;
+
+    use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg, Termios};
+
+    /// Puts the terminal behind `fd` into non-canonical, non-echoing mode
+    /// and restores the previous settings on drop.
+    pub struct RawModeGuard<F: AsFd> {
+        fd: F,
+        original: Termios,
+    }
+
+    impl<F: AsFd> RawModeGuard<F> {
+        pub fn new(fd: F) -> io::Result<Self> {
+            let original = tcgetattr(fd.as_fd())?;
+            let mut raw = original.clone();
+            raw.local_flags
+                .remove(LocalFlags::ICANON | LocalFlags::ECHO);
+            tcsetattr(fd.as_fd(), SetArg::TCSANOW, &raw)?;
+            Ok(Self { fd, original })
+        }
+    }
+
+    impl<F: AsFd> Drop for RawModeGuard<F> {
+        fn drop(&mut self) {
+            let _ = tcsetattr(self.fd.as_fd(), SetArg::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Reads lines interactively from stdin and passes each non-empty one to
+/// `on_line`. Returns when the user enters Ctrl-D on an empty line or
+/// stdin reaches end of file.
+#[cfg(unix)]
+pub fn run(mut on_line: impl FnMut(&str) -> UResult<()>) -> UResult<()> {
+    let mut stdin = io::stdin();
+    let _guard = raw_mode::RawModeGuard::new(io::stdin())
+        .map_err_context(|| "failed to configure terminal".into())?;
+
+    let mut history: Vec<Vec<u8>> = Vec::new();
+    let mut history_pos = 0;
+    let mut line: Vec<u8> = Vec::new();
+
+    echo(PROMPT.as_bytes())?;
+    let mut buf = [0u8; 1];
+    loop {
+        let n = stdin
+            .read(&mut buf)
+            .map_err_context(|| "error reading input".into())?;
+        if n == 0 {
+            echo(b"\r\n")?;
+            break;
+        }
+        match buf[0] {
+            b'\r' | b'\n' => {
+                echo(b"\r\n")?;
+                let input = String::from_utf8_lossy(&line).to_string();
+                if !input.trim().is_empty() {
+                    history.push(std::mem::take(&mut line));
+                    on_line(&input)?;
+                }
+                line.clear();
+                history_pos = history.len();
+                echo(PROMPT.as_bytes())?;
+            }
+            // Ctrl-D: exit on an empty line, ignore while editing
+            0x04 if line.is_empty() => {
+                echo(b"\r\n")?;
+                break;
+            }
+            // backspace
+            0x08 | 0x7f if !line.is_empty() => {
+                line.pop();
+                echo(b"\x08 \x08")?;
+            }
+            // Ctrl-U: discard the whole line
+            0x15 => {
+                line.clear();
+                redraw(&line)?;
+            }
+            // escape sequences: only the up/down arrows are acted upon
+            0x1b => {
+                let mut seq = [0u8; 2];
+                if stdin.read(&mut seq).unwrap_or(0) == 2 && seq[0] == b'[' {
+                    match seq[1] {
+                        b'A' if history_pos > 0 => {
+                            history_pos -= 1;
+                            line.clone_from(&history[history_pos]);
+                            redraw(&line)?;
+                        }
+                        b'B' if history_pos < history.len() => {
+                            history_pos += 1;
+                            line.clear();
+                            if history_pos < history.len() {
+                                line.clone_from(&history[history_pos]);
+                            }
+                            redraw(&line)?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            c if c >= 0x20 => {
+                line.push(c);
+                echo(&[c])?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Fallback for platforms without termios: the console's own line
+/// editing applies, history is not available.
+#[cfg(not(unix))]
+pub fn run(mut on_line: impl FnMut(&str) -> UResult<()>) -> UResult<()> {
+    let stdin = io::stdin();
+    let mut input = String::new();
+    loop {
+        echo(PROMPT.as_bytes())?;
+        input.clear();
+        let n = stdin
+            .read_line(&mut input)
+            .map_err_context(|| "error reading input".into())?;
+        if n == 0 {
+            echo(b"\n")?;
+            break;
+        }
+        if !input.trim().is_empty() {
+            on_line(&input)?;
+        }
+    }
+    Ok(())
+}
+
+fn echo(bytes: &[u8]) -> UResult<()> {
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(bytes)
+        .and_then(|()| stdout.flush())
+        .map_err_context(|| "write error".into())
+}
+
+/// Rewrites the current terminal line: prompt plus the edited input.
+#[cfg(unix)]
+fn redraw(line: &[u8]) -> UResult<()> {
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(b"\r\x1b[K")
+        .and_then(|()| stdout.write_all(PROMPT.as_bytes()))
+        .and_then(|()| stdout.write_all(line))
+        .and_then(|()| stdout.flush())
+        .map_err_context(|| "write error".into())
+}
@@ -0,0 +1,184 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore mulmod semiprimes
+
+//! Pollard rho factorization with Brent's cycle detection, for the 64-bit
+//! cofactors left over by trial division. Semiprimes of two similarly sized
+//! primes — the worst case for general-purpose machinery — are split in
+//! O(n^(1/4)) expected steps, with a deterministic Miller-Rabin test
+//! deciding when to stop recursing.
+
+use std::collections::BTreeMap;
+
+/// (a * b) % m without overflow.
+fn mulmod(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+/// (base ^ exp) % m by square and multiply.
+fn powmod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mulmod(result, base, m);
+        }
+        base = mulmod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Deterministic Miller-Rabin for u64: the first twelve primes are a proven
+/// complete witness set for the whole range.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    let trailing = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> trailing;
+    'witness: for a in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        let mut x = powmod(a, odd, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..trailing {
+            x = mulmod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// One Brent-cycle rho round on composite odd `n` with increment `c`.
+/// Returns a nontrivial divisor, or `n` when this `c` ran into a cycle
+/// without finding one (the caller retries with another increment).
+fn brent_rho(n: u64, c: u64) -> u64 {
+    let f = |x: u64| (mulmod(x, x, n) + c) % n;
+    let mut power = 1u64;
+    let mut lam = 1u64;
+    let mut tortoise = 2u64;
+    let mut hare = f(tortoise);
+    while tortoise != hare {
+        let divisor = gcd(tortoise.abs_diff(hare), n);
+        if divisor > 1 {
+            // usually a proper factor; n itself on a rare total collapse,
+            // which the caller treats as a failed round
+            return divisor;
+        }
+        if power == lam {
+            tortoise = hare;
+            power *= 2;
+            lam = 0;
+        }
+        hare = f(hare);
+        lam += 1;
+    }
+    n
+}
+
+/// Split composite odd `n` into two nontrivial divisors, trying rho rounds
+/// with increasing increments until one succeeds.
+fn split(n: u64) -> (u64, u64) {
+    for c in 1.. {
+        let divisor = brent_rho(n, c);
+        if divisor > 1 && divisor < n {
+            return (divisor, n / divisor);
+        }
+    }
+    unreachable!("a composite always splits eventually");
+}
+
+/// Factor `n` completely into primes with their exponents. Handles any
+/// value, but shines on cofactors that trial division left composite.
+pub fn factor(n: u64) -> BTreeMap<u64, usize> {
+    let mut factors = BTreeMap::new();
+    let mut queue = vec![n];
+    while let Some(mut m) = queue.pop() {
+        if m < 2 {
+            continue;
+        }
+        let twos = m.trailing_zeros();
+        if twos > 0 {
+            *factors.entry(2).or_insert(0) += twos as usize;
+            m >>= twos;
+            if m == 1 {
+                continue;
+            }
+        }
+        if is_prime(m) {
+            *factors.entry(m).or_insert(0) += 1;
+            continue;
+        }
+        let (a, b) = split(m);
+        queue.push(a);
+        queue.push(b);
+    }
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_splits_a_hard_semiprime() {
+        // the two largest primes below 2^32
+        let factors = factor(4_294_967_291 * 4_294_967_279);
+        assert_eq!(
+            factors.into_iter().collect::<Vec<_>>(),
+            vec![(4_294_967_279, 1), (4_294_967_291, 1)]
+        );
+    }
+
+    #[test]
+    fn test_prime_powers() {
+        let factors = factor(3u64.pow(20));
+        assert_eq!(factors.into_iter().collect::<Vec<_>>(), vec![(3, 20)]);
+    }
+
+    quickcheck! {
+        fn factors_multiply_back_to_input(n: u64) -> bool {
+            if n < 2 {
+                return true;
+            }
+            let mut product = 1u64;
+            for (factor, exponent) in factor(n) {
+                for _ in 0..exponent {
+                    product = match product.checked_mul(factor) {
+                        Some(next) => next,
+                        None => return false,
+                    };
+                }
+            }
+            product == n
+        }
+
+        fn all_reported_factors_are_prime(n: u64) -> bool {
+            n < 2 || factor(n).keys().all(|&factor| is_prime(factor))
+        }
+
+        fn primality_matches_num_prime(n: u64) -> bool {
+            is_prime(n) == num_prime::nt_funcs::is_prime(&n, None).probably()
+        }
+    }
+}
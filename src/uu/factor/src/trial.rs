@@ -0,0 +1,302 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore (math) Granlund cofactor mullo epu movemask castsi
+
+//! Small-prime trial division, the first stage of factoring a `u64`.
+//!
+//! Divisibility by an odd prime `p` is decided with the Granlund–Montgomery
+//! test: `n` is divisible by `p` iff `n * p⁻¹ (mod 2⁶⁴)` is at most
+//! `⌊u64::MAX / p⌋`, and the product then already equals `n / p`. This turns
+//! the per-prime work into one multiplication and one comparison, which the
+//! optional SIMD backend (the `simd` cargo feature) evaluates for several
+//! primes per iteration; only lanes that signal divisibility fall back to
+//! the scalar division loop.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Primes below this bound are stripped by the trial-division stage.
+pub const TRIAL_LIMIT: u64 = 1 << 10;
+
+/// An odd prime together with the precomputed constants of the divisibility
+/// test.
+struct TrialPrime {
+    prime: u64,
+    /// `prime⁻¹ (mod 2⁶⁴)`.
+    inverse: u64,
+    /// `⌊u64::MAX / prime⌋`.
+    limit: u64,
+}
+
+/// The odd primes below [`TRIAL_LIMIT`] with their test constants.
+fn trial_primes() -> &'static [TrialPrime] {
+    static PRIMES: OnceLock<Vec<TrialPrime>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        let limit = TRIAL_LIMIT as usize;
+        let mut is_prime = vec![true; limit];
+        is_prime[0] = false;
+        is_prime[1] = false;
+        for n in 2..limit {
+            if is_prime[n] {
+                for multiple in (n * n..limit).step_by(n) {
+                    is_prime[multiple] = false;
+                }
+            }
+        }
+        (3..limit)
+            .step_by(2)
+            .filter(|&n| is_prime[n])
+            .map(|n| {
+                let prime = n as u64;
+                TrialPrime {
+                    prime,
+                    inverse: mod_inverse_pow2(prime),
+                    limit: u64::MAX / prime,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Inverse of the odd number `p` modulo 2⁶⁴, by Newton–Hensel lifting:
+/// `p * p ≡ 1 (mod 8)` seeds three correct bits and every iteration doubles
+/// them.
+fn mod_inverse_pow2(p: u64) -> u64 {
+    let mut inverse = p;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inverse)));
+    }
+    inverse
+}
+
+/// Strips all prime factors below [`TRIAL_LIMIT`] from `n`, returning them
+/// with their exponents plus the remaining cofactor (1 if `n` was factored
+/// completely; 0 and 1 are returned unchanged with no factors).
+pub fn partial_factor(mut n: u64) -> (BTreeMap<u64, usize>, u64) {
+    let mut factors = BTreeMap::new();
+    if n < 2 {
+        return (factors, n);
+    }
+    let twos = n.trailing_zeros() as usize;
+    if twos > 0 {
+        factors.insert(2, twos);
+        n >>= twos;
+    }
+    if n > 1 {
+        strip_odd_factors(&mut n, &mut factors);
+    }
+    (factors, n)
+}
+
+/// [`partial_factor`] for inputs beyond 64 bits. The clever divisibility
+/// constants above are modulo 2⁶⁴ and do not carry over, so this uses plain
+/// big-integer division; with at most a few thousand candidate divisors that
+/// is still negligible next to factoring the cofactor.
+pub fn partial_factor_big(n: &num_bigint::BigUint) -> (BTreeMap<u64, usize>, num_bigint::BigUint) {
+    use num_traits::Zero;
+
+    let mut factors = BTreeMap::new();
+    let mut n = n.clone();
+    if n.is_zero() {
+        return (factors, n);
+    }
+    let twos = n.trailing_zeros().unwrap_or(0) as usize;
+    if twos > 0 {
+        factors.insert(2, twos);
+        n >>= twos;
+    }
+    for trial_prime in trial_primes() {
+        let prime = num_bigint::BigUint::from(trial_prime.prime);
+        let mut exponent = 0;
+        while (&n % &prime).is_zero() {
+            n /= &prime;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.insert(trial_prime.prime, exponent);
+        }
+    }
+    (factors, n)
+}
+
+fn strip_odd_factors(n: &mut u64, factors: &mut BTreeMap<u64, usize>) {
+    let primes = trial_primes();
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        // SAFETY: AVX2 support was just verified.
+        unsafe { avx2::strip_odd_factors(n, factors, primes) };
+        return;
+    }
+
+    for trial_prime in primes {
+        divide_out(n, factors, trial_prime);
+        if *n == 1 {
+            return;
+        }
+    }
+}
+
+/// Divides `trial_prime` out of `n` as often as it is contained, recording
+/// the exponent.
+fn divide_out(n: &mut u64, factors: &mut BTreeMap<u64, usize>, trial_prime: &TrialPrime) {
+    let mut exponent = 0;
+    loop {
+        let quotient = n.wrapping_mul(trial_prime.inverse);
+        if quotient > trial_prime.limit {
+            break;
+        }
+        *n = quotient;
+        exponent += 1;
+    }
+    if exponent > 0 {
+        factors.insert(trial_prime.prime, exponent);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2 {
+    use super::{divide_out, TrialPrime};
+    use std::arch::x86_64::*;
+    use std::collections::BTreeMap;
+
+    /// Like the scalar loop in [`super::strip_odd_factors`], but testing
+    /// four primes per iteration.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn strip_odd_factors(
+        n: &mut u64,
+        factors: &mut BTreeMap<u64, usize>,
+        primes: &[TrialPrime],
+    ) {
+        let mut chunks = primes.chunks_exact(4);
+        for chunk in &mut chunks {
+            let mask = divisible_lanes(*n, chunk);
+            if mask == 0 {
+                continue;
+            }
+            for (lane, trial_prime) in chunk.iter().enumerate() {
+                if mask & (1 << lane) != 0 {
+                    divide_out(n, factors, trial_prime);
+                }
+            }
+            if *n == 1 {
+                return;
+            }
+        }
+        for trial_prime in chunks.remainder() {
+            divide_out(n, factors, trial_prime);
+            if *n == 1 {
+                return;
+            }
+        }
+    }
+
+    /// Bitmask of the lanes of `chunk` whose prime divides `n`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn divisible_lanes(n: u64, chunk: &[TrialPrime]) -> u32 {
+        let inverses = _mm256_setr_epi64x(
+            chunk[0].inverse as i64,
+            chunk[1].inverse as i64,
+            chunk[2].inverse as i64,
+            chunk[3].inverse as i64,
+        );
+        let limits = _mm256_setr_epi64x(
+            chunk[0].limit as i64,
+            chunk[1].limit as i64,
+            chunk[2].limit as i64,
+            chunk[3].limit as i64,
+        );
+        let products = mullo_epi64(_mm256_set1_epi64x(n as i64), inverses);
+        // unsigned `product > limit` via the signed comparison with the sign
+        // bit flipped; the remaining lanes are the divisible ones
+        let sign = _mm256_set1_epi64x(i64::MIN);
+        let too_large = _mm256_cmpgt_epi64(
+            _mm256_xor_si256(products, sign),
+            _mm256_xor_si256(limits, sign),
+        );
+        !(_mm256_movemask_pd(_mm256_castsi256_pd(too_large)) as u32) & 0xF
+    }
+
+    /// The low 64 bits of the lane-wise product. A dedicated instruction for
+    /// this needs AVX-512, so it is assembled from 32×32 multiplications.
+    #[target_feature(enable = "avx2")]
+    unsafe fn mullo_epi64(a: __m256i, b: __m256i) -> __m256i {
+        let low = _mm256_mul_epu32(a, b);
+        let a_high = _mm256_srli_epi64(a, 32);
+        let b_high = _mm256_srli_epi64(b, 32);
+        let cross = _mm256_add_epi64(_mm256_mul_epu32(a, b_high), _mm256_mul_epu32(a_high, b));
+        _mm256_add_epi64(_mm256_slli_epi64(cross, 32), low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    /// Trial division with actual divisions, as a reference. Dividing by the
+    /// composites as well is harmless: their prime factors are already gone.
+    fn naive_partial_factor(mut n: u64) -> (BTreeMap<u64, usize>, u64) {
+        let mut factors = BTreeMap::new();
+        if n < 2 {
+            return (factors, n);
+        }
+        for p in 2..TRIAL_LIMIT {
+            while n % p == 0 {
+                *factors.entry(p).or_insert(0) += 1;
+                n /= p;
+            }
+        }
+        (factors, n)
+    }
+
+    #[test]
+    fn test_small_inputs_and_powers() {
+        assert_eq!(partial_factor(0), (BTreeMap::new(), 0));
+        assert_eq!(partial_factor(1), (BTreeMap::new(), 1));
+        assert_eq!(partial_factor(1024), (BTreeMap::from([(2, 10)]), 1));
+        assert_eq!(
+            partial_factor(2 * 3 * 3 * 1021),
+            (BTreeMap::from([(2, 1), (3, 2), (1021, 1)]), 1)
+        );
+    }
+
+    #[test]
+    fn test_large_prime_factors_survive() {
+        // 1031 is the smallest prime above TRIAL_LIMIT
+        assert_eq!(
+            partial_factor(6 * 1031 * 1031),
+            (BTreeMap::from([(2, 1), (3, 1)]), 1031 * 1031)
+        );
+    }
+
+    quickcheck! {
+        fn matches_naive_trial_division(n: u64) -> bool {
+            partial_factor(n) == naive_partial_factor(n)
+        }
+
+        fn big_path_agrees_with_the_u64_path(n: u64) -> bool {
+            use num_bigint::BigUint;
+            let (factors, cofactor) = partial_factor(n);
+            let (big_factors, big_cofactor) = partial_factor_big(&BigUint::from(n));
+            big_factors == factors && big_cofactor == BigUint::from(cofactor)
+        }
+
+        fn factors_multiply_back_to_input(n: u64) -> bool {
+            let (factors, cofactor) = partial_factor(n);
+            let mut product = cofactor.max(1);
+            for (factor, exponent) in factors {
+                for _ in 0..exponent {
+                    product = match product.checked_mul(factor) {
+                        Some(next) => next,
+                        None => return false,
+                    };
+                }
+            }
+            product == n.max(1) && (n != 0 || cofactor == 0)
+        }
+    }
+}
@@ -96,6 +96,6 @@ fn sleep(args: &[&str]) -> UResult<()> {
     if arg_error {
         return Err(UUsageError::new(1, ""));
     };
-    thread::sleep(sleep_dur);
+    thread::sleep(uucore::fake_clock::scaled(sleep_dur));
     Ok(())
 }
@@ -5,13 +5,34 @@
 
 // spell-checker:ignore parenb parodd cmspar hupcl cstopb cread clocal crtscts CSIZE
 // spell-checker:ignore ignbrk brkint ignpar parmrk inpck istrip inlcr igncr icrnl ixoff ixon iuclc ixany imaxbel iutf
-// spell-checker:ignore opost olcuc ocrnl onlcr onocr onlret ofdel nldly crdly tabdly bsdly vtdly ffdly
+// spell-checker:ignore opost olcuc ocrnl onlcr onocr onlret ofill ofdel nldly crdly tabdly bsdly vtdly ffdly oflag
 // spell-checker:ignore isig icanon iexten echoe crterase echok echonl noflsh xcase tostop echoprt prterase echoctl ctlecho echoke crtkill flusho extproc
 // spell-checker:ignore lnext rprnt susp swtch vdiscard veof veol verase vintr vkill vlnext vquit vreprint vstart vstop vsusp vswtc vwerase werase
 // spell-checker:ignore sigquit sigtstp
 
 use crate::Flag;
 
+// Bits the libc crate does not export; the values are taken from the
+// kernel's termbits.h (powerpc kept the historic layout).
+#[cfg(all(
+    any(target_os = "android", target_os = "linux"),
+    not(any(target_arch = "powerpc", target_arch = "powerpc64"))
+))]
+mod missing_bits {
+    use nix::libc::tcflag_t;
+    pub const IUCLC: tcflag_t = 0o001000;
+    pub const XCASE: tcflag_t = 0o000004;
+}
+#[cfg(all(
+    any(target_os = "android", target_os = "linux"),
+    any(target_arch = "powerpc", target_arch = "powerpc64")
+))]
+mod missing_bits {
+    use nix::libc::tcflag_t;
+    pub const IUCLC: tcflag_t = 0o010000;
+    pub const XCASE: tcflag_t = 0o040000;
+}
+
 #[cfg(not(any(
     target_os = "freebsd",
     target_os = "dragonfly",
@@ -55,11 +76,12 @@ pub const INPUT_FLAGS: &[Flag<I>] = &[
     Flag::new("inlcr", I::INLCR),
     Flag::new("igncr", I::IGNCR),
     Flag::new("icrnl", I::ICRNL).sane(),
+    Flag::new("ixon", I::IXON).sane(),
     Flag::new("ixoff", I::IXOFF),
-    Flag::new("tandem", I::IXOFF),
-    Flag::new("ixon", I::IXON),
-    // not supported by nix
-    // Flag::new("iuclc", I::IUCLC),
+    Flag::new("tandem", I::IXOFF).hidden(),
+    // neither nix nor libc has a name for the IUCLC bit
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    Flag::new("iuclc", I::from_bits_retain(missing_bits::IUCLC)),
     Flag::new("ixany", I::IXANY),
     Flag::new("imaxbel", I::IMAXBEL).sane(),
     #[cfg(any(target_os = "android", target_os = "linux", target_os = "macos"))]
@@ -79,6 +101,9 @@ pub const OUTPUT_FLAGS: &[Flag<O>] = &[
     Flag::new("onlcr", O::ONLCR).sane(),
     Flag::new("onocr", O::ONOCR),
     Flag::new("onlret", O::ONLRET),
+    // nix has no name for the OFILL bit, so it comes straight from libc
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    Flag::new("ofill", O::from_bits_retain(nix::libc::OFILL)),
     #[cfg(any(
         target_os = "android",
         target_os = "haiku",
@@ -234,8 +259,9 @@ pub const LOCAL_FLAGS: &[Flag<L>] = &[
     Flag::new("echok", L::ECHOK).sane(),
     Flag::new("echonl", L::ECHONL),
     Flag::new("noflsh", L::NOFLSH),
-    // Not supported by nix
-    // Flag::new("xcase", L::XCASE),
+    // neither nix nor libc has a name for the XCASE bit
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    Flag::new("xcase", L::from_bits_retain(missing_bits::XCASE)),
     Flag::new("tostop", L::TOSTOP),
     Flag::new("echoprt", L::ECHOPRT),
     Flag::new("prterase", L::ECHOPRT).hidden(),
@@ -247,6 +273,143 @@ pub const LOCAL_FLAGS: &[Flag<L>] = &[
     Flag::new("extproc", L::EXTPROC),
 ];
 
+/// Delay and fill related settings accepted for compatibility on platforms
+/// where termios cannot express them. Portable scripts set these (e.g.
+/// `stty nl0 cr0 ofill`) and expect them not to fail.
+pub const NO_OP_SETTINGS: &[&str] = &[
+    // a real flag on Linux, merely accepted elsewhere
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    "ofill",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "nl0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "nl1",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "cr0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "cr1",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "cr2",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "cr3",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "tab0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "tab1",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "tab2",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "tab3",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "bs0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "bs1",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "vt0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "vt1",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "ff0",
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "haiku",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    "ff1",
+];
+
 // BSD's use u32 as baud rate, to using the enum is unnecessary.
 #[cfg(not(any(
     target_os = "freebsd",
@@ -3,25 +3,26 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-// spell-checker:ignore clocal erange tcgetattr tcsetattr tcsanow tiocgwinsz tiocswinsz cfgetospeed cfsetospeed ushort vmin vtime
+// spell-checker:ignore clocal erange tcgetattr tcsetattr tcsanow tiocgwinsz tiocswinsz cfgetospeed cfsetispeed cfsetospeed ispeed ospeed ushort vmin vtime ofill oflag
 
 mod flags;
 
 use clap::{crate_version, Arg, ArgAction, ArgMatches, Command};
 use nix::libc::{c_ushort, O_NONBLOCK, TIOCGWINSZ, TIOCSWINSZ};
 use nix::sys::termios::{
-    cfgetospeed, cfsetospeed, tcgetattr, tcsetattr, ControlFlags, InputFlags, LocalFlags,
-    OutputFlags, SpecialCharacterIndices, Termios,
+    cfgetospeed, cfsetispeed, cfsetospeed, tcgetattr, tcsetattr, ControlFlags, InputFlags,
+    LocalFlags, OutputFlags, SpecialCharacterIndices, Termios,
 };
 use nix::{ioctl_read_bad, ioctl_write_ptr_bad};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, stdout, Stdout};
+use std::io::{stdout, Stdout};
 use std::ops::ControlFlow;
 use std::os::fd::{AsFd, BorrowedFd};
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::{AsRawFd, RawFd};
-use uucore::error::{UResult, USimpleError};
-use uucore::{format_usage, help_about, help_usage};
+use uucore::error::{set_exit_code, strip_errno, FromIo, UError, UResult, USimpleError};
+use uucore::{format_usage, help_about, help_usage, show_error};
 
 #[cfg(not(any(
     target_os = "freebsd",
@@ -32,7 +33,7 @@ use uucore::{format_usage, help_about, help_usage};
     target_os = "openbsd"
 )))]
 use flags::BAUD_RATES;
-use flags::{CONTROL_CHARS, CONTROL_FLAGS, INPUT_FLAGS, LOCAL_FLAGS, OUTPUT_FLAGS};
+use flags::{CONTROL_CHARS, CONTROL_FLAGS, INPUT_FLAGS, LOCAL_FLAGS, NO_OP_SETTINGS, OUTPUT_FLAGS};
 
 const USAGE: &str = help_usage!("stty.md");
 const SUMMARY: &str = help_about!("stty.md");
@@ -86,14 +87,29 @@ trait TermiosFlag: Copy {
 mod options {
     pub const ALL: &str = "all";
     pub const SAVE: &str = "save";
+    pub const DIFF: &str = "diff";
     pub const FILE: &str = "file";
+    pub const VERBOSE: &str = "verbose";
+    pub const QUIET_ERRORS: &str = "quiet-errors";
+    pub const FORCE: &str = "force";
+    pub const NO_ROLLBACK: &str = "no-rollback";
+    pub const GENERATE_COMPLETION: &str = "generate-completion";
     pub const SETTINGS: &str = "settings";
 }
 
 struct Options<'a> {
     all: bool,
     save: bool,
+    /// Print only the settings that differ from the `sane` profile
+    /// (`--diff`).
+    diff: bool,
     file: Device,
+    /// The `--file` argument, for error messages.
+    file_name: Option<&'a str>,
+    verbose: bool,
+    quiet_errors: bool,
+    force: bool,
+    no_rollback: bool,
     settings: Option<Vec<&'a str>>,
 }
 
@@ -121,11 +137,15 @@ impl AsRawFd for Device {
 }
 
 impl<'a> Options<'a> {
-    fn from(matches: &'a ArgMatches) -> io::Result<Self> {
+    fn from(matches: &'a ArgMatches) -> UResult<Self> {
+        let file_name = matches
+            .get_one::<String>(options::FILE)
+            .map(|name| name.as_str());
         Ok(Self {
             all: matches.get_flag(options::ALL),
             save: matches.get_flag(options::SAVE),
-            file: match matches.get_one::<String>(options::FILE) {
+            diff: matches.get_flag(options::DIFF),
+            file: match file_name {
                 // Two notes here:
                 // 1. O_NONBLOCK is needed because according to GNU docs, a
                 //    POSIX tty can block waiting for carrier-detect if the
@@ -139,15 +159,37 @@ impl<'a> Options<'a> {
                     std::fs::OpenOptions::new()
                         .read(true)
                         .custom_flags(O_NONBLOCK)
-                        .open(f)?,
+                        .open(f)
+                        .map_err_context(|| f.to_string())?,
                 ),
                 None => Device::Stdout(stdout()),
             },
+            file_name,
+            verbose: matches.get_flag(options::VERBOSE),
+            quiet_errors: matches.get_flag(options::QUIET_ERRORS),
+            force: matches.get_flag(options::FORCE),
+            no_rollback: matches.get_flag(options::NO_ROLLBACK),
             settings: matches
                 .get_many::<String>(options::SETTINGS)
                 .map(|v| v.map(|s| s.as_ref()).collect()),
         })
     }
+
+    /// The name of the device we operate on as it should appear in error
+    /// messages: the `--file` argument, or "standard input".
+    fn device_name(&self) -> &str {
+        self.file_name.unwrap_or("standard input")
+    }
+}
+
+/// Map a termios failure to GNU's "DEVICE: strerror" style, e.g.
+/// "standard input: Inappropriate ioctl for device" when the input is
+/// redirected from a pipe or a regular file.
+fn device_error(opts: &Options, e: nix::errno::Errno) -> Box<dyn UError> {
+    // go through io::Error to get the strerror() text GNU prints, instead of
+    // the historic names nix uses (e.g. "Not a typewriter" for ENOTTY)
+    let e = std::io::Error::from_raw_os_error(e as i32);
+    USimpleError::new(1, format!("{}: {}", opts.device_name(), strip_errno(&e)))
 }
 
 // Needs to be repr(C) because we pass it to the ioctl calls.
@@ -178,37 +220,143 @@ ioctl_write_ptr_bad!(
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
+    if let Some(shell) = matches.get_one::<clap_complete::Shell>(options::GENERATE_COMPLETION) {
+        clap_complete::generate(*shell, &mut uu_app(), uucore::util_name(), &mut stdout());
+        return Ok(());
+    }
+
     let opts = Options::from(&matches)?;
 
     stty(&opts)
 }
 
 fn stty(opts: &Options) -> UResult<()> {
-    if opts.save && opts.all {
+    if [opts.save, opts.all, opts.diff]
+        .iter()
+        .filter(|b| **b)
+        .count()
+        > 1
+    {
         return Err(USimpleError::new(
             1,
             "the options for verbose and stty-readable output styles are mutually exclusive",
         ));
     }
 
-    if opts.settings.is_some() && (opts.save || opts.all) {
+    if opts.settings.is_some() && (opts.save || opts.all || opts.diff) {
         return Err(USimpleError::new(
             1,
             "when specifying an output style, modes may not be set",
         ));
     }
 
-    // TODO: Figure out the right error message for when tcgetattr fails
-    let mut termios = tcgetattr(opts.file.as_fd()).expect("Could not get terminal attributes");
+    let mut termios = tcgetattr_retaining(opts.file.as_fd()).map_err(|e| device_error(opts, e))?;
+    // kept around so a partially applied batch can be rolled back
+    let original = termios.clone();
 
     if let Some(settings) = &opts.settings {
-        for setting in settings {
-            if let ControlFlow::Break(false) = apply_setting(&mut termios, setting) {
+        if let Some((earlier, later)) = find_conflicting_settings(settings) {
+            if opts.force {
+                uucore::show_warning!(
+                    "settings '{earlier}' and '{later}' contradict each other; the last one wins"
+                );
+            } else {
+                return Err(USimpleError::new(
+                    1,
+                    format!("the settings '{earlier}' and '{later}' contradict each other"),
+                ));
+            }
+        }
+
+        let mut settings_iter = settings.iter();
+        while let Some(setting) = settings_iter.next() {
+            // A token with ':' separators is a state saved with `stty -g`,
+            // possibly on another system.
+            if setting.contains(':') {
+                if apply_save_string(&mut termios, setting).is_err() {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("invalid argument '{setting}'"),
+                    ));
+                }
+                continue;
+            }
+
+            // Speed settings for one direction consume the following token as
+            // their value; a bare baud rate token sets both directions and is
+            // handled by apply_setting below.
+            if matches!(*setting, "ispeed" | "ospeed") {
+                let Some(value) = settings_iter.next() else {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("missing argument to '{setting}'"),
+                    ));
+                };
+                if apply_speed_setting(&mut termios, setting, value).is_err() {
+                    return Err(USimpleError::new(1, format!("invalid argument '{value}'")));
+                }
+                continue;
+            }
+
+            // Window size settings consume the following token as their value
+            // and take effect immediately, independent of the termios state.
+            if matches!(*setting, "rows" | "columns" | "cols") {
+                let Some(value) = settings_iter.next() else {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("missing argument to '{setting}'"),
+                    ));
+                };
+                let Ok(n) = value.parse::<c_ushort>() else {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("invalid integer argument '{value}'"),
+                    ));
+                };
+                set_window_size(opts, *setting == "rows", n)?;
+                continue;
+            }
+
+            // Control character settings consume the following token as their
+            // value, e.g. "intr ^C" or "erase 0x7f".
+            if let Some(cc_index) = control_char_index(setting) {
+                let Some(value) = settings_iter.next() else {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("missing argument to '{setting}'"),
+                    ));
+                };
+                let Ok(cc) = string_to_control_char(value) else {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("invalid integer argument '{value}'"),
+                    ));
+                };
+                termios.control_chars[cc_index as usize] = cc;
+                if opts.quiet_errors && opts.verbose {
+                    println!("setting {setting}: ok");
+                }
+                continue;
+            }
+
+            if let ControlFlow::Break(false) = apply_setting(&mut termios, setting, opts.verbose) {
+                if opts.quiet_errors {
+                    // keep going, report individually and fail at the end
+                    show_error!("invalid argument '{setting}'");
+                    set_exit_code(1);
+                    if opts.verbose {
+                        println!("setting {setting}: invalid");
+                    }
+                    continue;
+                }
                 return Err(USimpleError::new(
                     1,
                     format!("invalid argument '{setting}'"),
                 ));
             }
+            if opts.quiet_errors && opts.verbose {
+                println!("setting {setting}: ok");
+            }
         }
 
         tcsetattr(
@@ -216,14 +364,155 @@ fn stty(opts: &Options) -> UResult<()> {
             nix::sys::termios::SetArg::TCSANOW,
             &termios,
         )
-        .expect("Could not write terminal attributes");
+        .map_err(|e| device_error(opts, e))?;
+
+        // tcsetattr succeeds as soon as *any* of the requested changes could
+        // be made, so read the state back to detect partial application. By
+        // default a partial batch is rolled back to the captured state, so a
+        // serial port is never left half-configured.
+        let applied = tcgetattr_retaining(opts.file.as_fd()).map_err(|e| device_error(opts, e))?;
+        if !settings_applied(&applied, &termios) {
+            if opts.no_rollback {
+                return Err(USimpleError::new(
+                    1,
+                    "unable to perform all requested operations",
+                ));
+            }
+            tcsetattr(
+                opts.file.as_fd(),
+                nix::sys::termios::SetArg::TCSANOW,
+                &original,
+            )
+            .map_err(|e| device_error(opts, e))?;
+            return Err(USimpleError::new(
+                1,
+                "unable to perform all requested operations; previous settings restored",
+            ));
+        }
+    } else {
+        print_settings(&termios, opts).map_err(|e| device_error(opts, e))?;
+    }
+    Ok(())
+}
+
+/// Like [`tcgetattr`], but with the flag bits nix has no name for (IUCLC,
+/// OFILL, XCASE on Linux) restored from the raw structure: nix truncates
+/// them away in the conversion, which would both hide them from the printer
+/// and make a round trip through [`tcsetattr`] look like a failure.
+fn tcgetattr_retaining(fd: BorrowedFd) -> nix::Result<Termios> {
+    let mut termios = tcgetattr(fd)?;
+    let mut raw = unsafe { std::mem::zeroed::<nix::libc::termios>() };
+    // SAFETY: a plain read of the terminal state into a zeroed struct.
+    if unsafe { nix::libc::tcgetattr(fd.as_raw_fd(), &mut raw) } == 0 {
+        termios.input_flags = InputFlags::from_bits_retain(raw.c_iflag);
+        termios.output_flags = OutputFlags::from_bits_retain(raw.c_oflag);
+        termios.control_flags = ControlFlags::from_bits_retain(raw.c_cflag);
+        termios.local_flags = LocalFlags::from_bits_retain(raw.c_lflag);
+    }
+    Ok(termios)
+}
+
+/// Compare the parts of two termios states that `tcsetattr` is supposed to
+/// apply. The raw speed fields are left out of the comparison: the C library
+/// derives them from the flags, so they can lag behind in the requested state
+/// without anything being wrong. On Linux the kernel additionally reports an
+/// input speed through the CIBAUD bits even when it was requested as "same as
+/// output", so those bits are masked as well.
+fn settings_applied(applied: &Termios, requested: &Termios) -> bool {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const CFLAG_MASK: nix::libc::tcflag_t = !nix::libc::CIBAUD;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const CFLAG_MASK: nix::libc::tcflag_t = !0;
+
+    applied.input_flags == requested.input_flags
+        && applied.output_flags == requested.output_flags
+        && applied.control_flags.bits() & CFLAG_MASK == requested.control_flags.bits() & CFLAG_MASK
+        && applied.local_flags == requested.local_flags
+        && applied.control_chars == requested.control_chars
+        && cfgetospeed(applied) == cfgetospeed(requested)
+}
+
+/// Change one dimension of the terminal window, like `stty rows N` or
+/// `stty columns N`. The other dimension is left as it is.
+fn set_window_size(opts: &Options, rows: bool, n: c_ushort) -> UResult<()> {
+    let mut size = TermSize::default();
+    unsafe { tiocgwinsz(opts.file.as_raw_fd(), &mut size as *mut _) }
+        .map_err(|e| device_error(opts, e))?;
+    if rows {
+        size.rows = n;
     } else {
-        print_settings(&termios, opts).expect("TODO: make proper error here from nix error");
+        size.columns = n;
     }
+    unsafe { tiocswinsz(opts.file.as_raw_fd(), &size as *const _) }
+        .map_err(|e| device_error(opts, e))?;
     Ok(())
 }
 
-fn print_terminal_size(termios: &Termios, opts: &Options) -> nix::Result<()> {
+/// The column at which output lines are wrapped: the width of the terminal
+/// on stdout, the COLUMNS variable when stdout is not a terminal, or 80 —
+/// the same lookup GNU stty performs.
+fn screen_columns() -> usize {
+    let mut size = TermSize::default();
+    if unsafe { tiocgwinsz(std::io::stdout().as_raw_fd(), &mut size as *mut _) }.is_ok()
+        && size.columns > 0
+    {
+        return size.columns as usize;
+    }
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&columns| columns > 0)
+    {
+        return columns;
+    }
+    80
+}
+
+/// Line wrapping identical to GNU's `wrapf`: chunks are joined by a single
+/// space, and a line break takes the place of the space once a chunk would
+/// no longer fit before [`screen_columns`]. Chunks are never broken up
+/// internally, which matters for the `name = value;` fields.
+struct WrappedPrinter {
+    max_col: usize,
+    current_col: usize,
+}
+
+impl WrappedPrinter {
+    fn new() -> Self {
+        Self {
+            max_col: screen_columns(),
+            current_col: 0,
+        }
+    }
+
+    fn wrapf(&mut self, chunk: &str) {
+        if self.current_col > 0 {
+            if self.current_col + chunk.len() >= self.max_col {
+                println!();
+                self.current_col = 0;
+            } else {
+                print!(" ");
+                self.current_col += 1;
+            }
+        }
+        print!("{chunk}");
+        self.current_col += chunk.len();
+    }
+
+    /// End the current block; the next chunk starts on a fresh line.
+    fn finish_line(&mut self) {
+        if self.current_col > 0 {
+            println!();
+            self.current_col = 0;
+        }
+    }
+}
+
+fn print_terminal_size(
+    termios: &Termios,
+    opts: &Options,
+    out: &mut WrappedPrinter,
+) -> nix::Result<()> {
     let speed = cfgetospeed(termios);
 
     // BSDs use a u32 for the baud rate, so we can simply print it.
@@ -235,7 +524,7 @@ fn print_terminal_size(termios: &Termios, opts: &Options) -> nix::Result<()> {
         target_os = "netbsd",
         target_os = "openbsd"
     ))]
-    print!("speed {speed} baud; ");
+    out.wrapf(&format!("speed {speed} baud;"));
 
     // Other platforms need to use the baud rate enum, so printing the right value
     // becomes slightly more complicated.
@@ -249,7 +538,7 @@ fn print_terminal_size(termios: &Termios, opts: &Options) -> nix::Result<()> {
     )))]
     for (text, baud_rate) in BAUD_RATES {
         if *baud_rate == speed {
-            print!("speed {text} baud; ");
+            out.wrapf(&format!("speed {text} baud;"));
             break;
         }
     }
@@ -257,7 +546,8 @@ fn print_terminal_size(termios: &Termios, opts: &Options) -> nix::Result<()> {
     if opts.all {
         let mut size = TermSize::default();
         unsafe { tiocgwinsz(opts.file.as_raw_fd(), &mut size as *mut _)? };
-        print!("rows {}; columns {}; ", size.rows, size.columns);
+        out.wrapf(&format!("rows {};", size.rows));
+        out.wrapf(&format!("columns {};", size.columns));
     }
 
     #[cfg(any(target_os = "linux", target_os = "redox"))]
@@ -266,10 +556,10 @@ fn print_terminal_size(termios: &Termios, opts: &Options) -> nix::Result<()> {
         // so we get the underlying libc::termios struct to get that information.
         let libc_termios: nix::libc::termios = termios.clone().into();
         let line = libc_termios.c_line;
-        print!("line = {line};");
+        out.wrapf(&format!("line = {line};"));
     }
 
-    println!();
+    out.finish_line();
     Ok(())
 }
 
@@ -299,23 +589,201 @@ fn control_char_to_string(cc: nix::libc::cc_t) -> nix::Result<String> {
     Ok(format!("{meta_prefix}{ctrl_prefix}{character}"))
 }
 
-fn print_control_chars(termios: &Termios, opts: &Options) -> nix::Result<()> {
+/// Parse a control character value the way GNU stty does: `undef` (or `^-`)
+/// disables the character, `^X` is caret notation with `^?` for DEL, a single
+/// character stands for its own value, and anything else is an integer in
+/// decimal, octal (leading 0) or hex (leading 0x) form.
+fn string_to_control_char(s: &str) -> Result<nix::libc::cc_t, ()> {
+    if s == "undef" || s == "^-" {
+        return Ok(0);
+    }
+
+    let bytes = s.as_bytes();
+    // Like GNU, anything following the caret-escaped character is ignored.
+    if let [b'^', rest @ ..] = bytes {
+        if !rest.is_empty() {
+            return Ok(if rest[0] == b'?' {
+                0x7f
+            } else {
+                rest[0] & !0o140
+            });
+        }
+    }
+    // A single character stands for its own value (including a lone '^').
+    if bytes.len() == 1 {
+        return Ok(bytes[0]);
+    }
+
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u8::from_str_radix(hex, 16)
+    } else if let Some(octal) = s.strip_prefix('0') {
+        if octal.is_empty() {
+            Ok(0)
+        } else {
+            u8::from_str_radix(octal, 8)
+        }
+    } else {
+        s.parse::<u8>()
+    }
+    .map_err(|_| ())
+}
+
+/// The termios index for a named control character setting, including the
+/// "min" and "time" values that are not part of [`CONTROL_CHARS`].
+fn control_char_index(name: &str) -> Option<SpecialCharacterIndices> {
+    if name == "min" {
+        return Some(SpecialCharacterIndices::VMIN);
+    }
+    if name == "time" {
+        return Some(SpecialCharacterIndices::VTIME);
+    }
+    CONTROL_CHARS
+        .iter()
+        .find(|(text, _)| *text == name)
+        .map(|&(_, cc)| cc)
+}
+
+/// The save-string formats the parser accepts: our own `stty -g` output and
+/// the `gfmt1` format that `stty -g` prints on the BSDs and macOS, so a
+/// configuration saved there can be replayed here.
+enum SaveFormat {
+    Native,
+    BsdGfmt1,
+}
+
+impl SaveFormat {
+    /// Pick the format a save string is written in; gfmt1 strings announce
+    /// themselves with a leading marker field.
+    fn detect(s: &str) -> Self {
+        if s.starts_with("gfmt1:") {
+            Self::BsdGfmt1
+        } else {
+            Self::Native
+        }
+    }
+
+    fn apply(&self, termios: &mut Termios, s: &str) -> Result<(), ()> {
+        match self {
+            Self::Native => apply_native_save_string(termios, s),
+            Self::BsdGfmt1 => apply_bsd_gfmt1_save_string(termios, s),
+        }
+    }
+}
+
+/// Restore a state saved with `stty -g`, in whichever supported format.
+/// The string may come from another system, so nothing of the current state
+/// is kept on success and nothing is touched on failure.
+fn apply_save_string(termios: &mut Termios, s: &str) -> Result<(), ()> {
+    SaveFormat::detect(s).apply(termios, s)
+}
+
+/// Translate a control character from a gfmt1 string: hex, with 0xff being
+/// the BSD way of disabling a character, which other platforms spell 0.
+fn gfmt1_control_char(value: &str) -> Result<nix::libc::cc_t, ()> {
+    let cc = nix::libc::cc_t::from_str_radix(value, 16).map_err(|_| ())?;
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "ios",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    let cc = if cc == 0xff { 0 } else { cc };
+    Ok(cc)
+}
+
+/// Restore a BSD `gfmt1` string: the `gfmt1` marker followed by colon
+/// separated NAME=VALUE fields, the flag fields and control characters in
+/// hex and the two speeds in decimal. Control characters that have no
+/// counterpart here (like `dsusp` or `status`) are validated but skipped; a
+/// name we do not recognize at all rejects the whole string.
+fn apply_bsd_gfmt1_save_string(termios: &mut Termios, s: &str) -> Result<(), ()> {
+    let mut updated = termios.clone();
+    let mut fields = s.split(':');
+    if fields.next() != Some("gfmt1") {
+        return Err(());
+    }
+    for field in fields {
+        let (name, value) = field.split_once('=').ok_or(())?;
+        let flag_bits = || nix::libc::tcflag_t::from_str_radix(value, 16).map_err(|_| ());
+        match name {
+            "iflag" => updated.input_flags = InputFlags::from_bits_retain(flag_bits()?),
+            "oflag" => updated.output_flags = OutputFlags::from_bits_retain(flag_bits()?),
+            "cflag" => updated.control_flags = ControlFlags::from_bits_retain(flag_bits()?),
+            "lflag" => updated.local_flags = LocalFlags::from_bits_retain(flag_bits()?),
+            "ispeed" | "ospeed" => apply_speed_setting(&mut updated, name, value)?,
+            // BSD spells this control character out in full
+            "reprint" => {
+                updated.control_chars[SpecialCharacterIndices::VREPRINT as usize] =
+                    gfmt1_control_char(value)?;
+            }
+            _ => match control_char_index(name) {
+                Some(index) => updated.control_chars[index as usize] = gfmt1_control_char(value)?,
+                // BSD-only control characters without a counterpart here
+                None if matches!(name, "dsusp" | "status" | "erase2") => {
+                    gfmt1_control_char(value)?;
+                }
+                None => return Err(()),
+            },
+        }
+    }
+
+    *termios = updated;
+    Ok(())
+}
+
+/// Restore a state saved with our own `stty -g`: four hex flag fields
+/// followed by one hex value per control character, colon separated.
+fn apply_native_save_string(termios: &mut Termios, s: &str) -> Result<(), ()> {
+    let mut updated = termios.clone();
+    let mut fields = s.split(':');
+
+    let mut next_flags = || -> Result<nix::libc::tcflag_t, ()> {
+        nix::libc::tcflag_t::from_str_radix(fields.next().ok_or(())?, 16).map_err(|_| ())
+    };
+    // Restore the flag fields bit for bit. Bits that nix has no name for
+    // (e.g. the speed bits inside c_cflag on Linux) must survive the round
+    // trip too, so the saved value cannot be truncated to the known flags.
+    updated.input_flags = InputFlags::from_bits_retain(next_flags()?);
+    updated.output_flags = OutputFlags::from_bits_retain(next_flags()?);
+    updated.control_flags = ControlFlags::from_bits_retain(next_flags()?);
+    updated.local_flags = LocalFlags::from_bits_retain(next_flags()?);
+
+    for cc in &mut updated.control_chars {
+        *cc = nix::libc::cc_t::from_str_radix(fields.next().ok_or(())?, 16).map_err(|_| ())?;
+    }
+    if fields.next().is_some() {
+        return Err(());
+    }
+
+    *termios = updated;
+    Ok(())
+}
+
+fn print_control_chars(
+    termios: &Termios,
+    opts: &Options,
+    out: &mut WrappedPrinter,
+) -> nix::Result<()> {
     if !opts.all {
         // TODO: this branch should print values that differ from defaults
         return Ok(());
     }
 
     for (text, cc_index) in CONTROL_CHARS {
-        print!(
-            "{text} = {}; ",
+        out.wrapf(&format!(
+            "{text} = {};",
             control_char_to_string(termios.control_chars[*cc_index as usize])?
-        );
+        ));
     }
-    println!(
+    // min and time always travel together, like in GNU's printer
+    out.wrapf(&format!(
         "min = {}; time = {};",
         termios.control_chars[SpecialCharacterIndices::VMIN as usize],
         termios.control_chars[SpecialCharacterIndices::VTIME as usize]
-    );
+    ));
+    out.finish_line();
     Ok(())
 }
 
@@ -336,19 +804,92 @@ fn print_in_save_format(termios: &Termios) {
 fn print_settings(termios: &Termios, opts: &Options) -> nix::Result<()> {
     if opts.save {
         print_in_save_format(termios);
+    } else if opts.diff {
+        print_diff_from_sane(termios)?;
     } else {
-        print_terminal_size(termios, opts)?;
-        print_control_chars(termios, opts)?;
-        print_flags(termios, opts, CONTROL_FLAGS);
-        print_flags(termios, opts, INPUT_FLAGS);
-        print_flags(termios, opts, OUTPUT_FLAGS);
-        print_flags(termios, opts, LOCAL_FLAGS);
+        let mut out = WrappedPrinter::new();
+        print_terminal_size(termios, opts, &mut out)?;
+        print_control_chars(termios, opts, &mut out)?;
+        // one block per flag field, in GNU's order
+        print_flags(termios, opts, CONTROL_FLAGS, &mut out);
+        print_flags(termios, opts, INPUT_FLAGS, &mut out);
+        print_flags(termios, opts, OUTPUT_FLAGS, &mut out);
+        print_flags(termios, opts, LOCAL_FLAGS, &mut out);
+    }
+    Ok(())
+}
+
+/// Print only the settings that differ from the `sane` profile (`--diff`).
+/// The profile is obtained by running [`apply_sane`] on a copy of the current
+/// state, so the comparison covers exactly what `stty sane` would change:
+/// flags and control characters, but not the line speed. A terminal that is
+/// already sane prints nothing.
+fn print_diff_from_sane(termios: &Termios) -> nix::Result<()> {
+    let mut sane = termios.clone();
+    apply_sane(&mut sane);
+    let mut out = WrappedPrinter::new();
+
+    for &(cc, _) in SANE_CONTROL_CHARS {
+        let current = termios.control_chars[cc as usize];
+        if current == sane.control_chars[cc as usize] {
+            continue;
+        }
+        match cc {
+            // min and time hold counts, not characters
+            SpecialCharacterIndices::VMIN => out.wrapf(&format!("min = {current};")),
+            SpecialCharacterIndices::VTIME => out.wrapf(&format!("time = {current};")),
+            _ => {
+                let name = CONTROL_CHARS
+                    .iter()
+                    .find(|(_, index)| *index == cc)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("?");
+                out.wrapf(&format!("{name} = {};", control_char_to_string(current)?));
+            }
+        }
+    }
+    out.finish_line();
+
+    fn diff_flags<T: TermiosFlag>(
+        termios: &Termios,
+        sane: &Termios,
+        flags: &[Flag<T>],
+        out: &mut WrappedPrinter,
+    ) {
+        for &Flag {
+            name,
+            flag,
+            show,
+            group,
+            ..
+        } in flags
+        {
+            if !show || flag.is_in(termios, group) == flag.is_in(sane, group) {
+                continue;
+            }
+            if flag.is_in(termios, group) {
+                out.wrapf(name);
+            } else if group.is_none() {
+                // a grouped flag that is off is represented by the member
+                // that is on instead, so only plain flags get a "-" form
+                out.wrapf(&format!("-{name}"));
+            }
+        }
+        out.finish_line();
     }
+    diff_flags(termios, &sane, CONTROL_FLAGS, &mut out);
+    diff_flags(termios, &sane, INPUT_FLAGS, &mut out);
+    diff_flags(termios, &sane, OUTPUT_FLAGS, &mut out);
+    diff_flags(termios, &sane, LOCAL_FLAGS, &mut out);
     Ok(())
 }
 
-fn print_flags<T: TermiosFlag>(termios: &Termios, opts: &Options, flags: &[Flag<T>]) {
-    let mut printed = false;
+fn print_flags<T: TermiosFlag>(
+    termios: &Termios,
+    opts: &Options,
+    flags: &[Flag<T>],
+    out: &mut WrappedPrinter,
+) {
     for &Flag {
         name,
         flag,
@@ -363,19 +904,201 @@ fn print_flags<T: TermiosFlag>(termios: &Termios, opts: &Options, flags: &[Flag<
         let val = flag.is_in(termios, group);
         if group.is_some() {
             if val && (!sane || opts.all) {
-                print!("{name} ");
-                printed = true;
+                out.wrapf(name);
             }
         } else if opts.all || val != sane {
-            if !val {
-                print!("-");
+            if val {
+                out.wrapf(name);
+            } else {
+                out.wrapf(&format!("-{name}"));
             }
-            print!("{name} ");
-            printed = true;
         }
     }
-    if printed {
-        println!();
+    out.finish_line();
+}
+
+/// Returns whether `name` refers to a boolean flag, i.e. a setting that can be
+/// negated with a `-` prefix. Only those can contradict each other; baud
+/// rates, control characters and no-op settings are replaced in order.
+fn is_boolean_flag_setting(name: &str) -> bool {
+    CONTROL_FLAGS.iter().any(|flag| flag.name == name)
+        || INPUT_FLAGS.iter().any(|flag| flag.name == name)
+        || OUTPUT_FLAGS.iter().any(|flag| flag.name == name)
+        || LOCAL_FLAGS.iter().any(|flag| flag.name == name)
+}
+
+/// Scan the settings of a single invocation for a flag that is both set and
+/// cleared, like "icanon -icanon". Returns the first such pair, in the order
+/// given on the command line.
+fn find_conflicting_settings(settings: &[&str]) -> Option<(String, String)> {
+    let mut seen: HashMap<&str, bool> = HashMap::new();
+    for setting in settings {
+        let (remove, name) = match setting.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, setting as &str),
+        };
+        if !is_boolean_flag_setting(name) {
+            continue;
+        }
+        if let Some(&previous_remove) = seen.get(name) {
+            if previous_remove != remove {
+                let format = |remove: bool| {
+                    if remove {
+                        format!("-{name}")
+                    } else {
+                        name.to_string()
+                    }
+                };
+                return Some((format(previous_remove), format(remove)));
+            }
+        }
+        seen.insert(name, remove);
+    }
+    None
+}
+
+/// The control character values restored by `sane` (and partially by other
+/// combination settings), as documented in GNU stty(1).
+const SANE_CONTROL_CHARS: &[(SpecialCharacterIndices, u8)] = &[
+    (SpecialCharacterIndices::VINTR, 0x03),  // ^C
+    (SpecialCharacterIndices::VQUIT, 0x1c),  // ^\
+    (SpecialCharacterIndices::VERASE, 0x7f), // DEL
+    (SpecialCharacterIndices::VKILL, 0x15),  // ^U
+    (SpecialCharacterIndices::VEOF, 0x04),   // ^D
+    (SpecialCharacterIndices::VEOL, 0),      // undef
+    (SpecialCharacterIndices::VEOL2, 0),     // undef
+    #[cfg(target_os = "linux")]
+    (SpecialCharacterIndices::VSWTC, 0), // undef
+    (SpecialCharacterIndices::VSTART, 0x11), // ^Q
+    (SpecialCharacterIndices::VSTOP, 0x13),  // ^S
+    (SpecialCharacterIndices::VSUSP, 0x1a),  // ^Z
+    (SpecialCharacterIndices::VREPRINT, 0x12), // ^R
+    (SpecialCharacterIndices::VWERASE, 0x17), // ^W
+    (SpecialCharacterIndices::VLNEXT, 0x16), // ^V
+    (SpecialCharacterIndices::VDISCARD, 0x0f), // ^O
+    (SpecialCharacterIndices::VMIN, 1),
+    (SpecialCharacterIndices::VTIME, 0),
+];
+
+/// Set every flag of every table to its sane state and restore the default
+/// control characters, like `stty sane`.
+fn apply_sane(termios: &mut Termios) {
+    fn sane_flags<T: TermiosFlag>(termios: &mut Termios, flags: &[Flag<T>]) {
+        for flag in flags {
+            if let Some(group) = flag.group {
+                if flag.sane {
+                    group.apply(termios, false);
+                    flag.flag.apply(termios, true);
+                }
+            } else {
+                flag.flag.apply(termios, flag.sane);
+            }
+        }
+    }
+    sane_flags(termios, CONTROL_FLAGS);
+    sane_flags(termios, INPUT_FLAGS);
+    sane_flags(termios, OUTPUT_FLAGS);
+    sane_flags(termios, LOCAL_FLAGS);
+    for &(cc, value) in SANE_CONTROL_CHARS {
+        termios.control_chars[cc as usize] = value;
+    }
+}
+
+/// Apply a list of individual flag settings that a combination setting
+/// expands to. All names are known table entries, so this cannot fail.
+fn apply_simple_settings(termios: &mut Termios, settings: &[&str]) -> ControlFlow<bool> {
+    for setting in settings {
+        let applied = apply_setting(termios, setting, false);
+        debug_assert_eq!(applied, ControlFlow::Break(true));
+    }
+    ControlFlow::Break(true)
+}
+
+/// Like [`apply_simple_settings`], for combinations that make further
+/// changes afterwards and must not propagate the `Break`.
+fn apply_simple_settings_void(termios: &mut Termios, settings: &[&str]) {
+    let _ = apply_simple_settings(termios, settings);
+}
+
+/// Apply one of GNU's combination settings (`sane`, `raw`, `evenp`, ...),
+/// which expand to several individual flag and control character changes.
+///
+/// The value inside the `Break` variant of the `ControlFlow` indicates whether
+/// the setting has been applied.
+fn apply_combination_setting(termios: &mut Termios, name: &str, remove: bool) -> ControlFlow<bool> {
+    // Some negated combinations are themselves combinations (e.g. -raw is
+    // cooked), so resolve those first.
+    let name = match (name, remove) {
+        (name, false) => name,
+        ("raw", true) => "cooked",
+        ("cooked", true) => "raw",
+        ("cbreak", true) => return apply_simple_settings(termios, &["icanon"]),
+        ("evenp" | "parity" | "oddp", true) => {
+            return apply_simple_settings(termios, &["-parenb", "cs8"])
+        }
+        ("nl", true) => {
+            return apply_simple_settings(
+                termios,
+                &["icrnl", "-inlcr", "-igncr", "onlcr", "-ocrnl", "-onlret"],
+            )
+        }
+        ("pass8", true) => return apply_simple_settings(termios, &["parenb", "istrip", "cs7"]),
+        ("litout", true) => {
+            return apply_simple_settings(termios, &["parenb", "istrip", "opost", "cs7"])
+        }
+        // the remaining combinations have no negated form
+        ("sane" | "ek" | "crt" | "dec", true) => return ControlFlow::Break(false),
+        (_, true) => return ControlFlow::Continue(()),
+    };
+    match name {
+        "evenp" | "parity" => apply_simple_settings(termios, &["parenb", "-parodd", "cs7"]),
+        "oddp" => apply_simple_settings(termios, &["parenb", "parodd", "cs7"]),
+        "nl" => apply_simple_settings(termios, &["-icrnl", "-onlcr"]),
+        "pass8" => apply_simple_settings(termios, &["-parenb", "-istrip", "cs8"]),
+        "litout" => apply_simple_settings(termios, &["-parenb", "-istrip", "-opost", "cs8"]),
+        "cbreak" => apply_simple_settings(termios, &["-icanon"]),
+        "crt" => apply_simple_settings(termios, &["echoe", "echoctl", "echoke"]),
+        "dec" => {
+            apply_simple_settings_void(termios, &["echoe", "echoctl", "echoke", "-ixany"]);
+            termios.control_chars[SpecialCharacterIndices::VINTR as usize] = 0x03;
+            termios.control_chars[SpecialCharacterIndices::VERASE as usize] = 0x7f;
+            termios.control_chars[SpecialCharacterIndices::VKILL as usize] = 0x15;
+            ControlFlow::Break(true)
+        }
+        "ek" => {
+            termios.control_chars[SpecialCharacterIndices::VERASE as usize] = 0x7f;
+            termios.control_chars[SpecialCharacterIndices::VKILL as usize] = 0x15;
+            ControlFlow::Break(true)
+        }
+        "sane" => {
+            apply_sane(termios);
+            ControlFlow::Break(true)
+        }
+        "raw" => {
+            apply_simple_settings_void(
+                termios,
+                &[
+                    "-ignbrk", "-brkint", "-ignpar", "-parmrk", "-inpck", "-istrip", "-inlcr",
+                    "-igncr", "-icrnl", "-ixon", "-ixoff", "-ixany", "-imaxbel", "-opost", "-isig",
+                    "-icanon",
+                ],
+            );
+            termios.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+            termios.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+            ControlFlow::Break(true)
+        }
+        "cooked" => {
+            apply_simple_settings_void(
+                termios,
+                &[
+                    "brkint", "ignpar", "istrip", "icrnl", "ixon", "opost", "isig", "icanon",
+                ],
+            );
+            termios.control_chars[SpecialCharacterIndices::VEOF as usize] = 0x04;
+            termios.control_chars[SpecialCharacterIndices::VEOL as usize] = 0;
+            ControlFlow::Break(true)
+        }
+        _ => ControlFlow::Continue(()),
     }
 }
 
@@ -383,17 +1106,27 @@ fn print_flags<T: TermiosFlag>(termios: &Termios, opts: &Options, flags: &[Flag<
 ///
 /// The value inside the `Break` variant of the `ControlFlow` indicates whether
 /// the setting has been applied.
-fn apply_setting(termios: &mut Termios, s: &str) -> ControlFlow<bool> {
+fn apply_setting(termios: &mut Termios, s: &str, verbose: bool) -> ControlFlow<bool> {
     apply_baud_rate_flag(termios, s)?;
 
     let (remove, name) = match s.strip_prefix('-') {
         Some(s) => (true, s),
         None => (false, s),
     };
+    apply_combination_setting(termios, name, remove)?;
     apply_flag(termios, CONTROL_FLAGS, name, remove)?;
     apply_flag(termios, INPUT_FLAGS, name, remove)?;
     apply_flag(termios, OUTPUT_FLAGS, name, remove)?;
     apply_flag(termios, LOCAL_FLAGS, name, remove)?;
+
+    // Accept delay/fill settings that termios on this platform cannot express,
+    // so portable scripts (e.g. "stty nl0 cr0 ofill") don't fail.
+    if NO_OP_SETTINGS.contains(&name) {
+        if verbose {
+            uucore::show_warning!("setting '{name}' has no effect on this platform");
+        }
+        return ControlFlow::Break(true);
+    }
     ControlFlow::Break(false)
 }
 
@@ -429,6 +1162,8 @@ fn apply_flag<T: TermiosFlag>(
 }
 
 fn apply_baud_rate_flag(termios: &mut Termios, input: &str) -> ControlFlow<bool> {
+    // A bare baud rate sets the speed in both directions, like GNU.
+
     // BSDs use a u32 for the baud rate, so any decimal number applies.
     #[cfg(any(
         target_os = "freebsd",
@@ -439,6 +1174,7 @@ fn apply_baud_rate_flag(termios: &mut Termios, input: &str) -> ControlFlow<bool>
         target_os = "openbsd"
     ))]
     if let Ok(n) = input.parse::<u32>() {
+        cfsetispeed(termios, n).expect("Failed to set baud rate");
         cfsetospeed(termios, n).expect("Failed to set baud rate");
         return ControlFlow::Break(true);
     }
@@ -454,6 +1190,7 @@ fn apply_baud_rate_flag(termios: &mut Termios, input: &str) -> ControlFlow<bool>
     )))]
     for (text, baud_rate) in BAUD_RATES {
         if *text == input {
+            cfsetispeed(termios, *baud_rate).expect("Failed to set baud rate");
             cfsetospeed(termios, *baud_rate).expect("Failed to set baud rate");
             return ControlFlow::Break(true);
         }
@@ -461,6 +1198,49 @@ fn apply_baud_rate_flag(termios: &mut Termios, input: &str) -> ControlFlow<bool>
     ControlFlow::Continue(())
 }
 
+/// Set the baud rate for one direction only, like `stty ispeed 9600` or
+/// `stty ospeed 9600`. An unknown rate is reported as `Err`.
+fn apply_speed_setting(termios: &mut Termios, setting: &str, value: &str) -> Result<(), ()> {
+    // BSDs use a u32 for the baud rate, so any decimal number applies.
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "ios",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        let n = value.parse::<u32>().map_err(|_| ())?;
+        if setting == "ispeed" {
+            cfsetispeed(termios, n).map_err(|_| ())
+        } else {
+            cfsetospeed(termios, n).map_err(|_| ())
+        }
+    }
+
+    // Other platforms use an enum.
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "ios",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let (_, baud_rate) = BAUD_RATES
+            .iter()
+            .find(|(text, _)| *text == value)
+            .ok_or(())?;
+        if setting == "ispeed" {
+            cfsetispeed(termios, *baud_rate).map_err(|_| ())
+        } else {
+            cfsetospeed(termios, *baud_rate).map_err(|_| ())
+        }
+    }
+}
+
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
         .version(crate_version!())
@@ -481,6 +1261,15 @@ pub fn uu_app() -> Command {
                 .help("print all current settings in a stty-readable form")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::DIFF)
+                .long(options::DIFF)
+                .help(
+                    "print only the settings that differ from what 'stty sane' \
+                would establish (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::FILE)
                 .short('F')
@@ -489,9 +1278,60 @@ pub fn uu_app() -> Command {
                 .value_name("DEVICE")
                 .help("open and use the specified DEVICE instead of stdin"),
         )
+        .arg(
+            Arg::new(options::VERBOSE)
+                .long(options::VERBOSE)
+                .help(
+                    "print a warning for each accepted setting that has no effect \
+                on this platform (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::QUIET_ERRORS)
+                .long(options::QUIET_ERRORS)
+                .help(
+                    "attempt all given settings instead of stopping at the first \
+                invalid one, report each failure individually and exit nonzero \
+                at the end; with --verbose, print a status line per setting \
+                (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::FORCE)
+                .long(options::FORCE)
+                .help(
+                    "when one invocation both sets and clears the same flag, \
+                like 'icanon -icanon', warn and let the last occurrence win \
+                instead of erroring out (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::NO_ROLLBACK)
+                .long(options::NO_ROLLBACK)
+                .help(
+                    "when the device accepts only part of the requested settings, \
+                keep that partial state like GNU stty does instead of restoring \
+                the previous settings (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::GENERATE_COMPLETION)
+                .long(options::GENERATE_COMPLETION)
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(clap_complete::Shell))
+                .help(
+                    "print a completion script for SHELL to stdout and exit (a uutils extension)",
+                ),
+        )
         .arg(
             Arg::new(options::SETTINGS)
                 .action(ArgAction::Append)
+                // so negated settings like "-icanon" work without a "--"
+                .allow_hyphen_values(true)
                 .help("settings to change"),
         )
 }
@@ -30,6 +30,13 @@ pub(crate) enum ExitStatus {
 
     /// When there is a failure while waiting for the child process to terminate.
     WaitingFailed,
+
+    /// When the command exists but cannot be invoked, e.g. for lack of the
+    /// execute permission.
+    CannotInvoke,
+
+    /// When the command cannot be found.
+    CommandNotFound,
 }
 
 impl From<ExitStatus> for i32 {
@@ -39,6 +46,8 @@ impl From<ExitStatus> for i32 {
             ExitStatus::TimeoutFailed => 125,
             ExitStatus::SignalSent(s) => 128 + s as Self,
             ExitStatus::WaitingFailed => 124,
+            ExitStatus::CannotInvoke => 126,
+            ExitStatus::CommandNotFound => 127,
         }
     }
 }
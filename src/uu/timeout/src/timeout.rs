@@ -5,34 +5,44 @@
 
 // spell-checker:ignore (ToDO) tstr sigstr cmdname setpgid sigchld getpid
 mod status;
+#[cfg(windows)]
+mod windows;
 
 use crate::status::ExitStatus;
+#[cfg(windows)]
+use crate::windows::{signal_by_name_or_value, signal_name_by_value, ChildExt};
 use clap::{crate_version, Arg, ArgAction, Command};
 use std::io::ErrorKind;
+#[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
 use std::process::{self, Child, Stdio};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uucore::display::Quotable;
 use uucore::error::{UClapError, UResult, USimpleError, UUsageError};
+#[cfg(unix)]
 use uucore::process::ChildExt;
 
 #[cfg(unix)]
-use uucore::signals::enable_pipe_errors;
+use uucore::signals::{enable_pipe_errors, signal_by_name_or_value, signal_name_by_value};
 
-use uucore::{
-    format_usage, help_about, help_usage, show_error,
-    signals::{signal_by_name_or_value, signal_name_by_value},
-};
+use uucore::{format_usage, help_about, help_usage, show_error};
 
 const ABOUT: &str = help_about!("timeout.md");
 const USAGE: &str = help_usage!("timeout.md");
 
 pub mod options {
     pub static FOREGROUND: &str = "foreground";
+    pub static FROM_ENV: &str = "from-env";
+    pub static INHERIT_BUDGET: &str = "inherit-budget";
     pub static KILL_AFTER: &str = "kill-after";
+    pub static NEW_SESSION: &str = "new-session";
+    pub static ON_TIMEOUT: &str = "on-timeout";
     pub static SIGNAL: &str = "signal";
     pub static PRESERVE_STATUS: &str = "preserve-status";
+    pub static QUIET_KILL: &str = "quiet-kill";
+    pub static STATUS_JSON: &str = "status-json";
     pub static VERBOSE: &str = "verbose";
+    pub static GENERATE_COMPLETION: &str = "generate-completion";
 
     // Positional args.
     pub static DURATION: &str = "duration";
@@ -41,31 +51,47 @@ pub mod options {
 
 struct Config {
     foreground: bool,
+    inherit_budget: bool,
     kill_after: Option<Duration>,
-    signal: usize,
+    #[cfg(unix)]
+    new_session: bool,
+    on_timeout: Option<String>,
+    /// The escalation chain: the first signal is sent when the timeout fires,
+    /// each following one after a further `--kill-after` interval, before the
+    /// unconditional final KILL.
+    signals: Vec<usize>,
     duration: Duration,
     preserve_status: bool,
-    verbose: bool,
+    #[cfg(target_os = "linux")]
+    quiet_kill: bool,
+    status_json: bool,
+    /// 0 is quiet, 1 diagnoses every signal sent like GNU does, 2 and more
+    /// add how long after the start the signal was sent, with microsecond
+    /// resolution.
+    verbose: u8,
 
     command: Vec<String>,
 }
 
 impl Config {
     fn from(options: &clap::ArgMatches) -> UResult<Self> {
-        let signal = match options.get_one::<String>(options::SIGNAL) {
-            Some(signal_) => {
-                let signal_result = signal_by_name_or_value(signal_);
-                match signal_result {
-                    None => {
-                        return Err(UUsageError::new(
-                            ExitStatus::TimeoutFailed.into(),
-                            format!("{}: invalid signal", signal_.quote()),
-                        ))
+        let signals = match options.get_many::<String>(options::SIGNAL) {
+            Some(signal_names) => {
+                let mut signals = Vec::new();
+                for signal_ in signal_names {
+                    match signal_by_name_or_value(signal_) {
+                        None => {
+                            return Err(UUsageError::new(
+                                ExitStatus::TimeoutFailed.into(),
+                                format!("{}: invalid signal", signal_.quote()),
+                            ))
+                        }
+                        Some(signal_value) => signals.push(signal_value),
                     }
-                    Some(signal_value) => signal_value,
                 }
+                signals
             }
-            _ => uucore::signals::signal_by_name_or_value("TERM").unwrap(),
+            _ => vec![uucore::signals::signal_by_name_or_value("TERM").unwrap()],
         };
 
         let kill_after = match options.get_one::<String>(options::KILL_AFTER) {
@@ -76,29 +102,99 @@ impl Config {
             },
         };
 
-        let duration = match uucore::parse_time::from_str(
-            options.get_one::<String>(options::DURATION).unwrap(),
-        ) {
-            Ok(duration) => duration,
-            Err(err) => return Err(UUsageError::new(ExitStatus::TimeoutFailed.into(), err)),
+        // Without a kill-after interval there is no point in time at which the
+        // later signals of a chain would be sent.
+        if signals.len() > 1 && kill_after.is_none() {
+            return Err(UUsageError::new(
+                ExitStatus::TimeoutFailed.into(),
+                "'--kill-after' is required when more than one signal is specified",
+            ));
+        }
+
+        let duration = match options.get_one::<String>(options::FROM_ENV) {
+            Some(var_name) => {
+                let value = match std::env::var(var_name) {
+                    Ok(value) => value,
+                    Err(std::env::VarError::NotPresent) => {
+                        return Err(UUsageError::new(
+                            ExitStatus::TimeoutFailed.into(),
+                            format!(
+                                "environment variable {} is not set for '--from-env'",
+                                var_name.quote()
+                            ),
+                        ))
+                    }
+                    Err(std::env::VarError::NotUnicode(_)) => {
+                        return Err(UUsageError::new(
+                            ExitStatus::TimeoutFailed.into(),
+                            format!(
+                                "environment variable {} is not valid UTF-8",
+                                var_name.quote()
+                            ),
+                        ))
+                    }
+                };
+                match uucore::parse_time::from_str(&value) {
+                    Ok(duration) => duration,
+                    Err(err) => {
+                        return Err(UUsageError::new(
+                            ExitStatus::TimeoutFailed.into(),
+                            format!(
+                                "invalid duration in environment variable {}: {err}",
+                                var_name.quote()
+                            ),
+                        ))
+                    }
+                }
+            }
+            None => match uucore::parse_time::from_str(
+                options.get_one::<String>(options::DURATION).unwrap(),
+            ) {
+                Ok(duration) => duration,
+                Err(err) => return Err(UUsageError::new(ExitStatus::TimeoutFailed.into(), err)),
+            },
         };
 
+        // let the test suite compress multi-second timeouts into milliseconds
+        let duration = uucore::fake_clock::scaled(duration);
+        let kill_after = kill_after.map(uucore::fake_clock::scaled);
+
         let preserve_status: bool = options.get_flag(options::PRESERVE_STATUS);
+        let status_json = options.get_flag(options::STATUS_JSON);
         let foreground = options.get_flag(options::FOREGROUND);
-        let verbose = options.get_flag(options::VERBOSE);
+        let verbose = options.get_count(options::VERBOSE);
 
-        let command = options
-            .get_many::<String>(options::COMMAND)
-            .unwrap()
-            .map(String::from)
-            .collect::<Vec<_>>();
+        let mut command: Vec<String> = Vec::new();
+        // With --from-env no duration is expected on the command line, so the
+        // first positional argument already belongs to the command.
+        if options.contains_id(options::FROM_ENV) {
+            if let Some(first) = options.get_one::<String>(options::DURATION) {
+                command.push(first.clone());
+            }
+        }
+        if let Some(values) = options.get_many::<String>(options::COMMAND) {
+            command.extend(values.map(String::from));
+        }
+        if command.is_empty() {
+            return Err(UUsageError::new(
+                ExitStatus::TimeoutFailed.into(),
+                "you must specify a command with '--from-env'",
+            ));
+        }
 
         Ok(Self {
             foreground,
+            inherit_budget: options.get_flag(options::INHERIT_BUDGET),
             kill_after,
-            signal,
+            #[cfg(unix)]
+            new_session: options.get_flag(options::NEW_SESSION),
+            on_timeout: options.get_one::<String>(options::ON_TIMEOUT).cloned(),
+            signals,
             duration,
             preserve_status,
+            #[cfg(target_os = "linux")]
+            quiet_kill: options.get_flag(options::QUIET_KILL),
+            status_json,
             verbose,
             command,
         })
@@ -109,20 +205,26 @@ impl Config {
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args).with_exit_code(125)?;
 
+    if let Some(shell) = matches.get_one::<clap_complete::Shell>(options::GENERATE_COMPLETION) {
+        clap_complete::generate(
+            *shell,
+            &mut uu_app(),
+            uucore::util_name(),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
     let config = Config::from(&matches)?;
-    timeout(
-        &config.command,
-        config.duration,
-        config.signal,
-        config.kill_after,
-        config.foreground,
-        config.preserve_status,
-        config.verbose,
-    )
+    #[cfg(target_os = "linux")]
+    if config.quiet_kill {
+        return timeout_quietly(&config);
+    }
+    timeout(&config)
 }
 
 pub fn uu_app() -> Command {
-    Command::new("timeout")
+    let command = Command::new("timeout")
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
@@ -136,6 +238,32 @@ pub fn uu_app() -> Command {
                 )
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::FROM_ENV)
+                .long(options::FROM_ENV)
+                .value_name("VARNAME")
+                .num_args(0..=1)
+                .default_missing_value("TIMEOUT")
+                .require_equals(true)
+                .help(
+                    "read the duration from the environment variable VARNAME \
+                (TIMEOUT by default) instead of the first positional argument, \
+                with the same validation; handy for wrapper scripts with \
+                configurable timeouts (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::INHERIT_BUDGET)
+                .long(options::INHERIT_BUDGET)
+                .help(
+                    "never run COMMAND longer than an enclosing 'timeout \
+                --inherit-budget' allows: the remaining budget is read from the \
+                TIMEOUT_BUDGET_DEADLINE environment variable, DURATION is capped \
+                to it, and the updated remainder is exported to COMMAND \
+                (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::KILL_AFTER)
                 .long(options::KILL_AFTER)
@@ -145,41 +273,204 @@ pub fn uu_app() -> Command {
                 after the initial signal was sent",
                 ),
         )
+        .arg(
+            Arg::new(options::ON_TIMEOUT)
+                .long(options::ON_TIMEOUT)
+                .value_name("CMD")
+                .help(
+                    "run CMD through the shell when the timeout fires, before the \
+                signal is sent; the PID of COMMAND is exported to CMD as \
+                TIMEOUT_CHILD_PID, e.g. for capturing a stack dump of the hanging \
+                process; CMD is killed if it runs longer than 10 seconds \
+                (a uutils extension)",
+                ),
+        )
         .arg(
             Arg::new(options::PRESERVE_STATUS)
                 .long(options::PRESERVE_STATUS)
                 .help("exit with the same status as COMMAND, even when the command times out")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::STATUS_JSON)
+                .long(options::STATUS_JSON)
+                .help(
+                    "describe what happened as a single JSON object on file \
+                descriptor 3, or on stderr with a 'timeout-status: ' prefix if \
+                fd 3 is not open (a uutils extension)",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::SIGNAL)
                 .short('s')
                 .long(options::SIGNAL)
                 .help(
                     "specify the signal to be sent on timeout; SIGNAL may be a name like \
-                'HUP' or a number; see 'kill -l' for a list of signals",
+                'HUP' or a number; see 'kill -l' for a list of signals; may be \
+                repeated to form an escalation chain, where each later signal is \
+                sent after a further '--kill-after' interval, before the final \
+                KILL (repeating it is a uutils extension)",
                 )
+                .action(ArgAction::Append)
                 .value_name("SIGNAL"),
         )
         .arg(
             Arg::new(options::VERBOSE)
                 .short('v')
                 .long(options::VERBOSE)
-                .help("diagnose to stderr any signal sent upon timeout")
-                .action(ArgAction::SetTrue),
+                .help(
+                    "diagnose to stderr any signal sent upon timeout; given twice, \
+                also report how long after the start each signal was sent, with \
+                microsecond resolution (repeating it is a uutils extension)",
+                )
+                .action(ArgAction::Count),
+        )
+        .arg(
+            Arg::new(options::GENERATE_COMPLETION)
+                .long(options::GENERATE_COMPLETION)
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(clap_complete::Shell))
+                .help(
+                    "print a completion script for SHELL to stdout and exit (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::DURATION)
+                .required_unless_present_any([options::GENERATE_COMPLETION, options::FROM_ENV]),
         )
-        .arg(Arg::new(options::DURATION).required(true))
         .arg(
             Arg::new(options::COMMAND)
-                .required(true)
+                .required_unless_present_any([options::GENERATE_COMPLETION, options::FROM_ENV])
                 .action(ArgAction::Append)
                 .value_hint(clap::ValueHint::CommandName),
         )
         .trailing_var_arg(true)
-        .infer_long_args(true)
+        .infer_long_args(true);
+    // Absorbing the signal death relies on PR_SET_CHILD_SUBREAPER, which
+    // only exists on Linux.
+    #[cfg(target_os = "linux")]
+    let command = command.arg(
+        Arg::new(options::QUIET_KILL)
+            .long(options::QUIET_KILL)
+            .help(
+                "do the waiting and killing in a forked supervisor, so the \
+            invoking shell never sees a child of its own die by a signal and \
+            stays quiet about it ('Terminated', 'Killed'); a signal death is \
+            reported as the customary 128+N exit code instead \
+            (a uutils extension)",
+            )
+            .action(ArgAction::SetTrue),
+    );
+    // Detaching from the controlling terminal is a Unix concept; on other
+    // platforms the flag does not exist at all.
+    #[cfg(unix)]
+    let command = command.arg(
+        Arg::new(options::NEW_SESSION)
+            .long(options::NEW_SESSION)
+            .help(
+                "run COMMAND in a new session via setsid(), fully detached from \
+            the controlling terminal: it can neither open /dev/tty nor receive \
+            terminal-generated signals like SIGINT; --foreground then only \
+            limits signalling to COMMAND itself, there is no tty left to share \
+            (a uutils extension)",
+            )
+            .action(ArgAction::SetTrue),
+    );
+    command
+}
+
+/// Data collected for the machine readable status report of `--status-json`.
+struct StatusReport {
+    timed_out: bool,
+    signal_sent: Option<usize>,
+    exit_code: Option<i32>,
+    child_signal: Option<i32>,
+    timeout: Duration,
+    started: Instant,
+}
+
+impl StatusReport {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timed_out: false,
+            signal_sent: None,
+            exit_code: None,
+            child_signal: None,
+            timeout,
+            started: Instant::now(),
+        }
+    }
+
+    fn record_exit_status(&mut self, status: &std::process::ExitStatus) {
+        self.exit_code = status.code();
+        self.child_signal = child_termination_signal(status);
+    }
+
+    fn to_json(&self) -> String {
+        fn opt_number(value: Option<i32>) -> String {
+            value.map_or_else(|| "null".to_string(), |v| v.to_string())
+        }
+        let signal_sent = self.signal_sent.map_or_else(
+            || "null".to_string(),
+            |s| format!("\"{}\"", signal_name_by_value(s).unwrap_or("UNKNOWN")),
+        );
+        format!(
+            "{{\"timed_out\":{},\"signal_sent\":{},\"exit_code\":{},\"child_signal\":{},\"duration_secs\":{:.6},\"timeout_secs\":{:.6}}}",
+            self.timed_out,
+            signal_sent,
+            opt_number(self.exit_code),
+            opt_number(self.child_signal),
+            self.started.elapsed().as_secs_f64(),
+            self.timeout.as_secs_f64(),
+        )
+    }
+
+    /// Write the report as a single line to file descriptor 3, or to stderr
+    /// with a `timeout-status: ` prefix if fd 3 is not open for writing.
+    fn emit(&self) {
+        let line = format!("{}\n", self.to_json());
+        #[cfg(unix)]
+        {
+            // SAFETY: writing to a fd that is not open only results in an error
+            // return value, which makes us fall back to stderr below.
+            let written = unsafe { libc::write(3, line.as_ptr().cast(), line.len()) };
+            if written == line.len() as isize {
+                return;
+            }
+        }
+        eprint!("timeout-status: {line}");
+    }
+}
+
+/// Maximum time the `--on-timeout` hook may run before it is killed itself.
+const ON_TIMEOUT_HOOK_LIMIT: Duration = Duration::from_secs(10);
+
+/// Run the `--on-timeout` hook through the shell with the PID of the timed out
+/// child exported as `TIMEOUT_CHILD_PID`, and wait for it with its own bound so
+/// a stuck hook cannot delay the signal indefinitely. Hook failures are only
+/// diagnosed, they never change the outcome of the timeout itself.
+fn run_on_timeout_hook(hook: &str, child_pid: u32) {
+    match process::Command::new("sh")
+        .args(["-c", hook])
+        .env("TIMEOUT_CHILD_PID", child_pid.to_string())
+        .spawn()
+    {
+        Ok(mut hook_process) => match hook_process.wait_or_timeout(ON_TIMEOUT_HOOK_LIMIT) {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                show_error!("on-timeout hook did not finish in time, killing it");
+                let _ = hook_process.kill();
+                let _ = hook_process.wait();
+            }
+            Err(err) => show_error!("failed to wait for on-timeout hook: {err}"),
+        },
+        Err(err) => show_error!("failed to execute on-timeout hook: {err}"),
+    }
 }
 
 /// Remove pre-existing SIGCHLD handlers that would make waiting for the child's exit code fail.
+#[cfg(unix)]
 fn unblock_sigchld() {
     unsafe {
         nix::sys::signal::signal(
@@ -190,14 +481,37 @@ fn unblock_sigchld() {
     }
 }
 
-/// Report that a signal is being sent if the verbose flag is set.
-fn report_if_verbose(signal: usize, cmd: &str, verbose: bool) {
-    if verbose {
-        let s = signal_name_by_value(signal).unwrap();
-        show_error!("sending signal {} to command {}", s, cmd.quote());
+/// Report that a signal is being sent if the verbose flag is set. With the
+/// flag given twice, the time elapsed since `started` is appended; `started`
+/// is a monotonic instant, so the reported offsets are meaningful even when
+/// the wall clock is adjusted while the command runs.
+fn report_if_verbose(signal: usize, cmd: &str, verbose: u8, started: Instant) {
+    let s = signal_name_by_value(signal).unwrap();
+    match verbose {
+        0 => (),
+        1 => show_error!("sending signal {} to command {}", s, cmd.quote()),
+        _ => show_error!(
+            "sending signal {} to command {} after {:.6}s",
+            s,
+            cmd.quote(),
+            started.elapsed().as_secs_f64()
+        ),
     }
 }
 
+#[cfg(windows)]
+fn send_signal(process: &mut Child, signal: usize, _foreground: bool) {
+    // There are no signals to deliver: KILL maps to TerminateProcess, every
+    // other signal to the graceful WM_CLOSE/CTRL_BREAK request, so the usual
+    // TERM→KILL escalation still gives the child a chance to clean up.
+    if signal == signal_by_name_or_value("KILL").unwrap() {
+        windows::force_terminate(process);
+    } else {
+        windows::request_graceful_shutdown(process);
+    }
+}
+
+#[cfg(unix)]
 fn send_signal(process: &mut Child, signal: usize, foreground: bool) {
     // NOTE: GNU timeout doesn't check for errors of signal.
     // The subprocess might have exited just after the timeout.
@@ -214,50 +528,82 @@ fn send_signal(process: &mut Child, signal: usize, foreground: bool) {
     }
 }
 
-/// Wait for a child process and send a kill signal if it does not terminate.
+/// Wait for a child process, escalating through the remaining signal chain.
 ///
 /// This function waits for the child `process` for the time period
 /// given by `duration`. If the child process does not terminate
-/// within that time, we send the `SIGKILL` signal to it. If `verbose`
-/// is `true`, then a message is printed to `stderr` when that
-/// happens.
+/// within that time, the next signal of `chain` is sent and the wait
+/// starts over; once the chain is exhausted, the `SIGKILL` signal is
+/// sent and we wait for the child unconditionally. If `verbose` is
+/// `true`, then a message is printed to `stderr` for every signal.
 ///
-/// If the child process terminates within the given time period and
-/// `preserve_status` is `true`, then the status code of the child
-/// process is returned. If the child process terminates within the
-/// given time period and `preserve_status` is `false`, then 124 is
-/// returned. If the child does not terminate within the time period,
-/// then 137 is returned. Finally, if there is an error while waiting
-/// for the child process to terminate, then 124 is returned.
+/// If the child process terminates within one of the waiting periods
+/// and `preserve_status` is `true`, then the status code of the child
+/// process is returned. If the child process terminates within one of
+/// the waiting periods and `preserve_status` is `false`, then 124 is
+/// returned. If the child does not terminate before the chain runs
+/// out, then 137 is returned. Finally, if there is an error while
+/// waiting for the child process to terminate, then 124 is returned.
 ///
 /// # Errors
 ///
 /// If there is a problem sending the `SIGKILL` signal or waiting for
 /// the process after that signal is sent.
+#[allow(clippy::too_many_arguments)]
 fn wait_or_kill_process(
     process: &mut Child,
     cmd: &str,
     duration: Duration,
+    chain: &[usize],
     preserve_status: bool,
     foreground: bool,
-    verbose: bool,
+    verbose: u8,
+    started: Instant,
+    mut report: Option<&mut StatusReport>,
 ) -> std::io::Result<i32> {
-    match process.wait_or_timeout(duration) {
-        Ok(Some(status)) => {
-            if preserve_status {
-                Ok(status.code().unwrap_or_else(|| status.signal().unwrap()))
-            } else {
-                Ok(ExitStatus::TimeoutFailed.into())
+    let kill_signal = signal_by_name_or_value("KILL").unwrap();
+    for &signal in chain.iter().chain(std::iter::once(&kill_signal)) {
+        match process.wait_or_timeout(duration) {
+            Ok(Some(status)) => {
+                if let Some(report) = report.as_mut() {
+                    report.record_exit_status(&status);
+                }
+                return if preserve_status {
+                    Ok(status
+                        .code()
+                        .unwrap_or_else(|| child_termination_signal(&status).unwrap()))
+                } else {
+                    Ok(ExitStatus::TimeoutFailed.into())
+                };
             }
+            Ok(None) => {
+                report_if_verbose(signal, cmd, verbose, started);
+                send_signal(process, signal, foreground);
+                if let Some(report) = report.as_mut() {
+                    report.signal_sent = Some(signal);
+                }
+            }
+            Err(_) => return Ok(ExitStatus::WaitingFailed.into()),
         }
-        Ok(None) => {
-            let signal = signal_by_name_or_value("KILL").unwrap();
-            report_if_verbose(signal, cmd, verbose);
-            send_signal(process, signal, foreground);
-            process.wait()?;
-            Ok(ExitStatus::SignalSent(signal).into())
-        }
-        Err(_) => Ok(ExitStatus::WaitingFailed.into()),
+    }
+    // KILL has just been sent; nothing is left to escalate to, so wait
+    // without a time limit.
+    let status = process.wait()?;
+    if let Some(report) = report.as_mut() {
+        report.record_exit_status(&status);
+    }
+    Ok(ExitStatus::SignalSent(kill_signal).into())
+}
+
+/// The signal that terminated the child, if any. Windows has no equivalent;
+/// there `ExitStatus::code()` is always available instead.
+fn child_termination_signal(status: &process::ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    return status.signal();
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
     }
 }
 
@@ -287,40 +633,215 @@ fn preserve_signal_info(signal: libc::c_int) -> libc::c_int {
     signal
 }
 
-/// TODO: Improve exit codes, and make them consistent with the GNU Coreutils exit codes.
+/// Environment variable carrying the budget of an outer
+/// `timeout --inherit-budget` to nested invocations, as fractional seconds
+/// since the Unix epoch at which the budget expires. A deadline rather than a
+/// remaining duration, so time spent between the nested invocations is
+/// charged against the budget as well.
+const BUDGET_DEADLINE_VAR: &str = "TIMEOUT_BUDGET_DEADLINE";
 
-fn timeout(
-    cmd: &[String],
-    duration: Duration,
-    signal: usize,
-    kill_after: Option<Duration>,
-    foreground: bool,
-    preserve_status: bool,
-    verbose: bool,
-) -> UResult<()> {
+/// Fractional seconds since the Unix epoch, the time base shared between
+/// nested timeouts for `--inherit-budget`. This is the one deliberate use
+/// of the wall clock here: the deadline has to be meaningful in another
+/// process, which a monotonic [`Instant`] is not. Everything measured
+/// within this process uses [`Instant`].
+fn unix_now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// The budget left by an enclosing `timeout --inherit-budget`, or `None`
+/// when we are the outermost one. An exhausted budget is reported as a zero
+/// duration, not as an error: the command still runs and the timeout fires
+/// right away, just as if it had been started moments earlier.
+fn inherited_budget() -> UResult<Option<Duration>> {
+    let Some(value) = std::env::var_os(BUDGET_DEADLINE_VAR) else {
+        return Ok(None);
+    };
+    let deadline = value
+        .to_str()
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|deadline| deadline.is_finite())
+        .ok_or_else(|| {
+            USimpleError::new(
+                ExitStatus::TimeoutFailed.into(),
+                format!("invalid deadline in ${BUDGET_DEADLINE_VAR}"),
+            )
+        })?;
+    Ok(Some(
+        Duration::try_from_secs_f64(deadline - unix_now()).unwrap_or(Duration::ZERO),
+    ))
+}
+
+/// Spawn the command with inherited standard streams, mapping spawn failures
+/// to the GNU exit codes: 127 if the command cannot be found, 126 if it
+/// exists but cannot be invoked (e.g. for lack of the execute permission).
+/// Errors of `timeout` itself keep using 125 elsewhere.
+fn spawn_command(config: &Config, budget_deadline: Option<f64>) -> UResult<Child> {
+    let cmd = &config.command;
+    let mut command = process::Command::new(&cmd[0]);
+    command
+        .args(&cmd[1..])
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+    if let Some(deadline) = budget_deadline {
+        command.env(BUDGET_DEADLINE_VAR, format!("{deadline:.6}"));
+    }
+    #[cfg(unix)]
+    if config.new_session {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid is async-signal-safe and only touches the process
+        // itself, so it is fine to call between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    command.spawn().map_err(|err| {
+        let exit_status = if err.kind() == ErrorKind::NotFound {
+            ExitStatus::CommandNotFound
+        } else {
+            ExitStatus::CannotInvoke
+        };
+        USimpleError::new(
+            exit_status.into(),
+            format!("failed to run command {}: {err}", cmd[0].quote()),
+        )
+    })
+}
+
+/// Run the actual timeout work in a forked supervisor and absorb its signal
+/// death (`--quiet-kill`). The regular code path deliberately kills itself
+/// with the signal the command died of, which makes an interactive shell
+/// print job-control noise like "Terminated"; here that happens to the
+/// forked child instead, and we exit normally with the customary 128+N code.
+/// We become a child subreaper first, so processes the command leaves behind
+/// reparent to us rather than to the shell, and are reaped once they exit.
+#[cfg(target_os = "linux")]
+fn timeout_quietly(config: &Config) -> UResult<()> {
+    // SAFETY: prctl with PR_SET_CHILD_SUBREAPER only sets an attribute of
+    // the calling process.
+    if unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) } != 0 {
+        return Err(USimpleError::new(
+            ExitStatus::TimeoutFailed.into(),
+            format!(
+                "cannot become a child subreaper: {}",
+                std::io::Error::last_os_error()
+            ),
+        ));
+    }
+    // SAFETY: no threads have been spawned yet, so the child may safely
+    // continue with the regular code path.
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(USimpleError::new(
+            ExitStatus::TimeoutFailed.into(),
+            format!(
+                "cannot fork the supervisor: {}",
+                std::io::Error::last_os_error()
+            ),
+        ));
+    }
+    if pid == 0 {
+        // the supervisor: run the normal logic, including the deliberate
+        // suicide-by-signal of [`preserve_signal_info`]
+        let code = match timeout(config) {
+            Ok(()) => 0,
+            Err(e) => e.code(),
+        };
+        process::exit(code);
+    }
+
+    let mut status = 0;
+    // SAFETY: waiting for the process we just forked; EINTR only restarts it.
+    while unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+        if std::io::Error::last_os_error().kind() != ErrorKind::Interrupted {
+            return Err(USimpleError::new(
+                ExitStatus::WaitingFailed.into(),
+                format!(
+                    "failed to wait for the supervisor: {}",
+                    std::io::Error::last_os_error()
+                ),
+            ));
+        }
+    }
+    // Reap whatever reparented to us on the way out, without waiting for
+    // survivors a --foreground run may leave behind.
+    // SAFETY: a WNOHANG wait never blocks and at worst reports ECHILD.
+    while unsafe { libc::waitpid(-1, std::ptr::null_mut(), libc::WNOHANG) } > 0 {}
+
+    let code = if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        ExitStatus::WaitingFailed.into()
+    };
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(code.into())
+    }
+}
+
+fn timeout(config: &Config) -> UResult<()> {
+    let cmd = &config.command;
+    let mut duration = config.duration;
+    let mut budget_deadline = None;
+    if config.inherit_budget {
+        if let Some(budget) = inherited_budget()? {
+            // A zero DURATION means "no limit", so the budget always wins;
+            // an exhausted budget is rounded up to the smallest representable
+            // timeout rather than down to "no limit".
+            if duration.is_zero() || budget < duration {
+                if config.verbose > 0 {
+                    show_error!(
+                        "reducing timeout to {:.6}s left by the enclosing timeout",
+                        budget.as_secs_f64()
+                    );
+                }
+                duration = budget.max(Duration::from_nanos(1));
+            }
+        }
+        budget_deadline = Some(unix_now() + duration.as_secs_f64());
+    }
+    let duration = duration;
+    let signals = &config.signals;
+    let kill_after = config.kill_after;
+    let foreground = config.foreground;
+    let preserve_status = config.preserve_status;
+    let verbose = config.verbose;
+
+    #[cfg(unix)]
     if !foreground {
         unsafe { libc::setpgid(0, 0) };
     }
     #[cfg(unix)]
     enable_pipe_errors()?;
 
-    let process = &mut process::Command::new(&cmd[0])
-        .args(&cmd[1..])
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()
-        .map_err(|err| {
-            let status_code = if err.kind() == ErrorKind::NotFound {
-                // FIXME: not sure which to use
-                127
-            } else {
-                // FIXME: this may not be 100% correct...
-                126
-            };
-            USimpleError::new(status_code, format!("failed to execute process: {err}"))
-        })?;
+    // Spawning happens before any timing starts: if the command cannot be
+    // run at all, we report that immediately and the timer is never armed.
+    let process = &mut spawn_command(config, budget_deadline)?;
+    // Monotonic reference for the offsets printed by -vv; deliberately not
+    // the wall clock, which may jump while the command runs.
+    let started = Instant::now();
+    #[cfg(unix)]
     unblock_sigchld();
+    // Done right after spawning, before the child can create processes of
+    // its own that would escape the job.
+    #[cfg(windows)]
+    windows::confine_to_job(process);
+
+    let mut report = config
+        .status_json
+        .then(|| StatusReport::new(config.duration));
+
     // Wait for the child process for the specified time period.
     //
     // If the process exits within the specified time period (the
@@ -333,20 +854,37 @@ fn timeout(
     // structure of `wait_or_kill_process()`. They can probably be
     // refactored into some common function.
     match process.wait_or_timeout(duration) {
-        Ok(Some(status)) => Err(status
-            .code()
-            .unwrap_or_else(|| preserve_signal_info(status.signal().unwrap()))
-            .into()),
+        Ok(Some(status)) => {
+            if let Some(report) = report.as_mut() {
+                report.record_exit_status(&status);
+                report.emit();
+            }
+            Err(status
+                .code()
+                .unwrap_or_else(|| preserve_signal_info(child_termination_signal(&status).unwrap()))
+                .into())
+        }
         Ok(None) => {
-            report_if_verbose(signal, &cmd[0], verbose);
-            send_signal(process, signal, foreground);
+            if let Some(hook) = &config.on_timeout {
+                run_on_timeout_hook(hook, process.id());
+            }
+            report_if_verbose(signals[0], &cmd[0], verbose, started);
+            send_signal(process, signals[0], foreground);
+            if let Some(report) = report.as_mut() {
+                report.timed_out = true;
+                report.signal_sent = Some(signals[0]);
+            }
             match kill_after {
                 None => {
                     let status = process.wait()?;
+                    if let Some(report) = report.as_mut() {
+                        report.record_exit_status(&status);
+                        report.emit();
+                    }
                     if preserve_status {
                         if let Some(ec) = status.code() {
                             Err(ec.into())
-                        } else if let Some(sc) = status.signal() {
+                        } else if let Some(sc) = child_termination_signal(&status) {
                             Err(ExitStatus::SignalSent(sc.try_into().unwrap()).into())
                         } else {
                             Err(ExitStatus::CommandTimedOut.into())
@@ -356,14 +894,21 @@ fn timeout(
                     }
                 }
                 Some(kill_after) => {
-                    match wait_or_kill_process(
+                    let result = wait_or_kill_process(
                         process,
                         &cmd[0],
                         kill_after,
+                        &signals[1..],
                         preserve_status,
                         foreground,
                         verbose,
-                    ) {
+                        started,
+                        report.as_mut(),
+                    );
+                    if let Some(report) = report.as_mut() {
+                        report.emit();
+                    }
+                    match result {
                         Ok(status) => Err(status.into()),
                         Err(e) => Err(USimpleError::new(
                             ExitStatus::TimeoutFailed.into(),
@@ -376,7 +921,11 @@ fn timeout(
         Err(_) => {
             // We're going to return ERR_EXIT_STATUS regardless of
             // whether `send_signal()` succeeds or fails
-            send_signal(process, signal, foreground);
+            send_signal(process, signals[0], foreground);
+            if let Some(report) = report.as_mut() {
+                report.signal_sent = Some(signals[0]);
+                report.emit();
+            }
             Err(ExitStatus::TimeoutFailed.into())
         }
     }
@@ -0,0 +1,143 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore HWND LPARAM WNDENUMPROC dwctrlevent
+
+//! Best-effort graceful shutdown of a child process on Windows.
+//!
+//! Windows has no Unix signals, so the TERM→KILL escalation is approximated
+//! in two phases: first every top-level window owned by the child receives
+//! `WM_CLOSE` and its console process group receives `CTRL_BREAK_EVENT`,
+//! both of which well-behaved applications treat as a request to exit; only
+//! the fallback after `--kill-after` resorts to `TerminateProcess`.
+//!
+//! There are no process groups that die together either, so the child is
+//! put into a kill-on-close job object right after spawning: the forced
+//! termination then takes down the child's entire process tree, and should
+//! timeout itself die first, closing the job handle does the same.
+
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use uucore::job_object::KillOnCloseJob;
+use uucore::show_warning;
+
+use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM, TRUE};
+use windows_sys::Win32::System::Console::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE,
+};
+
+/// The signals `--signal` accepts on Windows; they keep the conventional Unix
+/// values so exit codes and `--status-json` reports stay comparable.
+const SIGNALS: &[(&str, usize)] = &[("INT", 2), ("KILL", 9), ("TERM", 15)];
+
+pub fn signal_by_name_or_value(name_or_value: &str) -> Option<usize> {
+    if let Ok(value) = name_or_value.parse() {
+        return SIGNALS.iter().any(|&(_, v)| v == value).then_some(value);
+    }
+    let name = name_or_value.strip_prefix("SIG").unwrap_or(name_or_value);
+    SIGNALS
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, value)| value)
+}
+
+pub fn signal_name_by_value(value: usize) -> Option<&'static str> {
+    SIGNALS.iter().find(|&&(_, v)| v == value).map(|&(n, _)| n)
+}
+
+/// `uucore::process::ChildExt` look-alike so the shared code in `timeout.rs`
+/// can keep calling `wait_or_timeout` on the `Child` directly.
+pub trait ChildExt {
+    /// Wait for a process to finish or return after the specified duration.
+    /// A `timeout` of zero disables the timeout.
+    fn wait_or_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>>;
+}
+
+impl ChildExt for Child {
+    fn wait_or_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        if timeout == Duration::from_micros(0) {
+            return self.wait().map(Some);
+        }
+        // .try_wait() doesn't drop stdin, so we do it manually
+        drop(self.stdin.take());
+
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Ask the child to exit: `WM_CLOSE` to its top-level windows, then
+/// `CTRL_BREAK_EVENT` to its console process group. Both are only requests;
+/// the caller keeps waiting and escalates to [`force_terminate`].
+pub fn request_graceful_shutdown(process: &Child) {
+    let pid = process.id();
+    post_wm_close_to_windows_of(pid);
+    // SAFETY: only reads its integer arguments. A failure (e.g. the child has
+    // no console) is fine, the TerminateProcess fallback still applies.
+    unsafe {
+        GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid);
+    }
+}
+
+/// The job object the child was assigned to, kept alive for the rest of the
+/// run so its kill-on-close semantics cover an unexpected exit of timeout.
+static JOB: OnceLock<KillOnCloseJob> = OnceLock::new();
+
+/// Assign the freshly spawned child to a kill-on-close job object so that
+/// [`force_terminate`] reaches its whole process tree. Failures are only
+/// diagnosed; without a job the direct child can still be terminated.
+pub fn confine_to_job(process: &Child) {
+    match KillOnCloseJob::new() {
+        Ok(job) => {
+            if let Err(err) = job.assign(process) {
+                show_warning!("cannot assign command to job object: {err}");
+            }
+            let _ = JOB.set(job);
+        }
+        Err(err) => show_warning!("cannot create job object: {err}"),
+    }
+}
+
+/// `TerminateProcess` the child, the closest Windows gets to `SIGKILL`.
+/// Through the job object this takes the child's descendants down as well,
+/// not just the direct child.
+pub fn force_terminate(process: &mut Child) {
+    if let Some(job) = JOB.get() {
+        // every process in the job exits with the code TerminateProcess
+        // would have used
+        let _ = job.terminate(1);
+    }
+    let _ = process.kill();
+}
+
+unsafe extern "system" fn post_close_callback(window: HWND, target_pid: LPARAM) -> BOOL {
+    let mut window_pid = 0u32;
+    GetWindowThreadProcessId(window, &mut window_pid);
+    if window_pid as LPARAM == target_pid {
+        // Posted, not sent: a hung window must not hang timeout itself.
+        PostMessageW(window, WM_CLOSE, 0, 0);
+    }
+    TRUE // keep enumerating, the child may own several windows
+}
+
+fn post_wm_close_to_windows_of(pid: u32) {
+    // SAFETY: the callback only receives the pid we pass by value.
+    unsafe {
+        EnumWindows(Some(post_close_callback), pid as LPARAM);
+    }
+}
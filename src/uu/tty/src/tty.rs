@@ -8,8 +8,11 @@
 // spell-checker:ignore (ToDO) ttyname filedesc
 
 use clap::{crate_version, Arg, ArgAction, Command};
+use nix::sys::stat::SFlag;
 use std::io::{IsTerminal, Write};
-use uucore::error::{set_exit_code, UResult};
+use std::path::{Path, PathBuf};
+use uucore::display::Quotable;
+use uucore::error::{set_exit_code, UResult, USimpleError};
 use uucore::{format_usage, help_about, help_usage};
 
 const ABOUT: &str = help_about!("tty.md");
@@ -17,14 +20,60 @@ const USAGE: &str = help_usage!("tty.md");
 
 mod options {
     pub const SILENT: &str = "silent";
+    pub const FILE: &str = "file";
+    pub const GENERATE_COMPLETION: &str = "generate-completion";
+}
+
+/// Check whether `path` refers to the same terminal device as the process's
+/// controlling terminal (`--file`). Prints "same", "different" or
+/// "not a tty" and exits 0, 1 or 2 respectively.
+fn compare_with_tty(path: &Path, silent: bool) -> UResult<()> {
+    let Ok(tty_name) = nix::unistd::ttyname(std::io::stdin()) else {
+        if !silent {
+            println!("not a tty");
+        }
+        return Err(2.into());
+    };
+    let tty_stat = nix::sys::stat::stat(&tty_name)
+        .map_err(|e| USimpleError::new(3, format!("cannot stat {}: {e}", tty_name.quote())))?;
+    let path_stat = nix::sys::stat::stat(path)
+        .map_err(|e| USimpleError::new(3, format!("cannot stat {}: {e}", path.quote())))?;
+
+    if path_stat.st_rdev == tty_stat.st_rdev
+        && SFlag::from_bits_truncate(path_stat.st_mode).contains(SFlag::S_IFCHR)
+    {
+        if !silent {
+            println!("same");
+        }
+        Ok(())
+    } else {
+        if !silent {
+            println!("different");
+        }
+        Err(1.into())
+    }
 }
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().get_matches_from(args);
 
+    if let Some(shell) = matches.get_one::<clap_complete::Shell>(options::GENERATE_COMPLETION) {
+        clap_complete::generate(
+            *shell,
+            &mut uu_app(),
+            uucore::util_name(),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
     let silent = matches.get_flag(options::SILENT);
 
+    if let Some(path) = matches.get_one::<PathBuf>(options::FILE) {
+        return compare_with_tty(path, silent);
+    }
+
     // If silent, we don't need the name, only whether or not stdin is a tty.
     if silent {
         return if std::io::stdin().is_terminal() {
@@ -69,4 +118,23 @@ pub fn uu_app() -> Command {
                 .help("print nothing, only return an exit status")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::FILE)
+                .long(options::FILE)
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help(
+                    "test whether PATH refers to the controlling terminal and print \
+                    'same', 'different' or 'not a tty' (a uutils extension)",
+                ),
+        )
+        .arg(
+            Arg::new(options::GENERATE_COMPLETION)
+                .long(options::GENERATE_COMPLETION)
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(clap_complete::Shell))
+                .help(
+                    "print a completion script for SHELL to stdout and exit (a uutils extension)",
+                ),
+        )
 }
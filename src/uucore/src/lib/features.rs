@@ -61,5 +61,7 @@ pub mod signals;
 ))]
 pub mod utmpx;
 // ** windows-only
+#[cfg(all(windows, feature = "job-object"))]
+pub mod job_object;
 #[cfg(all(windows, feature = "wide"))]
 pub mod wide;
@@ -50,6 +50,70 @@ pub fn human_readable(size: u64, sfmt: SizeFormat) -> String {
     }
 }
 
+/// A single binary unit (power of 1024) for [`human_readable_fixed`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FixedUnit {
+    Kibi,
+    Mebi,
+    Gibi,
+    Tebi,
+    Pebi,
+    Exbi,
+}
+
+impl FixedUnit {
+    fn multiplier(self) -> u64 {
+        match self {
+            Self::Kibi => 1 << 10,
+            Self::Mebi => 1 << 20,
+            Self::Gibi => 1 << 30,
+            Self::Tebi => 1 << 40,
+            Self::Pebi => 1 << 50,
+            Self::Exbi => 1 << 60,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Self::Kibi => 'K',
+            Self::Mebi => 'M',
+            Self::Gibi => 'G',
+            Self::Tebi => 'T',
+            Self::Pebi => 'P',
+            Self::Exbi => 'E',
+        }
+    }
+}
+
+impl std::str::FromStr for FixedUnit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "K" | "k" => Ok(Self::Kibi),
+            "M" | "m" => Ok(Self::Mebi),
+            "G" | "g" => Ok(Self::Gibi),
+            "T" | "t" => Ok(Self::Tebi),
+            "P" | "p" => Ok(Self::Pebi),
+            "E" | "e" => Ok(Self::Exbi),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Format `size` in one fixed unit with one decimal place, e.g. always MiB.
+///
+/// Unlike [`human_readable`], which picks a different prefix per value, every
+/// size rendered with the same `unit` lines up in a column and can be summed
+/// without unit conversion.
+pub fn human_readable_fixed(size: u64, unit: FixedUnit) -> String {
+    format!(
+        "{:.1}{}",
+        size as f64 / unit.multiplier() as f64,
+        unit.symbol()
+    )
+}
+
 #[cfg(test)]
 #[test]
 fn test_human_readable() {
@@ -63,3 +127,18 @@ fn test_human_readable() {
         assert_eq!(human_readable(size, sfmt), expected_str);
     }
 }
+
+#[cfg(test)]
+#[test]
+fn test_human_readable_fixed() {
+    let test_cases = [
+        (133456345, FixedUnit::Mebi, "127.3M"),
+        (12 * 1024 * 1024, FixedUnit::Mebi, "12.0M"),
+        (8500, FixedUnit::Kibi, "8.3K"),
+        (512, FixedUnit::Gibi, "0.0G"),
+    ];
+
+    for &(size, unit, expected_str) in &test_cases {
+        assert_eq!(human_readable_fixed(size, unit), expected_str);
+    }
+}
@@ -0,0 +1,91 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore HANDLE JOBOBJECT LPVOID
+
+//! A Windows job object that kills every process assigned to it when the
+//! last handle to it is closed.
+//!
+//! Windows has no process groups that die together like Unix sessions do, so
+//! utilities that supervise a command (env, timeout) cannot otherwise
+//! guarantee that the command's descendants are cleaned up. Assigning the
+//! child to a kill-on-close job right after spawning closes that gap: once
+//! the supervising process exits, for whatever reason, the handle is closed
+//! and the whole tree is terminated by the kernel.
+
+use std::io;
+use std::mem;
+use std::os::windows::io::AsRawHandle;
+use std::process::Child;
+
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+
+/// A job object with `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` set. Dropping it
+/// closes the handle and thereby terminates every process still assigned.
+pub struct KillOnCloseJob {
+    handle: HANDLE,
+}
+
+impl KillOnCloseJob {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: an anonymous job object is created and configured before
+        // any process is assigned; the handle is owned by the returned value.
+        unsafe {
+            let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if handle == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Self { handle };
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            if SetInformationJobObject(
+                handle,
+                JobObjectExtendedLimitInformation,
+                std::ptr::addr_of!(info).cast(),
+                mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            ) == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+    }
+
+    /// Put the child (and, transitively, everything it spawns) into the job.
+    /// This should happen right after spawning, before the child had a
+    /// chance to create processes of its own.
+    pub fn assign(&self, child: &Child) -> io::Result<()> {
+        // SAFETY: both handles are valid for the duration of the call.
+        if unsafe { AssignProcessToJobObject(self.handle, child.as_raw_handle() as HANDLE) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Terminate every process assigned to the job right now, without waiting
+    /// for the handle to be closed. All of them exit with `exit_code`.
+    pub fn terminate(&self, exit_code: u32) -> io::Result<()> {
+        // SAFETY: the handle is valid until self is dropped.
+        if unsafe { TerminateJobObject(self.handle, exit_code) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for KillOnCloseJob {
+    fn drop(&mut self) {
+        // SAFETY: the handle was created in new() and is closed exactly once.
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
@@ -22,6 +22,7 @@ pub use uucore_procs::*;
 // * cross-platform modules
 pub use crate::mods::display;
 pub use crate::mods::error;
+pub use crate::mods::fake_clock;
 pub use crate::mods::io;
 pub use crate::mods::line_ending;
 pub use crate::mods::os;
@@ -86,6 +87,8 @@ pub use crate::features::signals;
 ))]
 pub use crate::features::utmpx;
 // ** windows-only
+#[cfg(all(windows, feature = "job-object"))]
+pub use crate::features::job_object;
 #[cfg(all(windows, feature = "wide"))]
 pub use crate::features::wide;
 
@@ -0,0 +1,50 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Clock scaling for the test suite, honored by the time-driven utilities.
+//!
+//! With `UUTILS_FAKE_CLOCK_SCALE` set to a positive integer, every duration
+//! passed through [`scaled`] is divided by it, so a test exercising a
+//! multi-second timeout finishes in milliseconds and stays deterministic.
+//! The variable is read once per process; without it the functions are
+//! no-ops, so production behavior is unchanged.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The environment variable holding the scale factor.
+pub const SCALE_ENV_VAR: &str = "UUTILS_FAKE_CLOCK_SCALE";
+
+fn scale() -> u32 {
+    static SCALE: OnceLock<u32> = OnceLock::new();
+    *SCALE.get_or_init(|| {
+        std::env::var(SCALE_ENV_VAR)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&scale| scale >= 1)
+            .unwrap_or(1)
+    })
+}
+
+/// `duration` divided by the configured scale; the identity without the
+/// environment variable.
+pub fn scaled(duration: Duration) -> Duration {
+    match scale() {
+        1 => duration,
+        scale => duration / scale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scaled;
+    use std::time::Duration;
+
+    #[test]
+    fn test_identity_without_the_variable() {
+        // the suite never sets the variable for its own process
+        assert_eq!(scaled(Duration::from_secs(5)), Duration::from_secs(5));
+    }
+}
@@ -310,6 +310,56 @@ pub fn parse_size_u128_max(size: &str) -> Result<u128, ParseSizeError> {
     Parser::default().parse_u128_max(size)
 }
 
+/// A block size parsed with the full GNU `--block-size` conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSize {
+    /// A fixed number of bytes to scale by. `thousands_separators` is set by
+    /// a leading `'` in the specification and asks for grouping the scaled
+    /// numbers into groups of three digits.
+    Bytes {
+        size: u64,
+        thousands_separators: bool,
+    },
+    /// The `human-readable` keyword: sizes with a suffix, powers of 1024.
+    HumanBinary,
+    /// The `si` keyword: sizes with a suffix, powers of 1000.
+    HumanDecimal,
+}
+
+/// Parse a GNU style block size specification.
+///
+/// This understands everything [`parse_size_u64`] does (so `K`/`KiB` are
+/// powers of 1024 while `KB` is a power of 1000), plus the keywords
+/// `human-readable` and `si` and an optional leading `'` requesting
+/// thousands separators in the scaled output.
+///
+/// # Examples
+///
+/// ```rust
+/// use uucore::parse_size::{parse_block_size, BlockSize};
+/// assert_eq!(Ok(BlockSize::HumanBinary), parse_block_size("human-readable"));
+/// assert_eq!(
+///     Ok(BlockSize::Bytes { size: 1024, thousands_separators: true }),
+///     parse_block_size("'1K")
+/// );
+/// ```
+pub fn parse_block_size(size: &str) -> Result<BlockSize, ParseSizeError> {
+    match size {
+        "human-readable" => Ok(BlockSize::HumanBinary),
+        "si" => Ok(BlockSize::HumanDecimal),
+        _ => {
+            let (rest, thousands_separators) = match size.strip_prefix('\'') {
+                Some(rest) => (rest, true),
+                None => (size, false),
+            };
+            parse_size_u64(rest).map(|size| BlockSize::Bytes {
+                size,
+                thousands_separators,
+            })
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParseSizeError {
     InvalidSuffix(String), // Suffix
@@ -0,0 +1,59 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use glob::Pattern;
+use uu_du::excludes::{is_excluded, ExcludePattern};
+
+const NUM_PATTERNS: usize = 100;
+const NUM_PATHS: usize = 1_000_000;
+
+/// A mix of literal names, suffix globs and directory globs, like a grown
+/// `--exclude-from` file. Only the last few paths match any of them, so the
+/// benchmark measures the cost of scanning the whole list per path.
+fn make_patterns() -> Vec<ExcludePattern> {
+    (0..NUM_PATTERNS)
+        .map(|i| {
+            let pattern = match i % 3 {
+                0 => format!("*.tmp{i}"),
+                1 => format!("build-{i}"),
+                _ => format!("target/debug-{i}/*"),
+            };
+            ExcludePattern::new(Pattern::new(&pattern).unwrap(), i % 10 == 9)
+        })
+        .collect()
+}
+
+fn make_paths() -> Vec<String> {
+    (0..NUM_PATHS)
+        .map(|i| match i % 4 {
+            0 => format!("src/module{}/file{i}.rs", i % 100),
+            1 => format!("docs/chapter{}/section{i}.md", i % 50),
+            2 => format!("target/debug-7/artifact{i}.o"),
+            _ => format!("scratch{i}.tmp42"),
+        })
+        .collect()
+}
+
+fn excludes(c: &mut Criterion) {
+    let patterns = make_patterns();
+    let paths = make_paths();
+
+    let mut group = c.benchmark_group("excludes");
+    group.throughput(Throughput::Elements(NUM_PATHS as u64));
+    group.sample_size(10);
+    group.bench_function("100_patterns_1m_paths", |b| {
+        b.iter(|| {
+            paths
+                .iter()
+                .filter(|path| is_excluded(&patterns, &[path]))
+                .count()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, excludes);
+criterion_main!(benches);
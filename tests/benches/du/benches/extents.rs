@@ -0,0 +1,54 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use uu_du::physical_extents::{Extent, Granularity, SeenPhysicalExtents};
+
+const NUM_EXTENTS: usize = 1_000_000;
+
+/// A workload resembling a large reflinked tree: mostly fresh disjoint
+/// extents, with every fourth one repeating an earlier range (shared
+/// storage) and every tenth one straddling two neighbours (partial
+/// overlap forcing a merge).
+fn make_extents() -> Vec<Extent> {
+    (0..NUM_EXTENTS as u64)
+        .map(|i| {
+            let range = if i % 10 == 9 {
+                (i - 2) * 4096 + 2048..i * 4096 + 2048
+            } else if i % 4 == 3 {
+                (i / 2) * 4096..(i / 2) * 4096 + 4096
+            } else {
+                i * 4096..(i + 1) * 4096
+            };
+            Extent {
+                space: (1, 0),
+                range,
+            }
+        })
+        .collect()
+}
+
+fn extents(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extents");
+    group.throughput(Throughput::Elements(NUM_EXTENTS as u64));
+    group.sample_size(10);
+    group.bench_function("1m_inserts_with_sharing", |b| {
+        b.iter_batched(
+            make_extents,
+            |extents| {
+                let mut seen = SeenPhysicalExtents::new(Granularity::Extent);
+                extents
+                    .iter()
+                    .map(|extent| seen.get_overlapping_and_insert(extent))
+                    .sum::<u64>()
+            },
+            criterion::BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, extents);
+criterion_main!(benches);
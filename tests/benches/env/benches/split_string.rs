@@ -0,0 +1,44 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::ffi::OsString;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use uu_env::native_int_str::{to_native_int_representation, NativeIntString};
+use uu_env::split_iterator::split;
+
+/// Builds a `-S` string of roughly `size` bytes out of representative
+/// material: plain words, quoting, escapes and variable references.
+/// Shebang lines generated by tooling easily reach this kind of length.
+fn make_input(size: usize) -> OsString {
+    const PIECE: &str = "command --flag='quoted value' \"double $PATH quoted\" plain\\_word \\$x ";
+    let mut input = String::with_capacity(size + PIECE.len());
+    while input.len() < size {
+        input.push_str(PIECE);
+    }
+    OsString::from(input)
+}
+
+fn split_string(c: &mut Criterion) {
+    let mut group = c.benchmark_group("split_string");
+    // The parser must stay linear in the input length; watch for
+    // superlinear growth between the sizes here.
+    for size in [1usize << 10, 1 << 16, 1 << 20] {
+        let input = make_input(size);
+        let native = to_native_int_representation(&input);
+        group.throughput(Throughput::Bytes(native.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &*native,
+            |b, native| {
+                b.iter(|| -> Vec<NativeIntString> { split(native).unwrap() });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, split_string);
+criterion_main!(benches);
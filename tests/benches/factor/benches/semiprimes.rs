@@ -0,0 +1,60 @@
+// This file is part of the uutils coreutils package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore funcs semiprimes
+
+//! The semiprime-heavy counterpart of the `table` benchmark: products of
+//! two primes of similar size, the worst case for general factorization,
+//! comparing our Pollard rho backend against num_prime.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const INPUT_SIZE: usize = 32;
+
+/// Deterministic semiprimes with both factors close to 2^32.
+fn make_semiprimes() -> Vec<u64> {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    const SEED: u64 = 0x5e31_b41a_c01d_cafe; // spell-checker:disable-line
+
+    let mut rng = ChaCha8Rng::seed_from_u64(SEED);
+    let mut next_prime = move || loop {
+        let candidate: u64 = rng.gen_range((1 << 31)..(1 << 32));
+        if uu_factor::rho::is_prime(candidate) {
+            return candidate;
+        }
+    };
+    (0..INPUT_SIZE).map(|_| next_prime() * next_prime()).collect()
+}
+
+fn semiprimes(c: &mut Criterion) {
+    let inputs = make_semiprimes();
+
+    let mut group = c.benchmark_group("semiprimes");
+    group.throughput(Throughput::Elements(INPUT_SIZE as u64));
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::new("rho", "32_semiprimes"), &inputs, |b, inputs| {
+        b.iter(|| {
+            for &n in inputs {
+                let _r = uu_factor::rho::factor(n);
+            }
+        });
+    });
+    group.bench_with_input(
+        BenchmarkId::new("num_prime", "32_semiprimes"),
+        &inputs,
+        |b, inputs| {
+            b.iter(|| {
+                for &n in inputs {
+                    let _r = num_prime::nt_funcs::factors(n, None);
+                }
+            });
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, semiprimes);
+criterion_main!(benches);
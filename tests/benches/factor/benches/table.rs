@@ -35,6 +35,18 @@ fn table(c: &mut Criterion) {
                 }
             });
         });
+        // the same inputs with the small factors stripped by trial division
+        // first, as `factor` itself does it
+        group.bench_with_input(BenchmarkId::new("trial+factor", &a_str), &a, |b, &a| {
+            b.iter(|| {
+                for n in a {
+                    let (_small, cofactor) = uu_factor::trial::partial_factor(n);
+                    if cofactor > 1 {
+                        let _r = num_prime::nt_funcs::factors(cofactor, None);
+                    }
+                }
+            });
+        });
     }
     group.finish();
 }
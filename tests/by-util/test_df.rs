@@ -353,9 +353,9 @@ fn test_total() {
     let last_line = lines.last().unwrap();
     let mut iter = last_line.split_whitespace();
     assert_eq!(iter.next().unwrap(), "total");
-    let reported_total_size = iter.next().unwrap().parse().unwrap();
-    let reported_total_used = iter.next().unwrap().parse().unwrap();
-    let reported_total_avail = iter.next().unwrap().parse().unwrap();
+    let reported_total_size: u64 = iter.next().unwrap().parse().unwrap();
+    let reported_total_used: u64 = iter.next().unwrap().parse().unwrap();
+    let reported_total_avail: u64 = iter.next().unwrap().parse().unwrap();
 
     // Loop over each row except the last, computing the sum of each column.
     let mut computed_total_size = 0;
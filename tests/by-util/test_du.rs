@@ -1077,6 +1077,40 @@ fn test_du_files0_from_with_invalid_zero_length_file_names() {
         .stderr_contains("filelist:3: invalid zero-length file name");
 }
 
+#[test]
+fn test_du_files0_from_trailing_slash_on_file() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.touch("testfile");
+    at.mkdir("testdir");
+
+    at.write("filelist", "testfile/\0testdir///\0");
+
+    ts.ucmd()
+        .arg("--files0-from=filelist")
+        .fails()
+        .code_is(1)
+        .stdout_contains("\ttestdir/\n")
+        .stderr_is("du: filelist:1: cannot access 'testfile/': Not a directory\n");
+}
+
+#[test]
+fn test_du_files0_from_stdin_trailing_slash_on_file() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.touch("testfile");
+
+    ts.ucmd()
+        .arg("--files0-from=-")
+        .pipe_in("testfile/\0testfile\0")
+        .fails()
+        .code_is(1)
+        .stdout_contains("\ttestfile\n")
+        .stderr_is("du: -:1: cannot access 'testfile/': Not a directory\n");
+}
+
 #[test]
 fn test_du_files0_from_stdin() {
     let ts = TestScenario::new(util_name!());
@@ -1147,6 +1181,43 @@ fn test_du_files0_from_combined() {
     assert!(stderr.contains("file operands cannot be combined with --files0-from"));
 }
 
+#[test]
+fn test_du_files0_from_error_on_later_entry_keeps_earlier_output() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.touch("testfile1");
+    at.touch("testfile2");
+    at.write("filelist", "testfile1\0missing\0testfile2\0");
+
+    ts.ucmd()
+        .arg("--files0-from=filelist")
+        .fails()
+        .code_is(1)
+        .stdout_contains("\ttestfile1\n")
+        .stdout_contains("\ttestfile2\n")
+        .stderr_contains("missing");
+}
+
+#[test]
+fn test_du_files0_from_streams_a_large_list() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    let file = "testfile";
+
+    at.touch(file);
+
+    // mostly duplicates: the whole list is consumed entry by entry, but the
+    // deduplicated file is reported only once
+    let input = format!("{file}\0").repeat(10_000);
+
+    ts.ucmd()
+        .arg("--files0-from=-")
+        .pipe_in(input)
+        .succeeds()
+        .stdout_is(format!("0\t{file}\n"));
+}
+
 #[test]
 fn test_invalid_time_style() {
     let ts = TestScenario::new(util_name!());
@@ -1156,3 +1227,1144 @@ fn test_invalid_time_style() {
         .succeeds()
         .stdout_does_not_contain("du: invalid argument 'banana' for 'time style'");
 }
+
+#[test]
+fn test_du_time_style_predefined_and_custom() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ccmd("touch")
+        .env("TZ", "UTC")
+        .args(&["-m", "-t", "201606160000", "date_test"])
+        .succeeds();
+
+    for (style, expected) in [
+        (
+            "full-iso",
+            "0\t2016-06-16 00:00:00.000000000 +0000\tdate_test\n",
+        ),
+        ("long-iso", "0\t2016-06-16 00:00\tdate_test\n"),
+        ("iso", "0\t2016-06-16\tdate_test\n"),
+        ("+%Y__%H%M", "0\t2016__0000\tdate_test\n"),
+        ("+literal", "0\tliteral\tdate_test\n"),
+    ] {
+        ts.ucmd()
+            .env("TZ", "UTC")
+            .arg("--time")
+            .arg(format!("--time-style={style}"))
+            .arg("date_test")
+            .succeeds()
+            .stdout_only(expected);
+    }
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_du_time_style_matches_gnu() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ccmd("touch")
+        .args(&["-m", "-t", "201606160000", "date_test"])
+        .succeeds();
+
+    for style in ["full-iso", "long-iso", "iso", "+%Y-%m-%dT%H:%M:%S", "+%s"] {
+        let style_arg = format!("--time-style={style}");
+        let args = ["--time", style_arg.as_str(), "date_test"];
+        let result = ts.ucmd().args(&args).succeeds();
+        let result_reference = unwrap_or_return!(expected_result(&ts, &args));
+        if result_reference.succeeded() {
+            assert_eq!(
+                result.stdout_str(),
+                result_reference.stdout_str(),
+                "style {style}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_du_by_extension() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.mkdir("dir");
+    at.write("dir/first.txt", "hello");
+    at.write("dir/second.txt", "hi");
+    at.write("dir/image.png", "png");
+    at.write("dir/noext", "x");
+
+    let result = ts
+        .ucmd()
+        .arg("--by-extension")
+        .arg("--apparent-size")
+        .arg("--block-size=1")
+        .arg("dir")
+        .succeeds();
+
+    assert_eq!(result.stdout_str(), "7\t2\ttxt\n3\t1\tpng\n1\t1\t<none>\n");
+}
+
+#[test]
+fn test_du_by_extension_respects_excludes() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.mkdir("dir");
+    at.write("dir/first.txt", "hello");
+    at.write("dir/image.png", "png");
+
+    let result = ts
+        .ucmd()
+        .arg("--by-extension")
+        .arg("--apparent-size")
+        .arg("--block-size=1")
+        .arg("--exclude=*.png")
+        .arg("dir")
+        .succeeds();
+
+    assert_eq!(result.stdout_str(), "5\t1\ttxt\n");
+}
+
+#[test]
+fn test_du_same_fs_as_same_filesystem_matches_plain_du() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.mkdir("dir");
+    at.write("dir/file.txt", "some content");
+
+    let plain = ts.ucmd().arg("dir").succeeds().stdout_move_str();
+    let restricted = ts
+        .ucmd()
+        .arg("--same-fs-as=.")
+        .arg("dir")
+        .succeeds()
+        .stdout_move_str();
+
+    assert_eq!(plain, restricted);
+}
+
+#[test]
+fn test_du_same_fs_as_nonexistent_path() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ucmd()
+        .arg("--same-fs-as=nonexistent")
+        .fails()
+        .stderr_contains("cannot access 'nonexistent'");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_du_same_fs_as_skips_other_filesystem() {
+    use std::path::Path;
+
+    // /proc is a different (virtual) filesystem on any normal Linux setup
+    if !Path::new("/proc/self").exists() {
+        return;
+    }
+
+    let ts = TestScenario::new(util_name!());
+    let result = ts.ucmd().arg("--same-fs-as=/proc").arg(".").succeeds();
+    result.stderr_contains("on a different filesystem");
+}
+
+#[test]
+fn test_du_skip_bind_mounts_without_bind_mounts_in_tree() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("dir");
+    at.write("dir/file", "some content");
+
+    let plain = ts.ucmd().arg("dir").succeeds().stdout_move_str();
+    let skipping = ts
+        .ucmd()
+        .args(&["--skip-bind-mounts", "dir"])
+        .succeeds()
+        .stdout_move_str();
+    assert_eq!(plain, skipping);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_du_skip_bind_mounts_ignores_bind_mounted_directory() {
+    use std::process::Command;
+
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.mkdir("tree/source");
+    at.write("tree/source/file", &"x".repeat(4096));
+    at.mkdir("tree/bound");
+
+    // creating a bind mount needs root privileges
+    let mounted = Command::new("mount")
+        .args([
+            "--bind",
+            &at.plus_as_string("tree/source"),
+            &at.plus_as_string("tree/bound"),
+        ])
+        .status()
+        .is_ok_and(|status| status.success());
+    if !mounted {
+        println!("test skipped: creating a bind mount requires root");
+        return;
+    }
+
+    let with_flag = ts
+        .ucmd()
+        .args(&[
+            "--skip-bind-mounts",
+            "--verbose",
+            &at.plus_as_string("tree"),
+        ])
+        .run();
+    let without_flag = ts.ucmd().arg(&at.plus_as_string("tree")).run();
+
+    let _ = Command::new("umount")
+        .arg(at.plus_as_string("tree/bound"))
+        .status();
+
+    with_flag.success();
+    without_flag.success();
+    assert!(
+        with_flag.stdout_str().contains("ignored (bind mount)"),
+        "missing verbose note in: {}",
+        with_flag.stdout_str()
+    );
+}
+
+#[test]
+fn test_du_generate_completion_includes_extensions() {
+    new_ucmd!()
+        .args(&["--generate-completion", "bash"])
+        .succeeds()
+        .stdout_contains("--skip-bind-mounts");
+}
+
+#[test]
+fn test_du_watch_reprints_summary_periodically() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("watched");
+    at.write("watched/file", "some content");
+
+    let mut child = ts
+        .ucmd()
+        .args(&["--watch=0.1", "--summarize", "watched"])
+        .run_no_wait();
+    std::thread::sleep(std::time::Duration::from_millis(550));
+    child.make_assertion().is_alive();
+    child.kill();
+
+    let output = child.wait().unwrap();
+    let summaries = output
+        .stdout_str()
+        .lines()
+        .filter(|line| line.ends_with("watched"))
+        .count();
+    assert!(
+        summaries >= 2,
+        "expected repeated summaries, got: {}",
+        output.stdout_str()
+    );
+}
+
+#[test]
+fn test_du_watch_invalid_interval() {
+    new_ucmd!()
+        .args(&["--watch=0", "."])
+        .fails()
+        .stderr_contains("invalid interval '0' for '--watch'");
+}
+
+#[test]
+fn test_du_respect_gitignore_excludes_ignored_files() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/.gitignore", "*.log\nbuild/\n");
+    at.write("tree/app.rs", "fn main() {}\n");
+    at.write("tree/debug.log", "noise\n");
+    at.mkdir("tree/build");
+    at.write("tree/build/out", "artifact\n");
+
+    let result = ts
+        .ucmd()
+        .args(&["--all", "--respect-gitignore", "tree"])
+        .succeeds();
+    result.stdout_contains("app.rs");
+    assert!(!result.stdout_str().contains("debug.log"));
+    assert!(!result.stdout_str().contains("build"));
+
+    // the verbose mode names each path it skips
+    ts.ucmd()
+        .args(&["--all", "--respect-gitignore", "--verbose", "tree"])
+        .succeeds()
+        .stdout_contains("ignored (gitignore)");
+}
+
+#[test]
+fn test_du_respect_gitignore_nested_negation() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/.gitignore", "*.log\n");
+    at.write("tree/top.log", "a\n");
+    at.mkdir("tree/sub");
+    at.write("tree/sub/.gitignore", "!keep.log\n");
+    at.write("tree/sub/keep.log", "b\n");
+    at.write("tree/sub/drop.log", "c\n");
+
+    let result = ts
+        .ucmd()
+        .args(&["--all", "--respect-gitignore", "tree"])
+        .succeeds();
+    result.stdout_contains("keep.log");
+    assert!(!result.stdout_str().contains("top.log"));
+    assert!(!result.stdout_str().contains("drop.log"));
+}
+
+#[test]
+fn test_du_without_respect_gitignore_counts_everything() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/.gitignore", "*.log\n");
+    at.write("tree/debug.log", "noise\n");
+
+    ts.ucmd()
+        .args(&["--all", "tree"])
+        .succeeds()
+        .stdout_contains("debug.log");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_du_inodes_percent() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("d");
+    at.write("d/file", "content");
+
+    let result = ts.ucmd().args(&["--inodes", "--percent", "d"]).succeeds();
+    // each line carries an extra percentage column: "<inodes>\t<pct>%\t<path>"
+    for line in result.stdout_str().lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert_eq!(fields.len(), 3, "unexpected line: {line}");
+        assert!(fields[1].ends_with('%'), "unexpected line: {line}");
+        fields[1]
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .expect("percentage should be numeric");
+    }
+}
+
+#[test]
+fn test_du_percent_requires_inodes() {
+    new_ucmd!().args(&["--percent", "."]).fails().code_is(1);
+}
+
+#[test]
+fn test_du_min_depth_suppresses_shallow_entries() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("parent");
+    at.mkdir("parent/project_a");
+    at.mkdir("parent/project_b");
+    at.write("parent/project_a/file", "content");
+
+    let result = ts.ucmd().args(&["--min-depth=1", "parent"]).succeeds();
+    result.stdout_contains("project_a");
+    result.stdout_contains("project_b");
+    assert!(!result
+        .stdout_str()
+        .lines()
+        .any(|line| line.ends_with("parent")));
+}
+
+#[test]
+fn test_du_min_depth_composes_with_max_depth() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("parent");
+    at.mkdir("parent/mid");
+    at.mkdir("parent/mid/deep");
+
+    let result = ts
+        .ucmd()
+        .args(&["--min-depth=1", "--max-depth=1", "parent"])
+        .succeeds();
+    result.stdout_contains("mid");
+    assert!(!result.stdout_str().contains("deep"));
+    assert!(!result
+        .stdout_str()
+        .lines()
+        .any(|line| line.ends_with("parent")));
+}
+
+#[test]
+fn test_du_min_depth_keeps_grand_total() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("parent");
+    at.mkdir("parent/child");
+
+    // the total still accounts for the suppressed shallow entries
+    ts.ucmd()
+        .args(&["--min-depth=5", "--total", "parent"])
+        .succeeds()
+        .stdout_contains("total");
+}
+
+#[test]
+fn test_du_duplicates_reports_identical_files() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/one", "same content\n");
+    at.write("tree/two", "same content\n");
+    at.write("tree/other", "different content\n");
+
+    let result = ts.ucmd().args(&["--duplicates", "tree"]).succeeds();
+    result.stdout_contains("duplicate group (13 bytes each):");
+    result.stdout_contains("tree/one");
+    result.stdout_contains("tree/two");
+    result.stdout_contains("wasted bytes in duplicates: 13");
+    // unique files are not part of any group
+    assert!(!result
+        .stdout_str()
+        .lines()
+        .any(|line| line.trim_start() == "tree/other"));
+}
+
+#[test]
+fn test_du_duplicates_same_size_different_content() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/aaa", "aaaa");
+    at.write("tree/bbb", "bbbb");
+
+    ts.ucmd()
+        .args(&["--duplicates", "tree"])
+        .succeeds()
+        .stdout_contains("wasted bytes in duplicates: 0")
+        .stdout_does_not_contain("duplicate group");
+}
+
+#[test]
+fn test_du_without_duplicates_has_no_report() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("tree/one", "same content\n");
+    at.write("tree/two", "same content\n");
+
+    ts.ucmd()
+        .arg("tree")
+        .succeeds()
+        .stdout_does_not_contain("wasted bytes");
+}
+
+#[test]
+fn test_du_invalid_min_depth() {
+    new_ucmd!()
+        .args(&["--min-depth=x", "."])
+        .fails()
+        .stderr_contains("invalid minimum depth 'x'");
+}
+
+#[test]
+fn test_du_unit_locks_output_to_one_scale() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("d");
+    at.write_bytes("d/big", &vec![0u8; 3 * 1024 * 1024]);
+    at.write_bytes("d/small", &vec![0u8; 4 * 1024]);
+
+    let result = ts
+        .ucmd()
+        .args(&["--unit=M", "--apparent-size", "--all", "d"])
+        .succeeds();
+    // every line shows MiB with one decimal, regardless of magnitude
+    result.stdout_contains("3.0M\t");
+    result.stdout_contains("0.0M\t");
+    for line in result.stdout_str().lines() {
+        let size = line.split('\t').next().unwrap();
+        assert!(size.ends_with('M'), "unexpected line: {line}");
+    }
+}
+
+#[test]
+fn test_du_unit_kibibytes_with_decimal() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("f", &vec![0u8; 1536]);
+
+    ts.ucmd()
+        .args(&["--unit=K", "--apparent-size", "f"])
+        .succeeds()
+        .stdout_is("1.5K\tf\n");
+}
+
+#[test]
+fn test_du_unit_invalid_argument() {
+    new_ucmd!()
+        .args(&["--unit=X", "."])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid argument 'X' for '--unit'");
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_shared_extents_counts_hard_links_once() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("a", &vec![0u8; 4096]);
+    at.hard_link("a", "b");
+
+    // without --shared-extents, hard links given as separate arguments are
+    // counted twice
+    let plain = ts.ucmd().args(&["a", "b"]).succeeds().stdout_move_str();
+    let plain_total: u64 = plain
+        .lines()
+        .map(|line| line.split('\t').next().unwrap().parse::<u64>().unwrap())
+        .sum();
+
+    let shared = ts
+        .ucmd()
+        .args(&["--shared-extents", "a", "b"])
+        .succeeds()
+        .stdout_move_str();
+    let shared_sizes: Vec<u64> = shared
+        .lines()
+        .map(|line| line.split('\t').next().unwrap().parse::<u64>().unwrap())
+        .collect();
+    assert_eq!(shared_sizes[1], 0, "second link should be fully shared");
+    assert_eq!(shared_sizes.iter().sum::<u64>(), plain_total / 2);
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_shared_percent_column() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("a", &vec![0u8; 4096]);
+    at.hard_link("a", "b");
+
+    let result = ts
+        .ucmd()
+        .args(&[
+            "--all",
+            "--shared-extents",
+            "--shared-percent",
+            "a",
+            "b",
+            ".",
+        ])
+        .succeeds();
+    result.stdout_contains("\t0.0%\ta\n");
+    result.stdout_contains("\t100.0%\tb\n");
+    // directories have no extent data, so the column shows a placeholder
+    result.stdout_contains("\t-\t.\n");
+}
+
+#[test]
+fn test_du_shared_extents_invalid_granularity() {
+    new_ucmd!()
+        .args(&["--shared-extents=bogus", "."])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid argument 'bogus' for '--shared-extents'");
+}
+
+#[test]
+fn test_du_shared_percent_requires_shared_extents() {
+    new_ucmd!()
+        .args(&["--shared-percent", "."])
+        .fails()
+        .stderr_contains("--shared-extents");
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_cache_reuses_unchanged_subtrees() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("tree/sub");
+    at.write_bytes("tree/sub/f", &vec![0u8; 8192]);
+
+    let first = ts
+        .ucmd()
+        .args(&["--cache=du.cache", "tree"])
+        .succeeds()
+        .stdout_move_str();
+    assert!(at.file_exists("du.cache"));
+
+    let second = ts
+        .ucmd()
+        .args(&["--cache=du.cache", "--verbose", "tree"])
+        .succeeds();
+    second.stdout_contains("reused from cache");
+    second.stdout_contains(first.lines().last().unwrap());
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_cache_detects_changes_in_subdirectories() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("tree/sub");
+    at.write_bytes("tree/sub/f", &vec![0u8; 8192]);
+
+    ts.ucmd().args(&["--cache=du.cache", "tree"]).succeeds();
+
+    // only visible in the subdirectory's metadata, not in tree's
+    at.write_bytes("tree/sub/g", &vec![0u8; 8192]);
+    let rescanned = ts
+        .ucmd()
+        .args(&["--cache=du.cache", "tree"])
+        .succeeds()
+        .stdout_move_str();
+    let fresh = ts.ucmd().arg("tree").succeeds().stdout_move_str();
+    assert_eq!(rescanned, fresh);
+}
+
+#[test]
+fn test_du_cache_conflicts_with_separate_dirs() {
+    new_ucmd!()
+        .args(&["--cache=du.cache", "-S", "."])
+        .fails()
+        .stderr_contains("cannot be used with");
+}
+
+#[test]
+fn test_du_blocks_prints_raw_st_blocks() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("blocksfile", &[0u8; 8192]);
+
+    let raw_512 = ts
+        .ucmd()
+        .args(&["--blocks=st_blocks", "blocksfile"])
+        .succeeds()
+        .stdout_move_str();
+    let blocks_512: u64 = raw_512.split_whitespace().next().unwrap().parse().unwrap();
+
+    // "512" is a synonym for "st_blocks"
+    ts.ucmd()
+        .args(&["--blocks=512", "blocksfile"])
+        .succeeds()
+        .stdout_is(&raw_512);
+
+    let raw_1024 = ts
+        .ucmd()
+        .args(&["--blocks=1024", "blocksfile"])
+        .succeeds()
+        .stdout_move_str();
+    let blocks_1024: u64 = raw_1024.split_whitespace().next().unwrap().parse().unwrap();
+    assert_eq!(blocks_1024, blocks_512.div_ceil(2));
+}
+
+#[test]
+fn test_du_blocks_ignores_block_size_env() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("blocksfile", &[0u8; 8192]);
+
+    let plain = ts
+        .ucmd()
+        .args(&["--blocks=512", "blocksfile"])
+        .succeeds()
+        .stdout_move_str();
+    ts.ucmd()
+        .env("DU_BLOCK_SIZE", "1M")
+        .env("BLOCK_SIZE", "1M")
+        .args(&["--blocks=512", "blocksfile"])
+        .succeeds()
+        .stdout_is(plain);
+}
+
+#[test]
+fn test_du_block_size_human_readable_keywords() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("bigfile", &[0u8; 2 * 1024 * 1024]);
+
+    let human = ts
+        .ucmd()
+        .args(&["-h", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_move_str();
+    ts.ucmd()
+        .args(&["--block-size=human-readable", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(&human);
+
+    let si = ts
+        .ucmd()
+        .args(&["--si", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_move_str();
+    ts.ucmd()
+        .args(&["--block-size=si", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(&si);
+}
+
+#[test]
+fn test_du_block_size_thousands_separator_flag() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("bigfile", &[0u8; 2 * 1024 * 1024]);
+
+    ts.ucmd()
+        .args(&["--block-size='1", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "2,097,152	bigfile
+",
+        );
+}
+
+#[test]
+fn test_du_block_size_si_vs_iec_suffixes() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("bigfile", &[0u8; 1024 * 1024]);
+
+    // KiB and K are powers of 1024, KB a power of 1000
+    ts.ucmd()
+        .args(&["--block-size=KiB", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "1024	bigfile
+",
+        );
+    ts.ucmd()
+        .args(&["--block-size=KB", "--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "1049	bigfile
+",
+        );
+}
+
+#[test]
+fn test_du_block_size_env_vars() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write_bytes("bigfile", &[0u8; 1024 * 1024]);
+
+    // DU_BLOCK_SIZE wins over BLOCK_SIZE, keywords work from the environment
+    ts.ucmd()
+        .env("DU_BLOCK_SIZE", "1M")
+        .env("BLOCK_SIZE", "1K")
+        .args(&["--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "1	bigfile
+",
+        );
+    ts.ucmd()
+        .env("BLOCK_SIZE", "human-readable")
+        .args(&["--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "1.0M	bigfile
+",
+        );
+
+    // the BSD style BLOCKSIZE only understands plain sizes
+    ts.ucmd()
+        .env("BLOCKSIZE", "human-readable")
+        .args(&["--apparent-size", "bigfile"])
+        .succeeds()
+        .stdout_is(
+            "1024	bigfile
+",
+        );
+}
+
+#[test]
+fn test_du_summarize_rejects_all() {
+    new_ucmd!()
+        .args(&["-s", "-a"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot both summarize and show all entries");
+}
+
+#[test]
+fn test_du_summarize_one_line_per_operand() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("dir");
+    at.write("dir/inner", "content");
+    at.write("plain", "content");
+
+    let result = ts.ucmd().args(&["-s", "dir", "plain"]).succeeds();
+    let stdout = result.stdout_str();
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.lines().any(|line| line.ends_with("\tdir")));
+    assert!(stdout.lines().any(|line| line.ends_with("\tplain")));
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_summarize_symlink_operand_gets_one_line() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("dir");
+    at.write("dir/inner", "content");
+    at.symlink_dir("dir", "link");
+
+    let result = ts.ucmd().args(&["-s", "link"]).succeeds();
+    let stdout = result.stdout_str();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.ends_with("\tlink\n"));
+}
+
+#[test]
+fn test_du_summarize_with_total() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("dir");
+    at.write("dir/inner", "content");
+    at.write("plain", "content");
+
+    let result = ts.ucmd().args(&["-s", "-c", "dir", "plain"]).succeeds();
+    let stdout = result.stdout_str();
+    assert_eq!(stdout.lines().count(), 3);
+    assert!(stdout.lines().last().unwrap().ends_with("\ttotal"));
+}
+
+#[test]
+fn test_du_blocks_invalid_unit() {
+    new_ucmd!()
+        .args(&["--blocks=7", "."])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid argument '7' for '--blocks'");
+}
+
+#[test]
+fn test_du_blocks_conflicts_with_formatting_options() {
+    for arg in ["-k", "-h", "--apparent-size", "--inodes", "-B1"] {
+        new_ucmd!()
+            .args(&["--blocks=512", arg, "."])
+            .fails()
+            .stderr_contains("cannot be used with");
+    }
+}
+
+#[test]
+fn test_du_exclude_from_stdin() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.touch("tree/drop.log");
+    at.touch("tree/note.txt");
+
+    ts.ucmd()
+        .args(&["--all", "--exclude-from=-", "tree"])
+        .pipe_in("*.log\n")
+        .succeeds()
+        .stdout_contains("note.txt")
+        .stdout_does_not_contain("drop.log");
+}
+
+#[test]
+fn test_du_exclude_from_comments_and_blank_lines() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.touch("tree/drop.log");
+    at.touch("tree/note.txt");
+    at.write("patterns", "# a comment\n\n*.log\n");
+
+    ts.ucmd()
+        .args(&["--all", "--exclude-from=patterns", "tree"])
+        .succeeds()
+        .stdout_contains("note.txt")
+        .stdout_does_not_contain("drop.log");
+}
+
+#[test]
+fn test_du_exclude_from_negated_pattern_reincludes() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.touch("tree/keep.log");
+    at.touch("tree/drop.log");
+    at.write("patterns", "*.log\n!keep.log\n");
+
+    ts.ucmd()
+        .args(&["--all", "--exclude-from=patterns", "tree"])
+        .succeeds()
+        .stdout_contains("keep.log")
+        .stdout_does_not_contain("drop.log");
+}
+
+#[test]
+fn test_du_exclude_from_missing_file() {
+    new_ucmd!()
+        .args(&["--exclude-from=does-not-exist", "."])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot open 'does-not-exist' for reading");
+}
+
+#[test]
+fn test_du_output_file_writes_report() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.touch("tree/a");
+    at.touch("tree/b");
+
+    let expected = ts
+        .ucmd()
+        .args(&["--all", "tree"])
+        .succeeds()
+        .stdout_move_str();
+
+    ts.ucmd()
+        .args(&["--all", "--output-file=report.txt", "tree"])
+        .succeeds()
+        .no_stdout();
+
+    assert_eq!(at.read("report.txt"), expected);
+}
+
+#[test]
+fn test_du_output_file_leaves_no_temporary_file_behind() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.touch("tree/a");
+    at.mkdir("out");
+
+    ts.ucmd()
+        .args(&["--output-file=out/report.txt", "tree"])
+        .succeeds();
+
+    // only the finished report remains; the temporary file was renamed away
+    let entries: Vec<String> = std::fs::read_dir(at.plus("out"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(entries, vec!["report.txt"]);
+}
+
+#[test]
+fn test_du_output_file_replaces_existing_report() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("tree");
+    at.write("report.txt", "stale report\n");
+
+    ts.ucmd()
+        .args(&["--output-file=report.txt", "tree"])
+        .succeeds();
+
+    let report = at.read("report.txt");
+    assert!(
+        !report.contains("stale report"),
+        "old report not replaced: {report}"
+    );
+    assert!(report.contains("tree"));
+}
+
+#[test]
+fn test_du_output_file_in_missing_directory_fails() {
+    new_ucmd!()
+        .args(&["--output-file=no-such-dir/report.txt", "."])
+        .fails()
+        .stderr_contains("cannot create");
+}
+
+#[test]
+fn test_du_keeps_counting_when_files_vanish_mid_scan() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("race");
+    for i in 0..50 {
+        at.mkdir(&format!("race/dir{i}"));
+        for j in 0..20 {
+            at.write(&format!("race/dir{i}/file{j}"), "some content\n");
+        }
+    }
+
+    // Delete files while the scan is running. Whether a particular entry is
+    // still there when du stats it is up to the scheduler; every outcome has
+    // to end in either a clean run or a cannot-access diagnostic, never in an
+    // aborted subtree.
+    let race_dir = at.plus("race");
+    let deleter = std::thread::spawn(move || {
+        for i in 0..50 {
+            for j in 0..20 {
+                let _ = std::fs::remove_file(race_dir.join(format!("dir{i}/file{j}")));
+            }
+            let _ = std::fs::remove_dir(race_dir.join(format!("dir{i}")));
+        }
+    });
+    let result = ts.ucmd().args(&["-a", "race"]).run();
+    deleter.join().unwrap();
+
+    assert!(
+        result.code() == 0 || result.code() == 1,
+        "unexpected exit code {}",
+        result.code()
+    );
+    for line in result.stderr_str().lines() {
+        assert!(
+            line.starts_with("du: cannot access"),
+            "unexpected diagnostic: {line}"
+        );
+    }
+    // the scanned root is always reported, however much of it survived
+    result.stdout_contains("\trace");
+}
+
+#[test]
+fn test_du_sort_name_orders_entries() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.touch("dir/charlie");
+    at.touch("dir/alpha");
+    at.touch("dir/bravo");
+
+    let result = ucmd.args(&["--all", "--sort=name", "dir"]).succeeds();
+    let names: Vec<&str> = result
+        .stdout_str()
+        .lines()
+        .map(|line| line.split_once('\t').unwrap().1)
+        .collect();
+    // children in name order, the parent last
+    assert_eq!(
+        names,
+        vec!["dir/alpha", "dir/bravo", "dir/charlie", "dir"],
+        "unexpected order: {names:?}"
+    );
+}
+
+#[test]
+fn test_du_sort_name_runs_are_byte_identical() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("dir");
+    at.mkdir("dir/sub");
+    for name in ["zz", "a", "m", "sub/x", "sub/b"] {
+        at.touch(format!("dir/{name}"));
+    }
+
+    let first = ts.ucmd().args(&["--all", "--sort=name", "dir"]).succeeds();
+    let second = ts.ucmd().args(&["--all", "--sort=name", "dir"]).succeeds();
+    assert_eq!(first.stdout(), second.stdout());
+}
+
+#[test]
+fn test_du_sort_rejects_unknown_order() {
+    new_ucmd!()
+        .args(&["--sort=size", "."])
+        .fails()
+        .stderr_contains("invalid value 'size' for '--sort <WORD>'");
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_verbose_reports_hard_link_deduplication() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/alpha", &"x".repeat(600));
+    at.hard_link("dir/alpha", "dir/bravo");
+
+    // with --sort=name, alpha claims the inode and bravo is the skipped link
+    let result = ucmd
+        .args(&["--all", "--verbose", "--sort=name", "dir"])
+        .succeeds();
+    result.stdout_contains("'dir/bravo' skipped (hard link of 'dir/alpha', inode ");
+    result.stderr_contains("hard links: 1 skipped, 600 bytes saved");
+    // the skipped link is not listed as an entry of its own
+    assert_eq!(
+        result
+            .stdout_str()
+            .lines()
+            .filter(|line| line.ends_with("\tdir/bravo"))
+            .count(),
+        0
+    );
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_du_hard_link_report_needs_verbose() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/alpha", "content");
+    at.hard_link("dir/alpha", "dir/bravo");
+
+    ucmd.args(&["--all", "dir"])
+        .succeeds()
+        .stdout_does_not_contain("skipped")
+        .stderr_does_not_contain("hard links:");
+}
+
+#[test]
+fn test_du_exclude_size_skips_files_in_range() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/small", &"x".repeat(10));
+    at.write("dir/big", &"x".repeat(5000));
+
+    let result = ucmd
+        .args(&["--apparent-size", "-B1", "-a", "--exclude-size=1K..", "dir"])
+        .succeeds();
+    result.stdout_contains("dir/small");
+    result.stdout_does_not_contain("dir/big");
+}
+
+#[test]
+fn test_du_exclude_size_lower_range_keeps_the_total_consistent() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/small", &"x".repeat(10));
+    at.write("dir/big", &"x".repeat(5000));
+
+    // the tiny file is excluded from both the listing and the total
+    let result = ucmd
+        .args(&[
+            "--apparent-size",
+            "-B1",
+            "-a",
+            "--exclude-size=..100",
+            "dir",
+        ])
+        .succeeds();
+    result.stdout_contains("5000\tdir/big");
+    result.stdout_contains("5000\tdir");
+    result.stdout_does_not_contain("dir/small");
+}
+
+#[test]
+fn test_du_exclude_size_verbose_reports_ignored_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/big", &"x".repeat(5000));
+
+    ucmd.args(&["-v", "--exclude-size=1K..", "dir"])
+        .succeeds()
+        .stdout_contains("ignored (size)");
+}
+
+#[test]
+fn test_du_exclude_size_invalid_ranges() {
+    for range in ["5", "..", "9..2", "x..2"] {
+        new_ucmd!()
+            .arg(format!("--exclude-size={range}"))
+            .fails()
+            .stderr_contains(format!("invalid --exclude-size argument '{range}'"));
+    }
+}
@@ -447,6 +447,35 @@ fn test_split_string_into_args_debug_output_whitespace_handling() {
     );
 }
 
+#[cfg(unix)]
+#[test]
+fn test_pty_runs_command_on_a_terminal() {
+    new_ucmd!()
+        .args(&[
+            "--pty",
+            "sh",
+            "-c",
+            "test -t 0 && test -t 1 && test -t 2 && echo on_a_tty",
+        ])
+        .succeeds()
+        .stdout_is("on_a_tty\r\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pty_propagates_exit_code() {
+    new_ucmd!()
+        .args(&["--pty", "sh", "-c", "exit 42"])
+        .fails()
+        .code_is(42);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_pty_unsupported_platform() {
+    new_ucmd!().args(&["--pty", "cmd", "/C", "exit 0"]).fails();
+}
+
 // FixMe: This test fails on MACOS:
 // thread 'test_env::test_gnu_e20' panicked at 'assertion failed: `(left == right)`
 // left: `"A=B C=D\n__CF_USER_TEXT_ENCODING=0x1F5:0x0:0x0\n"`,
@@ -629,6 +658,33 @@ fn test_env_overwrite_arg0() {
         .stderr_is("");
 }
 
+#[test]
+#[cfg(unix)]
+fn test_env_login_dash_prefixes_arg0() {
+    new_ucmd!()
+        .args(&["--login", "sh", "-c", "echo $0"])
+        .succeeds()
+        .stdout_is("-sh\n")
+        .stderr_is("");
+
+    new_ucmd!()
+        .args(&["-l", "sh", "-c", "echo $0"])
+        .succeeds()
+        .stdout_is("-sh\n")
+        .stderr_is("");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_env_login_combined_with_argv0() {
+    // --login applies on top of the --argv0 override
+    new_ucmd!()
+        .args(&["--argv0", "zsh", "--login", "sh", "-c", "echo $0"])
+        .succeeds()
+        .stdout_is("-zsh\n")
+        .stderr_is("");
+}
+
 #[test]
 #[cfg(unix)]
 fn test_env_arg_argv0_overwrite() {
@@ -1365,3 +1421,851 @@ mod test_raw_string_parser {
         );
     }
 }
+
+#[cfg(unix)]
+#[test]
+fn test_try_interpreter_uses_shebang_line() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    // deliberately without execute permission
+    at.write("script.sh", "#!/bin/sh\necho shebang_ok\n");
+
+    ts.ucmd()
+        .args(&["--try-interpreter", "./script.sh"])
+        .succeeds()
+        .stdout_is("shebang_ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_try_interpreter_fallback_without_shebang() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.write("script.sh", "echo fallback_ok\n");
+
+    ts.ucmd()
+        .args(&["--try-interpreter=/bin/sh", "./script.sh"])
+        .succeeds()
+        .stdout_is("fallback_ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_without_try_interpreter_missing_exec_permission_fails() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    at.write("script.sh", "#!/bin/sh\necho shebang_ok\n");
+
+    ts.ucmd().args(&["./script.sh"]).fails().code_is(126);
+}
+
+#[test]
+fn test_default_sets_missing_variable() {
+    new_ucmd!()
+        .args(&["-i", "--default", "FOO=bar"])
+        .succeeds()
+        .stdout_is("FOO=bar\n");
+}
+
+#[test]
+fn test_default_does_not_override_assignment() {
+    new_ucmd!()
+        .args(&["-i", "--default", "FOO=bar", "FOO=baz"])
+        .succeeds()
+        .stdout_is("FOO=baz\n");
+}
+
+#[test]
+fn test_default_does_not_override_inherited_variable() {
+    let result = new_ucmd!()
+        .env("FOO", "inherited")
+        .args(&["--default", "FOO=bar"])
+        .succeeds();
+    result.stdout_contains("FOO=inherited");
+    assert!(!result.stdout_str().contains("FOO=bar"));
+}
+
+#[test]
+fn test_default_applies_after_unset() {
+    new_ucmd!()
+        .env("FOO", "inherited")
+        .args(&["-u", "FOO", "--default", "FOO=bar"])
+        .succeeds()
+        .stdout_contains("FOO=bar");
+}
+
+#[test]
+fn test_inherit_carries_variables_through_ignore_environment() {
+    new_ucmd!()
+        .env("FOO", "bar")
+        .env("BAZ", "qux")
+        .args(&["-i", "--inherit", "FOO", "--inherit", "BAZ"])
+        .succeeds()
+        .stdout_is_any(&["FOO=bar\nBAZ=qux\n", "BAZ=qux\nFOO=bar\n"]);
+}
+
+#[test]
+fn test_inherit_is_overridden_by_assignment() {
+    new_ucmd!()
+        .env("FOO", "bar")
+        .args(&["-i", "--inherit", "FOO", "FOO=override"])
+        .succeeds()
+        .stdout_is("FOO=override\n");
+}
+
+#[test]
+fn test_inherit_loses_against_unset() {
+    new_ucmd!()
+        .env("FOO", "bar")
+        .args(&["-i", "--inherit", "FOO", "-u", "FOO"])
+        .succeeds()
+        .stdout_is("");
+}
+
+#[test]
+fn test_inherit_of_a_missing_variable_is_silently_ignored() {
+    new_ucmd!()
+        .args(&["-i", "--inherit", "NO_SUCH_VARIABLE"])
+        .succeeds()
+        .no_output();
+}
+
+#[cfg(not(target_os = "windows"))] // windows has no executable "echo", its only supported as part of a batch-file
+#[test]
+fn test_split_string_expand_tilde() {
+    let out = new_ucmd!()
+        .env("HOME", "/testhome")
+        .args(&["--split-string-expand", "-S echo ~/bin"])
+        .succeeds()
+        .stdout_move_str();
+    assert_eq!(out, "/testhome/bin\n");
+}
+
+#[cfg(not(target_os = "windows"))] // windows has no executable "echo", its only supported as part of a batch-file
+#[test]
+fn test_split_string_expand_tilde_stays_literal_mid_word() {
+    let out = new_ucmd!()
+        .env("HOME", "/testhome")
+        .args(&["--split-string-expand", "-S echo a~/b ~x"])
+        .succeeds()
+        .stdout_move_str();
+    assert_eq!(out, "a~/b ~x\n");
+}
+
+#[cfg(not(target_os = "windows"))] // windows has no executable "echo", its only supported as part of a batch-file
+#[test]
+fn test_split_string_expand_pid() {
+    let out = new_ucmd!()
+        .args(&["--split-string-expand", "-S echo $$"])
+        .succeeds()
+        .stdout_move_str();
+    let pid: u32 = out.trim().parse().expect("$$ should expand to a pid");
+    assert!(pid > 0);
+}
+
+#[test]
+fn test_split_string_expand_is_off_by_default() {
+    // without the flag, `$$` keeps being rejected like GNU env does
+    new_ucmd!()
+        .args(&["-S echo $$"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("variable name issue");
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_split_string_expand_only_affects_later_strings() {
+    // the flag is positional: a -S string before it is split the GNU way
+    new_ucmd!()
+        .args(&["-S echo $$", "--split-string-expand"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("variable name issue");
+}
+
+#[cfg(not(target_os = "windows"))] // windows has no executable "echo", its only supported as part of a batch-file
+#[test]
+fn test_debug_format_json_exec_event() {
+    let result = new_ucmd!()
+        .args(&["-v", "--debug-format=json", "echo", "ok"])
+        .succeeds();
+    result.stdout_is("ok\n");
+    let exec_line = result
+        .stderr_str()
+        .lines()
+        .find(|line| line.contains("\"event\":\"exec\""))
+        .expect("no exec event in the trace");
+    assert!(exec_line.contains("\"program\":\"echo\""));
+    assert!(exec_line.contains("\"argv\":[\"echo\",\"ok\"]"));
+}
+
+#[test]
+fn test_debug_format_json_variable_events() {
+    let result = new_ucmd!()
+        .args(&["-v", "--debug-format=json", "-i", "-u", "FOO", "A=1"])
+        .succeeds();
+    result.stdout_is("A=1\n");
+    let stderr = result.stderr_str();
+    assert!(stderr.contains("{\"event\":\"clear-env\"}"));
+    assert!(stderr.contains("{\"event\":\"unset\",\"name\":\"FOO\"}"));
+    assert!(stderr
+        .contains("{\"event\":\"set\",\"source\":\"assignment\",\"name\":\"A\",\"value\":\"1\"}"));
+}
+
+#[cfg(not(target_os = "windows"))] // windows has no executable "echo", its only supported as part of a batch-file
+#[test]
+fn test_debug_format_json_split_string_events() {
+    let result = new_ucmd!()
+        .args(&["--debug-format=json", "-vvS echo hello"])
+        .succeeds();
+    let stderr = result.stderr_str();
+    // one split-string event replaces the human per-expansion trace
+    assert!(stderr.contains("\"event\":\"input-args\""));
+    assert!(stderr.contains("\"event\":\"split-string\""));
+    assert!(stderr.contains("\"argv\":[\"echo\",\"hello\"]"));
+    assert!(!stderr.contains("input args:"));
+}
+
+#[test]
+fn test_debug_format_human_is_unchanged() {
+    let result = new_ucmd!().args(&["-vv", "-i"]).succeeds();
+    assert!(result.stderr_str().contains("input args:"));
+    assert!(!result.stderr_str().contains("\"event\""));
+}
+
+#[test]
+fn test_secure_strips_loader_variables_with_a_warning() {
+    new_ucmd!()
+        .args(&["-i", "--secure", "LD_PRELOAD=/tmp/evil.so", "FOO=bar"])
+        .succeeds()
+        .stdout_is("FOO=bar\n")
+        .stderr_is("env: warning: --secure: not passing 'LD_PRELOAD' to the command\n");
+}
+
+#[test]
+fn test_secure_strips_inherited_loader_variables() {
+    new_ucmd!()
+        .arg("--secure")
+        .env("LD_LIBRARY_PATH", "/tmp/evil")
+        .succeeds()
+        .stdout_does_not_contain("LD_LIBRARY_PATH")
+        .stderr_contains("not passing 'LD_LIBRARY_PATH'");
+}
+
+#[test]
+fn test_secure_deny_extends_the_denylist() {
+    new_ucmd!()
+        .args(&[
+            "-i",
+            "--secure",
+            "--secure-deny",
+            "SNEAKY",
+            "SNEAKY=1",
+            "OK=1",
+        ])
+        .succeeds()
+        .stdout_is("OK=1\n")
+        .stderr_contains("not passing 'SNEAKY'");
+}
+
+#[test]
+fn test_secure_deny_requires_secure() {
+    new_ucmd!()
+        .args(&["--secure-deny", "SNEAKY"])
+        .fails()
+        .stderr_contains("--secure");
+}
+
+#[test]
+fn test_default_requires_name_value_pair() {
+    new_ucmd!()
+        .args(&["--default", "FOO"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid argument 'FOO' for '--default': expected NAME=VALUE");
+}
+
+#[test]
+fn test_generate_completion_includes_extensions() {
+    new_ucmd!()
+        .args(&["--generate-completion", "bash"])
+        .succeeds()
+        .stdout_contains("--pty");
+}
+
+#[test]
+fn test_check_env_clean_environment_passes() {
+    new_ucmd!()
+        .args(&["--check-env", "echo", "ok"])
+        .succeeds()
+        .stdout_is("ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_env_reports_name_containing_equals() {
+    new_ucmd!()
+        .env("=BAD", "value")
+        .args(&["--check-env", "echo", "ok"])
+        .succeeds()
+        .stderr_contains("variable name contains '='")
+        .stdout_is("ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_env_fail_mode_exits_with_125() {
+    new_ucmd!()
+        .env("=BAD", "value")
+        .args(&["--check-env=fail", "echo", "ok"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid entry")
+        .no_stdout();
+}
+
+#[test]
+fn test_command_var_runs_split_command() {
+    new_ucmd!()
+        .env("RUN_CMD", "echo hello world")
+        .args(&["--command-var", "RUN_CMD"])
+        .succeeds()
+        .stdout_is("hello world\n");
+}
+
+#[test]
+fn test_command_var_sees_assignments_even_with_ignore_environment() {
+    new_ucmd!()
+        .args(&["-i", "--command-var", "RUN_CMD", "RUN_CMD=echo ok"])
+        .succeeds()
+        .stdout_is("ok\n");
+}
+
+#[test]
+fn test_command_var_unset_variable_fails() {
+    new_ucmd!()
+        .args(&["-u", "RUN_CMD", "--command-var", "RUN_CMD"])
+        .env("RUN_CMD", "echo nope")
+        .fails()
+        .code_is(125)
+        .stderr_contains("is not set for '--command-var'");
+}
+
+#[test]
+fn test_command_var_conflicts_with_command() {
+    new_ucmd!()
+        .env("RUN_CMD", "echo nope")
+        .args(&["--command-var", "RUN_CMD", "echo", "other"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("cannot specify both --command-var and a command");
+}
+
+#[test]
+fn test_command_var_quoting_is_honored() {
+    new_ucmd!()
+        .env("RUN_CMD", "printf '%s\\n' 'a b'")
+        .args(&["--command-var", "RUN_CMD"])
+        .succeeds()
+        .stdout_is("a b\n");
+}
+
+#[test]
+fn test_split_string_vv_traces_variable_expansion() {
+    let result = new_ucmd!()
+        .env("TRACED_VAR", "from-env")
+        .arg("-vvSecho ${TRACED_VAR} ${TRACED_UNSET} ${TRACED_UNSET:default}")
+        .succeeds();
+    result.stderr_contains("expanding ${TRACED_VAR} (environment) -> 'from-env'");
+    result.stderr_contains("expanding ${TRACED_UNSET} (unset) -> ''");
+    result.stderr_contains("expanding ${TRACED_UNSET} (default) -> 'default'");
+    result.stdout_contains("from-env");
+}
+
+#[test]
+fn test_split_string_single_v_does_not_trace_expansion() {
+    new_ucmd!()
+        .env("TRACED_VAR", "from-env")
+        .arg("-vSecho ${TRACED_VAR}")
+        .succeeds()
+        .stderr_does_not_contain("expanding");
+}
+
+#[test]
+fn test_no_proxy_env_strips_proxy_variables() {
+    let result = new_ucmd!()
+        .env("http_proxy", "http://localhost:3128")
+        .env("HTTPS_PROXY", "http://localhost:3128")
+        .env("no_proxy", "localhost")
+        .env("OTHER_VAR", "kept")
+        .arg("--no-proxy-env")
+        .succeeds();
+    result.stdout_contains("OTHER_VAR=kept");
+    assert!(!result.stdout_str().contains("proxy"));
+    assert!(!result.stdout_str().contains("PROXY"));
+}
+
+#[test]
+fn test_no_proxy_env_combines_with_unset() {
+    let result = new_ucmd!()
+        .env("http_proxy", "http://localhost:3128")
+        .env("OTHER_VAR", "dropped")
+        .args(&["--no-proxy-env", "-u", "OTHER_VAR"])
+        .succeeds();
+    assert!(!result.stdout_str().contains("http_proxy"));
+    assert!(!result.stdout_str().contains("OTHER_VAR"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_env_retry_eventually_succeeds() {
+    let ts = TestScenario::new(util_name!());
+
+    // fails on the first attempt, then the marker exists and it succeeds
+    ts.ucmd()
+        .args(&[
+            "--retry=2",
+            "sh",
+            "-c",
+            "if [ -e marker ]; then exit 0; else touch marker; exit 1; fi",
+        ])
+        .succeeds();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_env_retry_reports_status_of_last_attempt() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+
+    ts.ucmd()
+        .args(&["--retry=2", "sh", "-c", "echo attempt >> log; exit 7"])
+        .fails()
+        .code_is(7);
+    // the initial run plus two retries
+    assert_eq!(at.read("log").lines().count(), 3);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_env_retry_with_delay_between_attempts() {
+    let start = std::time::Instant::now();
+    new_ucmd!()
+        .args(&["--retry=2,0.2", "false"])
+        .fails()
+        .code_is(1);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+}
+
+#[test]
+fn test_env_retry_invalid_spec() {
+    new_ucmd!()
+        .args(&["--retry=x", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid retry count 'x'");
+
+    new_ucmd!()
+        .args(&["--retry=1,nope", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid retry delay 'nope'");
+}
+
+#[test]
+fn test_env_posixly_correct_disables_extensions() {
+    for args in [
+        &["--retry=2", "true"] as &[&str],
+        &["--no-proxy-env"],
+        &["--default", "A=1"],
+        &["--inherit", "HOME"],
+        &["--secure"],
+        &["--split-string-expand"],
+        &["--debug-format=json"],
+        &["--watch-file", "some-file"],
+        &["--print-env-fd", "3"],
+        &["--command-var", "CMD"],
+        &["--check-env"],
+        #[cfg(unix)]
+        &["--login", "sh", "-c", "true"],
+        #[cfg(unix)]
+        &["--pty", "true"],
+        &["--try-interpreter", "script"],
+    ] {
+        let result = new_ucmd!().env("POSIXLY_CORRECT", "1").args(args).fails();
+        result.code_is(125);
+        result.stderr_contains(
+            "is a uutils extension and is disabled because POSIXLY_CORRECT is set",
+        );
+    }
+}
+
+#[test]
+fn test_env_posixly_correct_keeps_standard_behavior() {
+    new_ucmd!()
+        .env("POSIXLY_CORRECT", "1")
+        .args(&["-i", "A=1"])
+        .succeeds()
+        .stdout_is("A=1\n");
+}
+
+#[test]
+fn test_env_help_mentions_posixly_correct() {
+    new_ucmd!()
+        .arg("--help")
+        .succeeds()
+        .stdout_contains("disabled when POSIXLY_CORRECT is set");
+}
+
+#[test]
+fn test_shebang_splits_like_a_shebang_line() {
+    new_ucmd!()
+        .args(&["--shebang", "-S printf %s_ a b"])
+        .succeeds()
+        .stdout_is("a_b_");
+}
+
+#[test]
+fn test_shebang_keeps_spaces_in_single_argument() {
+    // without -S the kernel passes the whole line as one argument,
+    // so this looks up a program with spaces in its name
+    new_ucmd!()
+        .args(&["--shebang", "printf x y"])
+        .fails()
+        .code_is(127)
+        .stderr_contains("'printf x y': No such file or directory");
+}
+
+#[test]
+fn test_shebang_strips_trailing_newline() {
+    new_ucmd!()
+        .args(&["--shebang", "-S printf hi\n"])
+        .succeeds()
+        .stdout_is("hi");
+}
+
+#[test]
+fn test_shebang_must_be_first_argument() {
+    new_ucmd!()
+        .args(&["-i", "--shebang", "-S printf hi"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("'--shebang' must be the first argument");
+}
+
+#[test]
+fn test_shebang_requires_a_line() {
+    new_ucmd!()
+        .arg("--shebang")
+        .fails()
+        .code_is(125)
+        .stderr_contains("missing shebang line after '--shebang'");
+}
+
+#[test]
+fn test_split_string_expansion_colon_dash_covers_empty_and_unset() {
+    new_ucmd!()
+        .env("EMPTY_VAR", "")
+        .arg("-Secho ${EMPTY_VAR:-fallback} ${UNSET_VAR:-fallback} ${SET_VAR:-fallback}")
+        .env("SET_VAR", "value")
+        .succeeds()
+        .stdout_is("fallback fallback value\n");
+}
+
+#[test]
+fn test_split_string_expansion_dash_keeps_empty_value() {
+    new_ucmd!()
+        .env("EMPTY_VAR", "")
+        .arg("-Sprintf %s:%s ${EMPTY_VAR-fallback} ${UNSET_VAR-fallback}")
+        .succeeds()
+        .stdout_is(":fallback");
+}
+
+#[test]
+fn test_split_string_expansion_colon_plus_skips_empty_and_unset() {
+    new_ucmd!()
+        .env("EMPTY_VAR", "")
+        .env("SET_VAR", "value")
+        .arg("-Sprintf %s:%s:%s ${EMPTY_VAR:+alt} ${UNSET_VAR:+alt} ${SET_VAR:+alt}")
+        .succeeds()
+        .stdout_is("::alt");
+}
+
+#[test]
+fn test_split_string_expansion_plus_triggers_on_empty_value() {
+    new_ucmd!()
+        .env("EMPTY_VAR", "")
+        .arg("-Sprintf %s:%s ${EMPTY_VAR+alt} ${UNSET_VAR+alt}")
+        .succeeds()
+        .stdout_is("alt:");
+}
+
+#[test]
+fn test_split_string_expansion_colon_question_passes_set_value() {
+    new_ucmd!()
+        .env("SET_VAR", "value")
+        .arg("-Secho ${SET_VAR:?must be set}")
+        .succeeds()
+        .stdout_is("value\n");
+}
+
+#[test]
+fn test_split_string_expansion_colon_question_fails_when_unset() {
+    new_ucmd!()
+        .arg("-Secho ${UNSET_VAR:?the message}")
+        .fails()
+        .code_is(125)
+        .stderr_contains("UNSET_VAR: the message");
+}
+
+#[test]
+fn test_split_string_expansion_colon_question_fails_when_empty() {
+    new_ucmd!()
+        .env("EMPTY_VAR", "")
+        .arg("-Secho ${EMPTY_VAR:?}")
+        .fails()
+        .code_is(125)
+        .stderr_contains("EMPTY_VAR: parameter null or not set");
+}
+
+#[test]
+fn test_split_string_expansion_legacy_colon_default_still_works() {
+    new_ucmd!()
+        .arg("-Secho ${UNSET_VAR:legacy}")
+        .succeeds()
+        .stdout_is("legacy\n");
+}
+
+#[test]
+fn test_chdir_repeated_resolves_relative_to_previous() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("outer");
+    at.mkdir("outer/inner");
+
+    ts.ucmd()
+        .args(&["-C", "outer", "-C", "inner", "--pwd"])
+        .succeeds()
+        .stdout_str_check(|s| s.trim_end().ends_with("inner"));
+}
+
+#[test]
+fn test_pwd_prints_directory_after_chdir() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("somewhere");
+
+    ts.ucmd()
+        .args(&["--chdir", "somewhere", "--pwd"])
+        .succeeds()
+        .stdout_str_check(|s| s.trim_end().ends_with("somewhere"));
+}
+
+#[test]
+fn test_pwd_conflicts_with_command() {
+    new_ucmd!()
+        .args(&["--pwd", "echo", "x"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("cannot specify command with --pwd");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_command_prefers_chdir_directory_over_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("bin");
+    at.mkdir("workdir");
+    at.write("bin/probe", "#!/bin/sh\necho from-path\n");
+    at.write("workdir/probe", "#!/bin/sh\necho from-chdir\n");
+    for p in ["bin/probe", "workdir/probe"] {
+        std::fs::set_permissions(at.plus(p), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let path = format!("{}:/usr/bin:/bin", at.plus_as_string("bin"));
+
+    // without the flag the PATH entry wins, with it the --chdir directory does
+    ts.ucmd()
+        .env("PATH", &path)
+        .args(&["-C", "workdir", "probe"])
+        .succeeds()
+        .stdout_is("from-path\n");
+    ts.ucmd()
+        .env("PATH", &path)
+        .args(&["-C", "workdir", "--resolve-command", "probe"])
+        .succeeds()
+        .stdout_is("from-chdir\n");
+}
+
+#[test]
+fn test_resolve_command_falls_back_to_path_lookup() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("empty");
+
+    ts.ucmd()
+        .args(&["-C", "empty", "--resolve-command", "echo", "ok"])
+        .succeeds()
+        .stdout_is("ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_command_applies_to_split_string_command() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir("workdir");
+    at.write("workdir/probe", "#!/bin/sh\necho split-ok\n");
+    std::fs::set_permissions(
+        at.plus("workdir/probe"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ts.ucmd()
+        .args(&["-C", "workdir", "--resolve-command", "-S", "probe"])
+        .succeeds()
+        .stdout_is("split-ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_print_env_matches_gnu_env_byte_for_byte() {
+    let ts = TestScenario::new(util_name!());
+
+    let ours = ts
+        .ucmd()
+        .env("ZZ_LAST", "z")
+        .env("AA_FIRST", "a")
+        .env("MIDDLE", "m")
+        .succeeds()
+        .stdout_move_bytes();
+    let reference = ts
+        .cmd("env")
+        .env("ZZ_LAST", "z")
+        .env("AA_FIRST", "a")
+        .env("MIDDLE", "m")
+        .succeeds()
+        .stdout_move_bytes();
+
+    assert_eq!(ours, reference);
+}
+
+#[test]
+fn test_print_env_value_with_newline_is_printed_raw() {
+    new_ucmd!()
+        .env("MULTILINE", "first\nsecond")
+        .succeeds()
+        .stdout_contains("MULTILINE=first\nsecond\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_print_env_non_utf8_value_is_printed_verbatim() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let value = OsStr::from_bytes(b"caf\xe9");
+    let result = new_ucmd!().env("NON_UTF8", value).succeeds();
+    assert!(result
+        .stdout()
+        .windows(13)
+        .any(|w| w == b"NON_UTF8=caf\xe9"));
+}
+
+#[test]
+fn test_no_environ_sort_check_is_accepted() {
+    new_ucmd!()
+        .env("SOME_VAR", "x")
+        .arg("--no-environ-sort-check")
+        .succeeds()
+        .stdout_contains("SOME_VAR=x");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_watch_file_restarts_when_the_file_changes() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("conf.env", "MARKER=one\n");
+
+    let mut child = ucmd
+        .args(&["-i", "-f", "conf.env", "--watch-file", "conf.env"])
+        .args(&["sh", "-c", "echo start-$MARKER; exec sleep 600"])
+        .run_no_wait();
+
+    child.expect_stdout("start-one\n");
+    at.write("conf.env", "MARKER=two\n");
+    // the poller debounces, so the restart takes a moment
+    child.expect_stdout("start-two\n");
+    child.kill();
+}
+
+#[test]
+fn test_watch_file_requires_a_command() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("conf.env", "A=1\n");
+    ucmd.args(&["--watch-file", "conf.env"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("must specify command with --watch-file");
+}
+
+#[test]
+fn test_watch_file_rejects_a_missing_file() {
+    new_ucmd!()
+        .args(&["--watch-file", "no-such-file", "sh", "-c", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("cannot watch 'no-such-file': no such file");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_print_env_fd_dumps_the_final_environment() {
+    let ts = TestScenario::new(util_name!());
+    ts.cmd("sh")
+        .arg("-c")
+        .arg(format!(
+            "{} env -i -0 --print-env-fd=3 A=1 B=2 echo ok 3>dump",
+            ts.bin_path.display()
+        ))
+        .succeeds()
+        .stdout_is("ok\n");
+    assert_eq!(ts.fixtures.read_bytes("dump"), b"A=1\0B=2\0");
+}
+
+#[test]
+fn test_print_env_fd_requires_a_command() {
+    new_ucmd!()
+        .args(&["-0", "--print-env-fd=3"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("must specify command with --print-env-fd");
+}
+
+#[test]
+fn test_print_env_fd_rejects_the_standard_descriptors() {
+    new_ucmd!()
+        .args(&["--print-env-fd=2", "echo", "ok"])
+        .fails()
+        .stderr_contains("invalid value '2' for '--print-env-fd <FD>'");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_print_env_fd_diagnoses_a_closed_descriptor() {
+    new_ucmd!()
+        .args(&["--print-env-fd=99", "echo", "ok"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("cannot write environment to fd 99");
+}
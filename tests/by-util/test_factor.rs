@@ -1315,3 +1315,291 @@ fn succeeds_with_numbers_larger_than_u256() {
                 269984665640564039457584007913129639936: 2^256\n",
         );
 }
+
+#[test]
+#[cfg(unix)]
+fn test_interactive_prompt_on_terminal() {
+    let mut child = new_ucmd!().terminal_simulation(true).run_no_wait();
+    // give the prompt time to switch the terminal into raw mode before typing
+    child.make_assertion_with_delay(200).is_alive();
+    child.write_in("42\n");
+    child.make_assertion_with_delay(200).is_alive();
+    // Ctrl-D on the empty prompt line exits
+    child.write_in(&[0x04][..]);
+    let result = child.wait().unwrap();
+    result.success();
+    let stdout = result.stdout_str();
+    assert!(stdout.contains("> "), "missing prompt in: {stdout:?}");
+    assert!(
+        stdout.contains("42: 2 3 7"),
+        "missing factorization in: {stdout:?}"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_interactive_backspace_editing() {
+    let mut child = new_ucmd!().terminal_simulation(true).run_no_wait();
+    child.make_assertion_with_delay(200).is_alive();
+    // type "15", erase the "5", complete the line as "12"
+    child.write_in(&b"15\x7f2\n"[..]);
+    child.make_assertion_with_delay(200).is_alive();
+    child.write_in(&[0x04][..]);
+    let result = child.wait().unwrap();
+    result.success();
+    result.stdout_str_check(|s| s.contains("12: 2 2 3"));
+}
+
+#[test]
+fn test_piped_stdin_stays_in_batch_mode() {
+    new_ucmd!()
+        .pipe_in("42\n")
+        .succeeds()
+        .stdout_only("42: 2 3 7\n");
+}
+
+#[test]
+fn test_range_matches_individual_factorizations() {
+    let expected = new_ucmd!()
+        .args(
+            &(999_990..=1_000_010)
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>(),
+        )
+        .succeeds()
+        .stdout_move_str();
+    new_ucmd!()
+        .arg("--range")
+        .arg("999990..1000010")
+        .succeeds()
+        .stdout_is(expected);
+}
+
+#[test]
+fn test_range_includes_zero_and_one() {
+    new_ucmd!()
+        .args(&["--range", "0..3"])
+        .succeeds()
+        .stdout_is("0:\n1:\n2: 2\n3: 3\n");
+}
+
+#[test]
+fn test_range_with_exponents() {
+    new_ucmd!()
+        .args(&["-h", "--range", "8..9"])
+        .succeeds()
+        .stdout_is("8: 2^3\n9: 3^2\n");
+}
+
+#[test]
+fn test_range_invalid() {
+    new_ucmd!()
+        .args(&["--range", "10..5"])
+        .fails()
+        .stderr_contains("invalid range '10..5': 10 is larger than 5");
+    new_ucmd!()
+        .args(&["--range", "10"])
+        .fails()
+        .stderr_contains("expected A..B");
+}
+
+#[test]
+fn test_timing_appends_trailing_column() {
+    let result = new_ucmd!().args(&["--timing", "1234", "10240"]).succeeds();
+    let stdout = result.stdout_str();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("1234: 2 617\t"));
+    assert!(lines[1].starts_with("10240: 2 2 2 2 2 2 2 2 2 2 2 5\t"));
+    for line in lines {
+        let timing = line.rsplit('\t').next().unwrap();
+        assert!(
+            timing.strip_suffix("us").unwrap().parse::<u128>().is_ok(),
+            "bad timing column in {line:?}"
+        );
+    }
+}
+
+#[test]
+fn test_timing_off_by_default() {
+    new_ucmd!()
+        .arg("1234")
+        .succeeds()
+        .stdout_only("1234: 2 617\n");
+}
+
+#[test]
+fn test_timing_conflicts_with_range() {
+    new_ucmd!()
+        .args(&["--timing", "--range", "1..10"])
+        .fails()
+        .stderr_contains("cannot be used with");
+}
+
+#[test]
+fn test_invalid_token_reports_and_continues() {
+    new_ucmd!()
+        .args(&["--", "-5", "abc", "12x", "8"])
+        .fails()
+        .code_is(1)
+        .stdout_is("8: 2 2 2\n")
+        .stderr_is(
+            "factor: '-5' is not a valid positive integer\n\
+             factor: 'abc' is not a valid positive integer\n\
+             factor: '12x' is not a valid positive integer\n",
+        );
+}
+
+#[test]
+fn test_invalid_token_from_stdin_reports_and_continues() {
+    new_ucmd!()
+        .pipe_in("4 -3 9\n")
+        .fails()
+        .code_is(1)
+        .stdout_is("4: 2 2\n9: 3 3\n")
+        .stderr_is("factor: '-3' is not a valid positive integer\n");
+}
+
+#[test]
+fn test_empty_token_reports_empty_quotes() {
+    new_ucmd!()
+        .arg("")
+        .fails()
+        .code_is(1)
+        .stderr_is("factor: '' is not a valid positive integer\n");
+}
+
+#[test]
+fn test_huge_token_is_factored_not_rejected() {
+    new_ucmd!()
+        .arg("99999999999999999999999")
+        .succeeds()
+        .stdout_only("99999999999999999999999: 3 3 11111111111111111111111\n");
+}
+
+mod gnu_differential {
+    use crate::common::util::{check_coreutil_version, host_name_for, TestScenario, PATH};
+
+    const VERSION_MIN: &str = "8.30";
+
+    fn factor_output(ts: &TestScenario, reference: bool, input: &str) -> (String, String) {
+        let mut cmd = if reference {
+            let mut cmd = ts.cmd(host_name_for("factor").as_ref());
+            cmd.env("PATH", PATH);
+            cmd
+        } else {
+            ts.ucmd()
+        };
+        let result = cmd.pipe_in(input).run();
+        (
+            result.stdout_str().to_string(),
+            result.stderr_str().to_string(),
+        )
+    }
+
+    #[test]
+    fn test_invalid_input_handling_matches_gnu() {
+        unwrap_or_return!(check_coreutil_version("factor", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        for input in [
+            "-5\n",
+            "abc 12x 8\n",
+            "+5\n",
+            " 5\n",
+            "4 -3 9\n",
+            "0x10\n",
+            "18446744073709551616\n",
+        ] {
+            let (our_stdout, our_stderr) = factor_output(&ts, false, input);
+            let (gnu_stdout, gnu_stderr) = factor_output(&ts, true, input);
+            assert_eq!(our_stdout, gnu_stdout, "stdout differs for input {input:?}");
+            assert_eq!(our_stderr, gnu_stderr, "stderr differs for input {input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_perfect_power_of_a_large_prime() {
+    // 1000000007^3; the perfect-power pre-check reduces this to factoring
+    // the base
+    new_ucmd!()
+        .args(&["-h", "1000000021000000147000000343"])
+        .succeeds()
+        .stdout_only("1000000021000000147000000343: 1000000007^3\n");
+}
+
+#[test]
+fn test_smooth_bound_reports_smoothness_only() {
+    new_ucmd!()
+        .args(&["--smooth-bound=7", "8", "10", "11", "49"])
+        .succeeds()
+        .stdout_only("8: smooth\n10: smooth\n11: not smooth\n49: smooth\n");
+}
+
+#[test]
+fn test_smooth_bound_reads_stdin() {
+    new_ucmd!()
+        .arg("--smooth-bound=3")
+        .pipe_in("9 25\n")
+        .succeeds()
+        .stdout_only("9: smooth\n25: not smooth\n");
+}
+
+#[test]
+fn test_smooth_bound_rejects_bad_bounds() {
+    new_ucmd!()
+        .args(&["--smooth-bound=1", "8"])
+        .fails()
+        .stderr_contains("invalid value '1'");
+    new_ucmd!()
+        .args(&["--smooth-bound=10", "--range=1..4"])
+        .fails()
+        .stderr_contains("cannot be used with");
+}
+
+#[test]
+fn test_algorithm_backends_agree_on_a_semiprime() {
+    // the two largest primes below 2^32; a worst case for the rho backend
+    let expected = "18446743979220271189: 4294967279 4294967291\n";
+    for algorithm in ["auto", "rho", "num-prime"] {
+        new_ucmd!()
+            .args(&[&format!("--algorithm={algorithm}"), "18446743979220271189"])
+            .succeeds()
+            .stdout_only(expected);
+    }
+}
+
+#[test]
+fn test_algorithm_rejects_unknown_backend() {
+    new_ucmd!()
+        .args(&["--algorithm=snfs", "12"])
+        .fails()
+        .stderr_contains("invalid value 'snfs'");
+}
+
+#[test]
+fn test_big_input_factors_stay_sorted() {
+    // 2^70 * 3 * 1031: the trial factors stream out first, the cofactor last
+    new_ucmd!()
+        .args(&["-h", "3651569882878953161490432"])
+        .succeeds()
+        .stdout_only("3651569882878953161490432: 2^70 3 1031\n");
+}
+
+#[test]
+fn test_big_semiprime_without_small_factors() {
+    // 2^67 - 1 = 193707721 * 761838257287, nothing for trial division
+    new_ucmd!()
+        .arg("147573952589676412927")
+        .succeeds()
+        .stdout_only("147573952589676412927: 193707721 761838257287\n");
+}
+
+#[test]
+fn test_big_perfect_power() {
+    new_ucmd!()
+        .args(&["-h", "1267650600228229401496703205376"])
+        .succeeds()
+        .stdout_only("1267650600228229401496703205376: 2^100\n");
+}
@@ -236,3 +236,9 @@ fn test_negative_interval() {
         .fails()
         .usage_error("invalid time interval '-1': Number was negative");
 }
+
+#[test]
+fn test_fake_clock_compresses_the_interval() {
+    // would block for 10 minutes without the scaled clock
+    new_ucmd!().fake_clock_scale(10_000).arg("600").succeeds();
+}
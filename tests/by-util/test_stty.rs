@@ -30,6 +30,47 @@ fn print_all() {
     }
 }
 
+#[test]
+fn pipe_target_reports_inappropriate_ioctl() {
+    // stdout of the test command is a pipe, not a tty
+    new_ucmd!()
+        .fails()
+        .code_is(1)
+        .stderr_only("stty: standard input: Inappropriate ioctl for device\n");
+}
+
+#[test]
+fn file_target_reports_inappropriate_ioctl() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("not_a_tty", "");
+    ts.ucmd()
+        .args(&["-F", "not_a_tty"])
+        .fails()
+        .code_is(1)
+        .stderr_only("stty: not_a_tty: Inappropriate ioctl for device\n");
+}
+
+#[test]
+fn missing_file_target_reports_open_error() {
+    new_ucmd!()
+        .args(&["-F", "/definitely/not/here"])
+        .fails()
+        .code_is(1)
+        .stderr_only("stty: /definitely/not/here: No such file or directory\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn closed_fd_target_reports_error() {
+    let ts = TestScenario::new(util_name!());
+    // run through a shell so stdout can be closed before stty starts
+    ts.cmd("sh")
+        .args(&["-c", &format!("exec >&-; {} stty", ts.bin_path.display())])
+        .fails()
+        .code_is(1)
+        .stderr_contains("standard input:");
+}
+
 #[test]
 fn save_and_setting() {
     new_ucmd!()
@@ -62,3 +103,612 @@ fn save_and_all() {
             "the options for verbose and stty-readable output styles are mutually exclusive",
         );
 }
+
+/// Differential tests comparing our output field-by-field against a system GNU
+/// stty under the PTY simulation.
+///
+/// The tests are skipped when there is no (recent enough) GNU stty in `$PATH`.
+#[cfg(target_os = "linux")]
+mod gnu_differential {
+    use crate::common::util::{check_coreutil_version, host_name_for, TestScenario, PATH};
+    use std::collections::HashMap;
+
+    const VERSION_MIN: &str = "8.30";
+
+    fn stty_under_pty(ts: &TestScenario, reference: bool, args: &[&str]) -> String {
+        let mut cmd = if reference {
+            let mut cmd = ts.cmd(host_name_for("stty").as_ref());
+            cmd.env("PATH", PATH);
+            cmd
+        } else {
+            ts.ucmd()
+        };
+        cmd.args(args)
+            .terminal_simulation(true)
+            .succeeds()
+            .stdout_move_str()
+    }
+
+    /// Parse the boolean flag blocks of `stty -a` into a name -> enabled map.
+    fn parse_flags(output: &str) -> HashMap<String, bool> {
+        let mut flags = HashMap::new();
+        for line in output.lines() {
+            // the speed line and the control character block contain '='
+            if line.contains('=') {
+                continue;
+            }
+            for token in line.split_whitespace() {
+                let token = token.trim_end_matches(';');
+                let (name, enabled) = token
+                    .strip_prefix('-')
+                    .map_or((token, true), |name| (name, false));
+                flags.insert(name.to_string(), enabled);
+            }
+        }
+        flags
+    }
+
+    /// Parse all `name = value;` fields of `stty -a` into a map.
+    fn parse_assignments(output: &str) -> HashMap<String, String> {
+        let mut assignments = HashMap::new();
+        for chunk in output.split(';') {
+            if let Some((name, value)) = chunk.split_once('=') {
+                assignments.insert(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+        assignments
+    }
+
+    #[test]
+    fn test_stty_all_flag_states_match_gnu() {
+        unwrap_or_return!(check_coreutil_version("stty", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        let ours = stty_under_pty(&ts, false, &["-a"]);
+        let gnu = stty_under_pty(&ts, true, &["-a"]);
+
+        // both ptys are set up identically, so the speed line must be identical
+        assert_eq!(ours.lines().next(), gnu.lines().next());
+
+        let our_flags = parse_flags(&ours);
+        let gnu_flags = parse_flags(&gnu);
+
+        let mut mismatches = Vec::new();
+        for (name, enabled) in &our_flags {
+            match gnu_flags.get(name) {
+                Some(gnu_enabled) if gnu_enabled != enabled => {
+                    mismatches.push(format!("{name}: ours={enabled}, gnu={gnu_enabled}"));
+                }
+                None => println!("uutils-tests-info: flag '{name}' not reported by GNU stty"),
+                Some(_) => {}
+            }
+        }
+        for name in gnu_flags.keys() {
+            if !our_flags.contains_key(name) {
+                println!("uutils-tests-info: flag '{name}' not reported by our stty");
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "flag states differ from GNU stty: {mismatches:?}"
+        );
+    }
+
+    #[test]
+    fn test_stty_all_control_characters_match_gnu() {
+        unwrap_or_return!(check_coreutil_version("stty", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        let ours = parse_assignments(&stty_under_pty(&ts, false, &["-a"]));
+        let gnu = parse_assignments(&stty_under_pty(&ts, true, &["-a"]));
+
+        let mut mismatches = Vec::new();
+        for (name, value) in &ours {
+            match gnu.get(name) {
+                Some(gnu_value) if gnu_value != value => {
+                    mismatches.push(format!("{name}: ours={value}, gnu={gnu_value}"));
+                }
+                None => println!("uutils-tests-info: field '{name}' not reported by GNU stty"),
+                Some(_) => {}
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "control characters differ from GNU stty: {mismatches:?}"
+        );
+    }
+
+    #[test]
+    fn test_stty_all_full_output_matches_gnu() {
+        unwrap_or_return!(check_coreutil_version("stty", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        // grouping, ordering and line wrapping now follow GNU exactly, so
+        // the whole output can be compared at once
+        let ours = stty_under_pty(&ts, false, &["-a"]);
+        let gnu = stty_under_pty(&ts, true, &["-a"]);
+        assert_eq!(ours, gnu, "-a output differs from GNU stty");
+    }
+
+    #[test]
+    fn test_stty_default_output_matches_gnu() {
+        unwrap_or_return!(check_coreutil_version("stty", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        let ours = stty_under_pty(&ts, false, &[]);
+        let gnu = stty_under_pty(&ts, true, &[]);
+        assert_eq!(ours, gnu, "default output differs from GNU stty");
+    }
+
+    #[test]
+    fn test_stty_save_string_matches_gnu() {
+        unwrap_or_return!(check_coreutil_version("stty", VERSION_MIN));
+        let ts = TestScenario::new(util_name!());
+
+        let ours = stty_under_pty(&ts, false, &["-g"]);
+        let gnu = stty_under_pty(&ts, true, &["-g"]);
+
+        let our_fields: Vec<&str> = ours.trim().split(':').collect();
+        let gnu_fields: Vec<&str> = gnu.trim().split(':').collect();
+
+        assert_eq!(
+            our_fields.len(),
+            gnu_fields.len(),
+            "-g field count differs from GNU stty"
+        );
+        for (i, (ours, gnu)) in our_fields.iter().zip(gnu_fields.iter()).enumerate() {
+            assert_eq!(ours, gnu, "-g field {i} differs from GNU stty");
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn accepts_delay_settings() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["nl0", "cr0", "tab3"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn accepts_delay_settings_with_verbose() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--verbose", "nl0", "ofill"])
+        .succeeds();
+}
+
+#[test]
+#[cfg(all(unix, not(any(target_os = "android", target_os = "linux"))))]
+fn verbose_warns_about_no_op_settings() {
+    // ofill is not representable through the termios abstraction here and
+    // is accepted as a no-op
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--verbose", "ofill"])
+        .succeeds()
+        .stderr_contains("setting 'ofill' has no effect on this platform");
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn ofill_is_applied_for_real_on_linux() {
+    // on Linux ofill is a real termios bit, not a no-op, and surviving the
+    // round trip through the kernel must not be reported as a failure
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--verbose", "ofill"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+fn generate_completion_includes_extensions() {
+    new_ucmd!()
+        .args(&["--generate-completion", "zsh"])
+        .succeeds()
+        .stdout_contains("--verbose");
+}
+
+#[test]
+#[cfg(unix)]
+fn quiet_errors_applies_valid_settings_and_fails_at_the_end() {
+    let result = new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--quiet-errors", "echo", "bogus1", "icanon", "bogus2"])
+        .fails();
+    result.code_is(1);
+    result.stderr_contains("invalid argument 'bogus1'");
+    result.stderr_contains("invalid argument 'bogus2'");
+}
+
+#[test]
+#[cfg(unix)]
+fn quiet_errors_verbose_prints_per_setting_status() {
+    let result = new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--quiet-errors", "--verbose", "echo", "bogus"])
+        .fails();
+    result.stdout_contains("setting echo: ok");
+    result.stdout_contains("setting bogus: invalid");
+}
+
+#[test]
+#[cfg(unix)]
+fn quiet_errors_all_valid_settings_succeed() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--quiet-errors", "echo", "icanon"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn contradicting_settings_are_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--", "icanon", "-icanon"])
+        .fails()
+        .stderr_contains("the settings 'icanon' and '-icanon' contradict each other");
+}
+
+#[test]
+#[cfg(unix)]
+fn contradicting_settings_with_force_warn_and_apply_last() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--force", "--", "-echo", "echo"])
+        .succeeds()
+        .stderr_contains("settings '-echo' and 'echo' contradict each other; the last one wins");
+}
+
+#[test]
+#[cfg(unix)]
+fn repeated_setting_with_same_polarity_is_not_a_conflict() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["icanon", "icanon"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn sets_control_chars_in_caret_integer_and_undef_forms() {
+    // equivalent spellings of the same characters; each must parse
+    for value in ["^C", "0x03", "003", "3"] {
+        new_ucmd!()
+            .terminal_simulation(true)
+            .args(&["intr", value])
+            .succeeds()
+            .no_output();
+    }
+    for value in ["undef", "^-"] {
+        new_ucmd!()
+            .terminal_simulation(true)
+            .args(&["eol", value])
+            .succeeds()
+            .no_output();
+    }
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["erase", "^?"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn control_char_with_invalid_value_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["intr", "0x1zz"])
+        .fails()
+        .stderr_contains("invalid integer argument '0x1zz'");
+}
+
+#[test]
+#[cfg(unix)]
+fn control_char_without_value_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("intr")
+        .fails()
+        .stderr_contains("missing argument to 'intr'");
+}
+
+#[test]
+#[cfg(unix)]
+fn save_string_round_trips() {
+    let saved = new_ucmd!()
+        .terminal_simulation(true)
+        .arg("--save")
+        .succeeds()
+        .stdout_move_str();
+
+    // both terminals live on the same system, so the saved state must apply
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg(saved.trim())
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn save_string_restores_exact_state_on_same_tty() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    // save, change speed and a local flag, restore, save again: the two
+    // saved strings must be identical bit for bit
+    let script = format!(
+        "p=$(tty); saved=$({bin} stty -g -F \"$p\") || exit 1; \
+         {bin} stty -F \"$p\" -icanon 9600 || exit 1; \
+         {bin} stty -F \"$p\" \"$saved\" || exit 1; \
+         after=$({bin} stty -g -F \"$p\") || exit 1; \
+         test \"$saved\" = \"$after\" && echo round-trip-ok"
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("round-trip-ok");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn bsd_gfmt1_save_string_is_accepted() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    // a BSD-style string with a subset of the fields: the interrupt
+    // character becomes ^G and both speeds 9600; the BSD-only dsusp entry
+    // is validated but skipped
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" 'gfmt1:intr=7:dsusp=19:ispeed=9600:ospeed=9600' || exit 1; \
+         {bin} stty -a -F \"$p\""
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("speed 9600 baud")
+        .stdout_contains("intr = ^G");
+}
+
+#[test]
+#[cfg(unix)]
+fn bsd_gfmt1_with_unknown_field_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("gfmt1:bogus=1")
+        .fails()
+        .stderr_contains("invalid argument 'gfmt1:bogus=1'");
+}
+
+#[test]
+#[cfg(unix)]
+fn bsd_gfmt1_disabled_character_translates_to_undef() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    // 0xff is how the BSDs disable a control character
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" 'gfmt1:susp=ff' || exit 1; \
+         {bin} stty -a -F \"$p\""
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("susp = <undef>");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn rows_and_columns_change_window_size() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" rows 30 cols 78 || exit 1; \
+         {bin} stty -a -F \"$p\""
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("rows 30; columns 78;");
+}
+
+#[test]
+#[cfg(unix)]
+fn window_size_with_invalid_value_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["rows", "abc"])
+        .fails()
+        .stderr_contains("invalid integer argument 'abc'");
+}
+
+#[test]
+#[cfg(unix)]
+fn window_size_without_value_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("columns")
+        .fails()
+        .stderr_contains("missing argument to 'columns'");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn bare_baud_rate_sets_the_speed() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" 9600 || exit 1; \
+         {bin} stty -F \"$p\""
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("speed 9600 baud;");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ospeed_sets_the_output_speed() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" ospeed 19200 || exit 1; \
+         {bin} stty -F \"$p\""
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("speed 19200 baud;");
+}
+
+#[test]
+#[cfg(unix)]
+fn ispeed_with_unknown_rate_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["ispeed", "12345"])
+        .fails()
+        .stderr_contains("invalid argument '12345'");
+}
+
+#[test]
+#[cfg(unix)]
+fn ispeed_without_value_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("ispeed")
+        .fails()
+        .stderr_contains("missing argument to 'ispeed'");
+}
+
+#[test]
+#[cfg(unix)]
+fn no_rollback_is_accepted_with_settings() {
+    // a fully applied batch behaves the same with and without rollback
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--no-rollback", "-icanon"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn malformed_save_string_is_rejected() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("1:2:3")
+        .fails()
+        .stderr_contains("invalid argument '1:2:3'");
+}
+
+#[test]
+#[cfg(unix)]
+fn combination_settings_are_accepted() {
+    // parity-related combinations (evenp, oddp, pass8, litout) are skipped:
+    // pty drivers may reject parity flag changes with EINVAL
+    for combo in [
+        "sane", "raw", "-raw", "cooked", "-cooked", "cbreak", "-cbreak", "nl", "-nl", "ek", "crt",
+        "dec",
+    ] {
+        new_ucmd!()
+            .terminal_simulation(true)
+            .args(&["--", combo])
+            .succeeds()
+            .no_output();
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn negated_settings_work_without_double_dash() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("-icanon")
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+#[cfg(unix)]
+fn combinations_without_negated_form_are_rejected() {
+    for combo in ["-sane", "-ek", "-crt", "-dec"] {
+        new_ucmd!()
+            .terminal_simulation(true)
+            .args(&["--", combo])
+            .fails()
+            .stderr_contains(format!("invalid argument '{combo}'"));
+    }
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn diff_prints_nothing_on_a_sane_terminal() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    // bracket the (empty) diff output with markers, since the terminal
+    // simulation interleaves its own echo into stdout
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" sane || exit 1; \
+         echo first-marker; {bin} stty --diff -F \"$p\"; echo second-marker"
+    );
+    ts.cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("first-marker\r\nsecond-marker");
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn diff_prints_only_the_deviating_settings() {
+    let ts = TestScenario::new(util_name!());
+    let bin = ts.bin_path.display().to_string();
+    let script = format!(
+        "p=$(tty); {bin} stty -F \"$p\" sane -icanon intr ^G || exit 1; \
+         {bin} stty --diff -F \"$p\""
+    );
+    let result = ts
+        .cmd("sh")
+        .args(&["-c", &script])
+        .terminal_simulation(true)
+        .succeeds();
+    result.stdout_contains("intr = ^G");
+    result.stdout_contains("-icanon");
+    // everything else still matches sane and stays quiet
+    result.stdout_does_not_contain("icrnl");
+    result.stdout_does_not_contain("speed");
+}
+
+#[test]
+#[cfg(unix)]
+fn diff_conflicts_with_the_other_output_styles() {
+    for args in [&["--diff", "-a"], &["--diff", "-g"]] {
+        new_ucmd!()
+            .terminal_simulation(true)
+            .args(args)
+            .fails()
+            .stderr_contains("output styles are mutually exclusive");
+    }
+
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--diff", "icanon"])
+        .fails()
+        .stderr_contains("when specifying an output style, modes may not be set");
+}
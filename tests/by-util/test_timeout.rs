@@ -170,3 +170,450 @@ fn test_kill_subprocess() {
         .stdout_contains("inside_trap")
         .stderr_contains("Terminated");
 }
+
+#[test]
+fn test_status_json_command_exits_in_time() {
+    new_ucmd!()
+        .args(&["--status-json", "10", "true"])
+        .succeeds()
+        .no_stdout()
+        .stderr_contains("timeout-status: {\"timed_out\":false,\"signal_sent\":null,\"exit_code\":0,\"child_signal\":null,");
+}
+
+#[test]
+fn test_status_json_command_times_out() {
+    new_ucmd!()
+        .args(&["--status-json", ".1", "sleep", "10"])
+        .fails()
+        .code_is(124)
+        .stderr_contains("timeout-status: {\"timed_out\":true,\"signal_sent\":\"TERM\",");
+}
+
+#[test]
+fn test_status_json_reports_child_exit_code() {
+    new_ucmd!()
+        .args(&["--status-json", "10", "sh", "-c", "exit 3"])
+        .fails()
+        .code_is(3)
+        .stderr_contains("\"exit_code\":3,\"child_signal\":null,");
+}
+
+#[test]
+fn test_on_timeout_hook_runs_when_timeout_fires() {
+    new_ucmd!()
+        .args(&["--on-timeout", "echo hook ran >&2", "0.1", "sleep", "10"])
+        .fails()
+        .code_is(124)
+        .stderr_contains("hook ran");
+}
+
+#[test]
+fn test_on_timeout_hook_receives_child_pid() {
+    let result = new_ucmd!()
+        .args(&[
+            "--on-timeout",
+            "echo pid=$TIMEOUT_CHILD_PID >&2",
+            "0.1",
+            "sleep",
+            "10",
+        ])
+        .fails();
+    result.code_is(124);
+    let stderr = result.stderr_str();
+    let pid = stderr
+        .trim()
+        .strip_prefix("pid=")
+        .and_then(|p| p.parse::<u32>().ok());
+    assert!(pid.is_some(), "no child pid in: {stderr:?}");
+}
+
+#[test]
+fn test_on_timeout_hook_not_run_without_timeout() {
+    new_ucmd!()
+        .args(&["--on-timeout", "echo hook ran >&2", "10", "true"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+fn test_generate_completion_without_duration_and_command() {
+    new_ucmd!()
+        .args(&["--generate-completion", "fish"])
+        .succeeds()
+        .stdout_contains("on-timeout");
+}
+
+#[test]
+fn test_command_not_found_exits_with_127() {
+    new_ucmd!()
+        .args(&["1", "/no/such/command-here"])
+        .fails()
+        .code_is(127)
+        .stderr_contains("failed to run command")
+        .stderr_contains("No such file or directory");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_not_executable_exits_with_126() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write("script", "#!/bin/sh\ntrue\n");
+    // no execute permission
+    ts.ucmd()
+        .args(&["1", &at.plus_as_string("script")])
+        .fails()
+        .code_is(126)
+        .stderr_contains("failed to run command")
+        .stderr_contains("Permission denied");
+}
+
+#[test]
+fn test_from_env_reads_timeout_variable() {
+    new_ucmd!()
+        .env("TIMEOUT", "10")
+        .args(&["--from-env", "true"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+fn test_from_env_named_variable_times_out() {
+    new_ucmd!()
+        .env("MY_TIMEOUT", "0.1")
+        .args(&["--from-env=MY_TIMEOUT", "sleep", "10"])
+        .fails()
+        .code_is(124);
+}
+
+#[test]
+fn test_from_env_unset_variable() {
+    new_ucmd!()
+        .args(&["--from-env=NO_SUCH_TIMEOUT_VAR", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("environment variable 'NO_SUCH_TIMEOUT_VAR' is not set for '--from-env'");
+}
+
+#[test]
+fn test_from_env_invalid_duration() {
+    new_ucmd!()
+        .env("TIMEOUT", "bogus")
+        .args(&["--from-env", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid duration in environment variable 'TIMEOUT'");
+}
+
+#[test]
+fn test_from_env_requires_command() {
+    new_ucmd!()
+        .env("TIMEOUT", "10")
+        .arg("--from-env")
+        .fails()
+        .code_is(125)
+        .stderr_contains("you must specify a command with '--from-env'");
+}
+
+fn budget_deadline_in(seconds: f64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    format!("{:.6}", now + seconds)
+}
+
+#[test]
+fn test_inherit_budget_caps_duration_to_outer_deadline() {
+    new_ucmd!()
+        .env("TIMEOUT_BUDGET_DEADLINE", budget_deadline_in(0.2))
+        .args(&["--inherit-budget", "-v", "3600", "sleep", "10"])
+        .fails()
+        .code_is(124)
+        .stderr_contains("left by the enclosing timeout");
+}
+
+#[test]
+fn test_inherit_budget_exports_deadline_to_command() {
+    let result = new_ucmd!()
+        .args(&[
+            "--inherit-budget",
+            "5",
+            "sh",
+            "-c",
+            "echo $TIMEOUT_BUDGET_DEADLINE",
+        ])
+        .succeeds();
+    result
+        .stdout_str()
+        .trim()
+        .parse::<f64>()
+        .expect("deadline should be exported as a number");
+}
+
+#[test]
+fn test_inherit_budget_ignored_without_flag() {
+    new_ucmd!()
+        .env("TIMEOUT_BUDGET_DEADLINE", budget_deadline_in(-10.0))
+        .args(&["10", "true"])
+        .succeeds()
+        .no_output();
+}
+
+#[test]
+fn test_inherit_budget_invalid_deadline() {
+    new_ucmd!()
+        .env("TIMEOUT_BUDGET_DEADLINE", "bogus")
+        .args(&["--inherit-budget", "10", "true"])
+        .fails()
+        .code_is(125)
+        .stderr_contains("invalid deadline in $TIMEOUT_BUDGET_DEADLINE");
+}
+
+#[test]
+fn test_inherit_budget_exhausted_budget_still_runs_command() {
+    // with the budget already spent the timeout fires right away,
+    // but the command is still started
+    new_ucmd!()
+        .env("TIMEOUT_BUDGET_DEADLINE", budget_deadline_in(-10.0))
+        .args(&["--inherit-budget", "3600", "sleep", "10"])
+        .fails()
+        .code_is(124);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_new_session_makes_command_the_session_leader() {
+    // field 6 of /proc/PID/stat is the session id
+    new_ucmd!()
+        .args(&[
+            "--new-session",
+            "10",
+            "sh",
+            "-c",
+            "test \"$(cut -d' ' -f6 /proc/$$/stat)\" = \"$$\" && echo leader",
+        ])
+        .succeeds()
+        .stdout_contains("leader");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_without_new_session_command_keeps_the_session() {
+    new_ucmd!()
+        .args(&[
+            "10",
+            "sh",
+            "-c",
+            "test \"$(cut -d' ' -f6 /proc/$$/stat)\" != \"$$\" && echo inherited",
+        ])
+        .succeeds()
+        .stdout_contains("inherited");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_signal_chain_escalates_through_each_signal() {
+    new_ucmd!()
+        .args(&[
+            "-v",
+            "-s",
+            "USR1",
+            "-s",
+            "USR2",
+            "-k",
+            ".2",
+            ".1",
+            "sh",
+            "-c",
+            "trap '' USR1 USR2; sleep 10",
+        ])
+        .fails()
+        .code_is(137)
+        .stderr_contains("sending signal USR1")
+        .stderr_contains("sending signal USR2")
+        .stderr_contains("sending signal KILL");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_signal_chain_stops_once_a_signal_terminates_the_command() {
+    // USR1 already terminates sleep, so neither TERM nor KILL is sent
+    new_ucmd!()
+        .args(&[
+            "-v", "-s", "USR1", "-s", "TERM", "-k", ".5", ".1", "sleep", "10",
+        ])
+        .fails()
+        .stderr_contains("sending signal USR1")
+        .stderr_does_not_contain("sending signal TERM")
+        .stderr_does_not_contain("sending signal KILL");
+}
+
+#[test]
+fn test_signal_chain_requires_kill_after() {
+    new_ucmd!()
+        .args(&["-s", "USR1", "-s", "TERM", "1", "sleep", "0"])
+        .fails()
+        .code_is(125)
+        .usage_error("'--kill-after' is required when more than one signal is specified");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_without_foreground_command_runs_in_timeouts_process_group() {
+    // field 5 of /proc/PID/stat is the process group; by default timeout
+    // makes itself (the $PPID of the command) the group leader
+    new_ucmd!()
+        .args(&[
+            "10",
+            "sh",
+            "-c",
+            "test \"$(cut -d' ' -f5 /proc/$$/stat)\" = \"$PPID\" && echo grouped",
+        ])
+        .succeeds()
+        .stdout_contains("grouped");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_foreground_command_keeps_the_process_group() {
+    // with --foreground no new process group is created, so the command
+    // stays in the group it inherited instead of the one led by timeout
+    new_ucmd!()
+        .args(&[
+            "--foreground",
+            "10",
+            "sh",
+            "-c",
+            "test \"$(cut -d' ' -f5 /proc/$$/stat)\" != \"$PPID\" && echo foreground",
+        ])
+        .succeeds()
+        .stdout_contains("foreground");
+}
+
+#[test]
+#[cfg(windows)]
+fn test_job_object_terminates_the_whole_process_tree() {
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    // cmd starts a detached grandchild that appends to a marker file about
+    // once a second; the job object has to take it down together with cmd
+    // itself, so the marker must stop growing once the timeout fired.
+    ts.ucmd()
+        .args(&[
+            "-k",
+            ".5",
+            "1",
+            "cmd",
+            "/C",
+            "start /B cmd /C \"for /L %i in (1,1,30) do (echo tick>> marker.txt & ping -n 2 127.0.0.1 >NUL)\" & ping -n 30 127.0.0.1 >NUL",
+        ])
+        .fails();
+    sleep(Duration::from_secs(2));
+    let after_kill = at.read("marker.txt");
+    sleep(Duration::from_secs(3));
+    assert_eq!(
+        after_kill,
+        at.read("marker.txt"),
+        "grandchild survived the job object"
+    );
+}
+
+#[test]
+fn test_double_verbose_reports_elapsed_time() {
+    let result = new_ucmd!().args(&["-vv", ".1", "sleep", "10"]).fails();
+    result
+        .code_is(124)
+        .stderr_contains("sending signal TERM to command 'sleep' after ");
+    // microsecond resolution: the offset has six fractional digits and is at
+    // least the .1s timeout itself
+    let offset = result
+        .stderr_str()
+        .trim_end()
+        .strip_suffix('s')
+        .and_then(|line| line.rsplit(' ').next())
+        .expect("no elapsed time in diagnostic");
+    let (_, fraction) = offset.split_once('.').expect("no fractional part");
+    assert_eq!(fraction.len(), 6, "unexpected precision in {offset:?}");
+    assert!(offset.parse::<f64>().unwrap() >= 0.1);
+}
+
+#[test]
+fn test_single_verbose_keeps_gnu_format() {
+    new_ucmd!()
+        .args(&["-v", ".1", "sleep", "10"])
+        .fails()
+        .stderr_only("timeout: sending signal TERM to command 'sleep'\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_quiet_kill_exits_normally_instead_of_dying_by_signal() {
+    let result = new_ucmd!()
+        .args(&["--quiet-kill", "--preserve-status", ".1", "sleep", "10"])
+        .fails();
+    // the supervisor absorbed the signal death; we exited with 128+SIGTERM
+    assert_eq!(result.signal(), None);
+    result.code_is(128 + 15).no_stderr().no_stdout();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_quiet_kill_keeps_the_plain_timeout_code() {
+    new_ucmd!()
+        .args(&["--quiet-kill", ".1", "sleep", "10"])
+        .fails()
+        .code_is(124)
+        .no_stderr()
+        .no_stdout();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_quiet_kill_passes_the_exit_code_through() {
+    new_ucmd!()
+        .args(&["--quiet-kill", "1", "true"])
+        .succeeds()
+        .no_output();
+    new_ucmd!()
+        .args(&["--quiet-kill", "1", "sh", "-c", "exit 3"])
+        .fails()
+        .code_is(3);
+}
+
+#[test]
+fn test_fake_clock_compresses_a_long_timeout() {
+    // 60 seconds shrink to 60 milliseconds, so the kill happens long before
+    // the harness' own timeout
+    new_ucmd!()
+        .fake_clock_scale(1000)
+        .args(&["60", "sleep", "600"])
+        .fails()
+        .code_is(124);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_fake_clock_scales_kill_after_too() {
+    // 30 scaled seconds until USR1 (ignored across the exec), 60 more until
+    // KILL; finishing within the harness timeout proves --kill-after shrank.
+    // --foreground signals the child directly, so the KILL reliably lands.
+    new_ucmd!()
+        .fake_clock_scale(1000)
+        .args(&[
+            "--foreground",
+            "-v",
+            "-sUSR1",
+            "-k60",
+            "30",
+            "sh",
+            "-c",
+            "trap '' USR1; exec sleep 600",
+        ])
+        .fails()
+        .code_is(137)
+        .stderr_contains("sending signal KILL");
+}
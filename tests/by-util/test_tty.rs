@@ -87,3 +87,65 @@ fn test_stdout_fail() {
     let status = proc.wait().unwrap();
     assert_eq!(status.code(), Some(3));
 }
+
+#[test]
+fn test_generate_completion() {
+    new_ucmd!()
+        .args(&["--generate-completion", "bash"])
+        .succeeds()
+        .stdout_contains("--silent");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_file_not_a_tty() {
+    new_ucmd!()
+        .args(&["--file=/dev/null"])
+        .set_stdin(File::open("/dev/null").unwrap())
+        .fails()
+        .code_is(2)
+        .stdout_is("not a tty\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_file_same_terminal() {
+    let result = new_ucmd!()
+        .args(&["--file=/dev/stdin"])
+        .terminal_simulation(true)
+        .succeeds();
+    assert_eq!(result.stdout_str().trim_end(), "same");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_file_different_device() {
+    let result = new_ucmd!()
+        .args(&["--file=/dev/null"])
+        .terminal_simulation(true)
+        .fails();
+    result.code_is(1);
+    assert_eq!(result.stdout_str().trim_end(), "different");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_file_silent_only_sets_exit_code() {
+    let result = new_ucmd!()
+        .args(&["--silent", "--file=/dev/null"])
+        .terminal_simulation(true)
+        .fails();
+    result.code_is(1);
+    assert_eq!(result.stdout_str(), "");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_file_nonexistent_path() {
+    new_ucmd!()
+        .args(&["--file=/dev/nonexistent"])
+        .terminal_simulation(true)
+        .fails()
+        .code_is(3)
+        .stderr_contains("cannot stat '/dev/nonexistent'");
+}
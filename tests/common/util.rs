@@ -10,6 +10,8 @@
 
 #[cfg(unix)]
 use nix::pty::OpenptyResult;
+#[cfg(unix)]
+use nix::sys::termios::{tcgetattr, LocalFlags, OutputFlags};
 use pretty_assertions::assert_eq;
 #[cfg(unix)]
 use rlimit::setrlimit;
@@ -21,9 +23,9 @@ use std::collections::VecDeque;
 use std::ffi::CString;
 use std::ffi::{OsStr, OsString};
 use std::fs::{self, hard_link, remove_file, File, OpenOptions};
-use std::io::{self, BufWriter, Read, Result, Write};
+use std::io::{self, BufWriter, Read, Result, Seek, SeekFrom, Write};
 #[cfg(unix)]
-use std::os::fd::OwnedFd;
+use std::os::fd::{AsRawFd, OwnedFd};
 #[cfg(unix)]
 use std::os::unix::fs::{symlink as symlink_dir, symlink as symlink_file, PermissionsExt};
 #[cfg(unix)]
@@ -37,7 +39,9 @@ use std::path::MAIN_SEPARATOR_STR;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Output, Stdio};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
 use std::thread::{sleep, JoinHandle};
 use std::time::{Duration, Instant};
 use std::{env, hint, mem, thread};
@@ -52,7 +56,15 @@ static ALREADY_RUN: &str = " you have already run this UCommand, if you want to
 static MULTIPLE_STDIN_MEANINGLESS: &str = "Ucommand is designed around a typical use case of: provide args and input stream -> spawn process -> block until completion -> return output streams. For verifying that a particular section of the input stream is what causes a particular behavior, use the Command type directly.";
 
 static NO_STDIN_MEANINGLESS: &str = "Setting this flag has no effect if there is no stdin";
+/// What an interactive user would type to signal end of input: `^D` on an
+/// empty line on unix, `^Z` followed by `Enter` on a windows console.
+#[cfg(not(windows))]
 static END_OF_TRANSMISSION_SEQUENCE: &[u8] = &[b'\n', 0x04];
+#[cfg(windows)]
+static END_OF_TRANSMISSION_SEQUENCE: &[u8] = &[b'\r', b'\n', 0x1A, b'\r', b'\n'];
+
+/// Marker appended to the captured output when the capture limit was reached.
+pub const CAPTURE_TRUNCATION_MARKER: &[u8] = b"[capture limit reached, output truncated]";
 
 pub const TESTS_BINARY: &str = env!("CARGO_BIN_EXE_coreutils");
 pub const PATH: &str = env!("PATH");
@@ -65,6 +77,42 @@ pub fn is_ci() -> bool {
     std::env::var("CI").is_ok_and(|s| s.eq_ignore_ascii_case("true"))
 }
 
+/// `base` plus a suffix the platform's filesystem accepts but that is not
+/// valid Unicode: arbitrary non-UTF8 bytes on unix, an unpaired surrogate on
+/// windows. Use this to exercise utilities on file names that `&str` cannot
+/// represent.
+pub fn non_utf8_name(base: &str) -> OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        let mut name = base.as_bytes().to_vec();
+        name.extend(b"_\xFF\xFE");
+        OsString::from_vec(name)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        let mut name: Vec<u16> = base.encode_utf16().collect();
+        name.extend([u16::from(b'_'), 0xD800]);
+        OsString::from_wide(&name)
+    }
+}
+
+/// The bytes a utility writes to its output streams when printing `s`: the
+/// raw bytes on unix, the lossy UTF-8 encoding on windows (which is how the
+/// utilities themselves print names that are not valid Unicode there).
+pub fn os_str_display_bytes(s: &OsStr) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        s.as_bytes().to_vec()
+    }
+    #[cfg(windows)]
+    {
+        s.to_string_lossy().into_owned().into_bytes()
+    }
+}
+
 /// Read a test scenario fixture, returning its bytes
 fn read_scenario_fixture<S: AsRef<OsStr>>(tmpd: &Option<Rc<TempDir>>, file_rel_path: S) -> Vec<u8> {
     let tmpdir_path = tmpd.as_ref().unwrap().as_ref().path();
@@ -89,6 +137,28 @@ pub struct CmdResult {
     stderr: Vec<u8>,
 }
 
+/// Exit code a process conventionally reports on windows when terminated in a
+/// way that corresponds to the given unix signal: `NTSTATUS` values for
+/// console events and faults, `1` for `TerminateProcess` (which
+/// [`UChild::kill`] uses).
+#[cfg(windows)]
+fn windows_exit_code_for_signal(name: &str) -> Option<i32> {
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    Some(match name {
+        // STATUS_CONTROL_C_EXIT
+        "INT" => 0xC000_013A_u32 as i32,
+        // TerminateProcess as used by `UChild::kill`
+        "KILL" | "TERM" => 1,
+        // STATUS_ACCESS_VIOLATION
+        "SEGV" => 0xC000_0005_u32 as i32,
+        // STATUS_ILLEGAL_INSTRUCTION
+        "ILL" => 0xC000_001D_u32 as i32,
+        // STATUS_INTEGER_DIVIDE_BY_ZERO
+        "FPE" => 0xC000_0094_u32 as i32,
+        _ => return None,
+    })
+}
+
 impl CmdResult {
     pub fn new<S, T, U, V>(
         bin_path: S,
@@ -319,6 +389,25 @@ impl CmdResult {
         self
     }
 
+    /// Assert that the process was terminated by the signal `name`, portably.
+    ///
+    /// On unix this checks the received signal like [`CmdResult::signal_name_is`].
+    /// On windows, where such terminations surface as exit codes instead, the
+    /// conventional exit code for the signal is checked (e.g. `0xC000013A` for a
+    /// console Ctrl+C). Tests for timeout/kill behavior can therefore state one
+    /// expectation instead of a cfg block per platform.
+    #[track_caller]
+    pub fn code_or_signal_is(&self, name: &str) -> &Self {
+        #[cfg(unix)]
+        return self.signal_name_is(name);
+        #[cfg(not(unix))]
+        {
+            let code = windows_exit_code_for_signal(name)
+                .unwrap_or_else(|| panic!("No windows exit code mapping for signal '{name}'"));
+            self.code_is(code)
+        }
+    }
+
     /// Returns a reference to the program's standard output as a slice of bytes
     pub fn stdout(&self) -> &[u8] {
         &self.stdout
@@ -329,6 +418,11 @@ impl CmdResult {
         std::str::from_utf8(&self.stdout).unwrap()
     }
 
+    /// Returns the program's standard output as a string slice, automatically handling invalid utf8
+    pub fn stdout_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stdout)
+    }
+
     /// Returns the program's standard output as a string
     /// consumes self
     pub fn stdout_move_str(self) -> String {
@@ -505,6 +599,27 @@ impl CmdResult {
         self
     }
 
+    /// like `stdout_is`, but takes an OS string, so the expected output may
+    /// contain file names that are not valid Unicode
+    #[track_caller]
+    pub fn stdout_is_os<T: AsRef<OsStr>>(&self, msg: T) -> &Self {
+        self.stdout_is_bytes(os_str_display_bytes(msg.as_ref()))
+    }
+
+    /// like `stdout_contains`, but takes an OS string, so the expected
+    /// fragment may be a file name that is not valid Unicode
+    #[track_caller]
+    pub fn stdout_contains_os<T: AsRef<OsStr>>(&self, cmp: T) -> &Self {
+        let needle = os_str_display_bytes(cmp.as_ref());
+        assert!(
+            needle.is_empty() || self.stdout.windows(needle.len()).any(|win| win == needle),
+            "'{:?}' does not contain '{:?}'",
+            self.stdout_str_lossy(),
+            cmp.as_ref()
+        );
+        self
+    }
+
     /// like `stdout_is()`, but expects the contents of the file at the provided relative path
     #[track_caller]
     pub fn stdout_is_fixture<T: AsRef<OsStr>>(&self, file_rel_path: T) -> &Self {
@@ -700,6 +815,20 @@ impl CmdResult {
         self
     }
 
+    /// like `stderr_contains`, but takes an OS string, so the expected
+    /// fragment may be a file name that is not valid Unicode
+    #[track_caller]
+    pub fn stderr_contains_os<T: AsRef<OsStr>>(&self, cmp: T) -> &Self {
+        let needle = os_str_display_bytes(cmp.as_ref());
+        assert!(
+            needle.is_empty() || self.stderr.windows(needle.len()).any(|win| win == needle),
+            "'{:?}' does not contain '{:?}'",
+            self.stderr_str_lossy(),
+            cmp.as_ref()
+        );
+        self
+    }
+
     #[track_caller]
     pub fn stdout_does_not_contain<T: AsRef<str>>(&self, cmp: T) -> &Self {
         assert!(
@@ -727,6 +856,49 @@ impl CmdResult {
         self
     }
 
+    /// Parses the program's standard output as JSON (for the `--format=json`
+    /// extension modes). Panics with the parse error and the raw output when
+    /// stdout is not valid JSON.
+    #[track_caller]
+    pub fn stdout_json(&self) -> serde_json::Value {
+        serde_json::from_str(self.stdout_str()).unwrap_or_else(|e| {
+            panic!(
+                "stdout is not valid JSON: {e}\nstdout:\n{}",
+                self.stdout_str()
+            )
+        })
+    }
+
+    /// asserts that the JSON document on stdout equals `expected` (build it
+    /// with `serde_json::json!`); mismatches show a pretty-printed diff
+    #[track_caller]
+    pub fn stdout_json_is(&self, expected: &serde_json::Value) -> &Self {
+        assert_eq!(
+            serde_json::to_string_pretty(&self.stdout_json()).unwrap(),
+            serde_json::to_string_pretty(expected).unwrap(),
+        );
+        self
+    }
+
+    /// asserts that the field at the JSON pointer `pointer` (RFC 6901, e.g.
+    /// `/terminal/rows`) exists in the stdout document and equals `expected`
+    #[track_caller]
+    pub fn stdout_json_field_is(&self, pointer: &str, expected: &serde_json::Value) -> &Self {
+        let document = self.stdout_json();
+        let actual = document.pointer(pointer).unwrap_or_else(|| {
+            panic!(
+                "no JSON field at {pointer:?} in stdout:\n{}",
+                serde_json::to_string_pretty(&document).unwrap()
+            )
+        });
+        assert_eq!(
+            serde_json::to_string_pretty(actual).unwrap(),
+            serde_json::to_string_pretty(expected).unwrap(),
+            "JSON mismatch at {pointer:?}"
+        );
+        self
+    }
+
     #[track_caller]
     pub fn stderr_matches(&self, regex: &regex::Regex) -> &Self {
         assert!(
@@ -882,6 +1054,24 @@ impl AtPath {
             .unwrap_or_else(|e| panic!("Couldn't write {name}: {e}"));
     }
 
+    /// Like [`AtPath::write_bytes`], but takes any path-like name, so tests
+    /// can create fixtures whose names are not valid Unicode.
+    pub fn write_bytes_os<P: AsRef<Path>>(&self, name: P, contents: &[u8]) {
+        let name = name.as_ref();
+        log_info("write(default)", self.plus_as_string(name));
+        std::fs::write(self.plus(name), contents)
+            .unwrap_or_else(|e| panic!("Couldn't write {}: {e}", name.display()));
+    }
+
+    /// Like [`AtPath::read_bytes`], but takes any path-like name, so tests
+    /// can read fixtures whose names are not valid Unicode.
+    pub fn read_bytes_os<P: AsRef<Path>>(&self, name: P) -> Vec<u8> {
+        let name = name.as_ref();
+        log_info("open", self.plus_as_string(name));
+        std::fs::read(self.plus(name))
+            .unwrap_or_else(|e| panic!("Couldn't read {}: {e}", name.display()))
+    }
+
     pub fn append(&self, name: &str, contents: &str) {
         log_info("write(append)", self.plus_as_string(name));
         let mut f = OpenOptions::new()
@@ -1205,6 +1395,110 @@ impl TestScenario {
     pub fn ccmd<S: AsRef<str>>(&self, util_name: S) -> UCommand {
         UCommand::with_util(util_name, self.tmpd.clone())
     }
+
+    /// Returns the path of a link in the temporary test directory that is
+    /// named like the utility and points at the multicall binary, creating
+    /// it on first use. Running it dispatches on `argv[0]` instead of the
+    /// first argument. A symlink is used where possible; on Windows, where
+    /// creating symlinks needs privileges, the binary is copied instead.
+    pub fn link_named_like_util(&self) -> PathBuf {
+        let link = self
+            .tmpd
+            .path()
+            .join(format!("{}{}", self.util_name, env::consts::EXE_SUFFIX));
+        if !link.exists() {
+            #[cfg(unix)]
+            symlink_file(TESTS_BINARY, &link).unwrap();
+            #[cfg(windows)]
+            fs::copy(TESTS_BINARY, &link).unwrap();
+        }
+        link
+    }
+
+    /// Returns builder invoking the multicall binary through a link named
+    /// after the utility (see [`TestScenario::link_named_like_util`]), so
+    /// dispatch-by-name logic gets test coverage.
+    pub fn ucmd_by_name(&self) -> UCommand {
+        self.cmd(self.link_named_like_util())
+    }
+}
+
+/// The size a simulated terminal has unless a test picks its own: 80 columns
+/// by 30 rows, with the conventional 8x10 pixels per cell.
+#[cfg(unix)]
+pub const DEFAULT_TERMINAL_SIZE: libc::winsize = libc::winsize {
+    ws_col: 80,
+    ws_row: 30,
+    ws_xpixel: 80 * 8,
+    ws_ypixel: 30 * 10,
+};
+
+/// Configuration of the simulated terminal a child is run in, applied with
+/// [`UCommand::terminal_simulation_config`].
+///
+/// Built through the constructors rather than field by field, so tests do not
+/// have to spell out the pixel fields of [`libc::winsize`] and new knobs can
+/// be added here without touching every test.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalSimulation {
+    size: libc::winsize,
+    echo: bool,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+}
+
+#[cfg(unix)]
+impl Default for TerminalSimulation {
+    /// A terminal of [`DEFAULT_TERMINAL_SIZE`] on all three streams, with
+    /// input echoing enabled, like a fresh interactive session.
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_TERMINAL_SIZE,
+            echo: true,
+            stdin: true,
+            stdout: true,
+            stderr: true,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl TerminalSimulation {
+    /// A default terminal of `rows` x `cols` cells; the pixel size follows
+    /// the usual 8x10 pixels per cell.
+    pub fn sized(rows: u16, cols: u16) -> Self {
+        Self {
+            size: libc::winsize {
+                ws_col: cols,
+                ws_row: rows,
+                ws_xpixel: cols * 8,
+                ws_ypixel: rows * 10,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// A default terminal with input echoing turned off, the state a utility
+    /// prompting for a password leaves behind.
+    pub fn echo_off() -> Self {
+        Self {
+            echo: false,
+            ..Default::default()
+        }
+    }
+
+    /// A terminal on stdout only: stdin and stderr stay ordinary pipes, so
+    /// `stdout.is_terminal()` holds while piped input and captured errors
+    /// behave exactly as in the non-terminal tests.
+    pub fn stdout_only() -> Self {
+        Self {
+            stdin: false,
+            stderr: false,
+            ..Default::default()
+        }
+    }
 }
 
 /// A `UCommand` is a builder wrapping an individual Command that provides several additional features:
@@ -1242,9 +1536,15 @@ pub struct UCommand {
     stderr_to_stdout: bool,
     timeout: Option<Duration>,
     #[cfg(unix)]
-    terminal_simulation: bool,
+    terminal_simulation: Option<TerminalSimulation>,
+    /// Clones of the pty masters of the simulated terminal, kept so
+    /// [`UChild::resize_terminal`] can change the size mid-run.
+    #[cfg(unix)]
+    pty_masters: Vec<OwnedFd>,
     #[cfg(unix)]
-    terminal_size: Option<libc::winsize>,
+    capture_limit: Option<u64>,
+    #[cfg(unix)]
+    capture_limit_policy: CaptureLimitPolicy,
     tmpd: Option<Rc<TempDir>>, // drop last
 }
 
@@ -1388,6 +1688,16 @@ impl UCommand {
         self
     }
 
+    /// Run the command under a fake clock that passes `scale` times faster.
+    ///
+    /// Utilities honoring `uucore::fake_clock` (timeout, sleep) divide their
+    /// durations by the scale, so a test of a multi-second timeout finishes
+    /// in milliseconds instead of relying on real sleeps. Commands that do
+    /// not honor the variable simply ignore it.
+    pub fn fake_clock_scale(&mut self, scale: u32) -> &mut Self {
+        self.env("UUTILS_FAKE_CLOCK_SCALE", scale.to_string())
+    }
+
     pub fn envs<I, K, V>(&mut self, iter: I) -> &mut Self
     where
         I: IntoIterator<Item = (K, V)>,
@@ -1429,7 +1739,12 @@ impl UCommand {
     /// (unix: pty, windows: ConPTY[not yet supported])
     #[cfg(unix)]
     pub fn terminal_simulation(&mut self, enable: bool) -> &mut Self {
-        self.terminal_simulation = enable;
+        if enable {
+            self.terminal_simulation
+                .get_or_insert_with(TerminalSimulation::default);
+        } else {
+            self.terminal_simulation = None;
+        }
         self
     }
 
@@ -1439,26 +1754,37 @@ impl UCommand {
     /// And the size of the terminal matters additionally.
     #[cfg(unix)]
     pub fn terminal_size(&mut self, win_size: libc::winsize) -> &mut Self {
-        self.terminal_simulation(true);
-        self.terminal_size = Some(win_size);
+        self.terminal_simulation
+            .get_or_insert_with(TerminalSimulation::default)
+            .size = win_size;
         self
     }
 
+    /// Run the process in a simulated terminal with the given configuration,
+    /// e.g. [`TerminalSimulation::sized`] or [`TerminalSimulation::stdout_only`].
     #[cfg(unix)]
-    fn read_from_pty(pty_fd: std::os::fd::OwnedFd, out: File) {
-        let read_file = std::fs::File::from(pty_fd);
-        let mut reader = std::io::BufReader::new(read_file);
-        let mut writer = std::io::BufWriter::new(out);
-        let result = std::io::copy(&mut reader, &mut writer);
-        match result {
-            Ok(_) => {}
-            // Input/output error (os error 5) is returned due to pipe closes. Buffer gets content anyway.
-            Err(e) if e.raw_os_error().unwrap_or_default() == 5 => {}
-            Err(e) => {
-                eprintln!("Unexpected error: {:?}", e);
-                panic!("error forwarding output of pty");
-            }
-        }
+    pub fn terminal_simulation_config(&mut self, config: TerminalSimulation) -> &mut Self {
+        self.terminal_simulation = Some(config);
+        self
+    }
+
+    /// Limit the amount of bytes captured per output stream in a simulated terminal.
+    ///
+    /// Without a limit the reader threads forward everything the child writes, so a runaway
+    /// child can fill up the disk of the test runner. When the limit is reached, the behavior
+    /// depends on the configured [`CaptureLimitPolicy`] and the truncation is reported by
+    /// [`UChild::stdout_is_truncated`] and [`UChild::stderr_is_truncated`].
+    #[cfg(unix)]
+    pub fn capture_limit(&mut self, limit: u64) -> &mut Self {
+        self.capture_limit = Some(limit);
+        self
+    }
+
+    /// Set the behavior of the reader threads when the [`UCommand::capture_limit`] is reached.
+    #[cfg(unix)]
+    pub fn capture_limit_policy(&mut self, policy: CaptureLimitPolicy) -> &mut Self {
+        self.capture_limit_policy = policy;
+        self
     }
 
     #[cfg(unix)]
@@ -1467,18 +1793,25 @@ impl UCommand {
         captured_output: Option<CapturedOutput>,
         pty_fd_master: OwnedFd,
         name: String,
+        terminal_size: libc::winsize,
     ) -> Option<CapturedOutput> {
         if let Some(mut captured_output_i) = captured_output {
             let fd = captured_output_i.try_clone().unwrap();
 
-            let handle = std::thread::Builder::new()
-                .name(name)
-                .spawn(move || {
-                    Self::read_from_pty(pty_fd_master, fd);
-                })
+            let recorder = TerminalRecorder::create(&name, terminal_size);
+            let mut forwarding = ForwardedOutput::default();
+            forwarding
+                .spawn(
+                    name,
+                    pty_fd_master,
+                    fd,
+                    self.capture_limit,
+                    self.capture_limit_policy,
+                    recorder,
+                )
                 .unwrap();
 
-            captured_output_i.reader_thread_handle = Some(handle);
+            captured_output_i.forwarding = Some(forwarding);
             Some(captured_output_i)
         } else {
             None
@@ -1545,6 +1878,26 @@ impl UCommand {
             }
         };
 
+        // If requested through the environment, wrap the uutils binary in a
+        // prefix command (e.g. `valgrind`, `qemu-aarch64`, `wine`) given as a
+        // whitespace separated command line. This enables sanitizer and
+        // foreign-arch runs of the whole suite without touching each test.
+        // Only our own binary is wrapped; shells and system commands spawned
+        // by the harness run directly.
+        if self.bin_path.as_deref() == Some(Path::new(TESTS_BINARY)) {
+            if let Ok(wrapper) = env::var("UUTILS_TEST_WRAPPER") {
+                let mut parts = wrapper.split_whitespace().map(OsString::from);
+                if let Some(wrapper_bin) = parts.next() {
+                    let mut wrapper_args: Vec<OsString> = parts.collect();
+                    wrapper_args.push(self.bin_path.take().unwrap().into_os_string());
+                    while let Some(arg) = wrapper_args.pop() {
+                        self.args.push_front(arg);
+                    }
+                    self.bin_path = Some(PathBuf::from(wrapper_bin));
+                }
+            }
+        }
+
         // unwrap is safe here because we have set `self.bin_path` before
         let mut command = Command::new(self.bin_path.as_ref().unwrap());
         command.args(&self.args);
@@ -1628,35 +1981,52 @@ impl UCommand {
         };
 
         #[cfg(unix)]
-        if self.terminal_simulation {
-            let terminal_size = self.terminal_size.unwrap_or(libc::winsize {
-                ws_col: 80,
-                ws_row: 30,
-                ws_xpixel: 80 * 8,
-                ws_ypixel: 30 * 10,
-            });
-
-            let OpenptyResult {
-                slave: pi_slave,
-                master: pi_master,
-            } = nix::pty::openpty(&terminal_size, None).unwrap();
-            let OpenptyResult {
-                slave: po_slave,
-                master: po_master,
-            } = nix::pty::openpty(&terminal_size, None).unwrap();
-            let OpenptyResult {
-                slave: pe_slave,
-                master: pe_master,
-            } = nix::pty::openpty(&terminal_size, None).unwrap();
-
-            stdin_pty = Some(File::from(pi_master));
-
-            captured_stdout =
-                self.spawn_reader_thread(captured_stdout, po_master, "stdout_reader".to_string());
-            captured_stderr =
-                self.spawn_reader_thread(captured_stderr, pe_master, "stderr_reader".to_string());
-
-            command.stdin(pi_slave).stdout(po_slave).stderr(pe_slave);
+        if let Some(simulation) = self.terminal_simulation {
+            let terminal_size = simulation.size;
+
+            if simulation.stdin {
+                let OpenptyResult { slave, master } =
+                    nix::pty::openpty(&terminal_size, None).unwrap();
+                if !simulation.echo {
+                    let mut termios = tcgetattr(&slave).unwrap();
+                    termios.local_flags.remove(LocalFlags::ECHO);
+                    nix::sys::termios::tcsetattr(
+                        &slave,
+                        nix::sys::termios::SetArg::TCSANOW,
+                        &termios,
+                    )
+                    .unwrap();
+                }
+                self.pty_masters.push(master.try_clone().unwrap());
+                stdin_pty = Some(File::from(master));
+                command.stdin(slave);
+            }
+
+            if simulation.stdout {
+                let OpenptyResult { slave, master } =
+                    nix::pty::openpty(&terminal_size, None).unwrap();
+                self.pty_masters.push(master.try_clone().unwrap());
+                captured_stdout = self.spawn_reader_thread(
+                    captured_stdout,
+                    master,
+                    "stdout_reader".to_string(),
+                    terminal_size,
+                );
+                command.stdout(slave);
+            }
+
+            if simulation.stderr {
+                let OpenptyResult { slave, master } =
+                    nix::pty::openpty(&terminal_size, None).unwrap();
+                self.pty_masters.push(master.try_clone().unwrap());
+                captured_stderr = self.spawn_reader_thread(
+                    captured_stderr,
+                    master,
+                    "stderr_reader".to_string(),
+                    terminal_size,
+                );
+                command.stderr(slave);
+            }
         }
 
         #[cfg(unix)]
@@ -1751,13 +2121,226 @@ impl std::fmt::Display for UCommand {
     }
 }
 
+/// Behavior of a [`ForwardedOutput`] reader thread when the capture limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureLimitPolicy {
+    /// Keep draining the stream but discard everything beyond the limit. The child can
+    /// continue writing output without being blocked.
+    #[default]
+    Truncate,
+    /// Stop reading the stream entirely. The child will block on its next write as soon as
+    /// the kernel buffer of the pty/pipe is full.
+    Backpressure,
+}
+
+/// Snapshot of the mode of the terminal connected to the child's stdin.
+///
+/// Taken with [`UChild::terminal_mode`] to verify that a utility which temporarily changes
+/// terminal settings (e.g. for a password prompt) restores them, or that `stty` applied the
+/// requested settings. On Unix the fields map to the termios flags `ECHO`, `ICANON` and
+/// `OPOST`; on Windows they are intended to map to the console modes `ENABLE_ECHO_INPUT`,
+/// `ENABLE_LINE_INPUT` and `ENABLE_VIRTUAL_TERMINAL_PROCESSING` once the terminal
+/// simulation is available there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalMode {
+    /// Input characters are echoed back to the terminal.
+    pub echo: bool,
+    /// Input is line-based with the terminal providing line editing.
+    pub canonical: bool,
+    /// Output processing (e.g. mapping NL to CR-NL) is enabled.
+    pub output_processing: bool,
+}
+
+/// Records the traffic of one forwarded pty stream into an
+/// asciinema-compatible recording (cast v2, one JSON value per line).
+///
+/// Enabled by setting `UUTILS_TEST_RECORD_TERMINAL` to a directory; every
+/// simulated-terminal test then leaves one `TEST_NAME.STREAM.cast` file per
+/// stream there, which an asciinema player can replay offline with the
+/// original timing — mainly a debugging aid for terminal tests that only
+/// misbehave on a remote CI runner.
+#[cfg(unix)]
+#[derive(Debug)]
+struct TerminalRecorder {
+    file: File,
+    started: Instant,
+}
+
+#[cfg(unix)]
+impl TerminalRecorder {
+    /// Start a recording for the stream named by `reader_name` (e.g.
+    /// "stdout_reader") or return `None` when recording is not enabled.
+    fn create(reader_name: &str, size: libc::winsize) -> Option<Self> {
+        let dir = env::var_os("UUTILS_TEST_RECORD_TERMINAL")?;
+        let test_name = thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .replace("::", "-");
+        let stream = reader_name.strip_suffix("_reader").unwrap_or(reader_name);
+        std::fs::create_dir_all(&dir).ok()?;
+        let path = PathBuf::from(dir).join(format!("{test_name}.{stream}.cast"));
+        let mut file = File::create(path).ok()?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        writeln!(
+            file,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {timestamp}}}",
+            size.ws_col, size.ws_row
+        )
+        .ok()?;
+
+        Some(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// Append one output event. Failures are swallowed, a broken recording
+    /// must not fail the test it was supposed to help debugging.
+    fn record(&mut self, data: &[u8]) {
+        let time = self.started.elapsed().as_secs_f64();
+        let _ = writeln!(
+            self.file,
+            "[{time:.6}, \"o\", \"{}\"]",
+            Self::json_escape(data)
+        );
+    }
+
+    /// Escape raw terminal output for use inside a JSON string literal.
+    fn json_escape(data: &[u8]) -> String {
+        let mut escaped = String::with_capacity(data.len());
+        for c in String::from_utf8_lossy(data).chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    escaped.push_str(&format!("\\u{:04x}", c as u32));
+                }
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+/// Forwards one output stream of the child (e.g. a pty master) into the capture file on a
+/// dedicated reader thread.
+///
+/// The forwarding can be bounded with a capture limit, so a runaway child cannot fill up the
+/// disk of the test runner. When the limit is reached, [`CAPTURE_TRUNCATION_MARKER`] is
+/// appended to the capture file and the [`CaptureLimitPolicy`] decides whether the remaining
+/// output is discarded or the child is left blocking.
+#[derive(Debug, Default)]
+struct ForwardedOutput {
+    truncated: Arc<AtomicBool>,
+    reader_thread_handle: Option<thread::JoinHandle<()>>,
+    /// Keeps the source alive in backpressure mode, so the child blocks on a full kernel
+    /// buffer instead of receiving `EIO` after the reader thread stopped reading.
+    #[cfg(unix)]
+    held_pty_master: Option<OwnedFd>,
+}
+
+impl ForwardedOutput {
+    /// Spawn the reader thread forwarding `source` into `dest`.
+    #[cfg(unix)]
+    fn spawn(
+        &mut self,
+        name: String,
+        source: OwnedFd,
+        dest: File,
+        limit: Option<u64>,
+        policy: CaptureLimitPolicy,
+        recorder: Option<TerminalRecorder>,
+    ) -> io::Result<()> {
+        if limit.is_some() && policy == CaptureLimitPolicy::Backpressure {
+            self.held_pty_master = Some(source.try_clone()?);
+        }
+
+        let truncated = self.truncated.clone();
+        let handle = thread::Builder::new().name(name).spawn(move || {
+            Self::forward(source, dest, limit, policy, &truncated, recorder);
+        })?;
+        self.reader_thread_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Return whether the capture limit was reached and the output was truncated.
+    fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    /// Join with the reader thread if it is still running.
+    fn join(&mut self) {
+        if let Some(handle) = self.reader_thread_handle.take() {
+            handle.join().unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    fn forward(
+        source: OwnedFd,
+        dest: File,
+        limit: Option<u64>,
+        policy: CaptureLimitPolicy,
+        truncated: &AtomicBool,
+        mut recorder: Option<TerminalRecorder>,
+    ) {
+        let mut reader = std::io::BufReader::new(File::from(source));
+        let mut writer = std::io::BufWriter::new(dest);
+        let mut remaining = limit.unwrap_or(u64::MAX);
+        let mut buffer = [0u8; 4096];
+        loop {
+            let count = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => count,
+                // Input/output error (os error 5) is returned due to pipe closes. Buffer gets content anyway.
+                Err(e) if e.raw_os_error().unwrap_or_default() == 5 => break,
+                Err(e) => {
+                    eprintln!("Unexpected error: {:?}", e);
+                    panic!("error forwarding output of pty");
+                }
+            };
+
+            if let Some(recorder) = recorder.as_mut() {
+                // recorded before the capture limit applies, a recording is
+                // for debugging and should show everything
+                recorder.record(&buffer[..count]);
+            }
+
+            let written = remaining.min(count as u64) as usize;
+            writer.write_all(&buffer[..written]).unwrap();
+            // make the output visible to mid-run readers like
+            // [`CapturedOutput::expect`] right away
+            writer.flush().unwrap();
+            remaining -= written as u64;
+
+            if written < count && !truncated.swap(true, Ordering::Relaxed) {
+                writer.write_all(CAPTURE_TRUNCATION_MARKER).unwrap();
+                writer.flush().unwrap();
+                if policy == CaptureLimitPolicy::Backpressure {
+                    // `held_pty_master` keeps the source open, the child blocks once the
+                    // kernel buffer is full.
+                    return;
+                }
+            }
+        }
+        writer.flush().unwrap();
+    }
+}
+
 /// Stored the captured output in a temporary file. The file is deleted as soon as
 /// [`CapturedOutput`] is dropped.
 #[derive(Debug)]
 struct CapturedOutput {
     current_file: File,
     output: tempfile::NamedTempFile, // drop last
-    reader_thread_handle: Option<thread::JoinHandle<()>>,
+    forwarding: Option<ForwardedOutput>,
 }
 
 impl CapturedOutput {
@@ -1766,7 +2349,7 @@ impl CapturedOutput {
         Self {
             current_file: output.reopen().unwrap(),
             output,
-            reader_thread_handle: None,
+            forwarding: None,
         }
     }
 
@@ -1775,6 +2358,20 @@ impl CapturedOutput {
         self.output.as_file().try_clone()
     }
 
+    /// Return whether the capture limit was reached and the output was truncated.
+    fn is_truncated(&self) -> bool {
+        self.forwarding
+            .as_ref()
+            .is_some_and(ForwardedOutput::is_truncated)
+    }
+
+    /// Join with the forwarding reader thread if there is one.
+    fn join_forwarding(&mut self) {
+        if let Some(forwarding) = self.forwarding.as_mut() {
+            forwarding.join();
+        }
+    }
+
     /// Return the captured output as [`String`].
     ///
     /// Subsequent calls to any of the other output methods will operate on the subsequent output.
@@ -1815,6 +2412,39 @@ impl CapturedOutput {
         buffer
     }
 
+    /// Block until the not yet consumed output contains `expected`, then
+    /// consume the output up to and including the match.
+    ///
+    /// Polls the capture file the reader thread (or the child directly, on a
+    /// plain pipe) appends to, so everything after the match stays available
+    /// for the other output methods and for subsequent `expect` calls.
+    /// Panics when `timeout` expires without a match, with the output
+    /// captured so far in the panic message.
+    fn expect(&mut self, stream_name: &str, expected: &[u8], timeout: Duration) {
+        assert!(!expected.is_empty(), "cannot expect an empty string");
+        let start = Instant::now();
+        let mut buffer = Vec::<u8>::new();
+        loop {
+            self.current_file.read_to_end(&mut buffer).unwrap();
+            if let Some(position) = buffer
+                .windows(expected.len())
+                .position(|window| window == expected)
+            {
+                // rewind to right after the match, the rest stays unconsumed
+                let unread = (buffer.len() - (position + expected.len())) as i64;
+                self.current_file.seek(SeekFrom::Current(-unread)).unwrap();
+                return;
+            }
+            std::assert!(
+                start.elapsed() < timeout,
+                "timeout waiting for {:?} on {stream_name}. captured output so far: {:?}",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(&buffer),
+            );
+            sleep(Duration::from_millis(10));
+        }
+    }
+
     /// Return the exact amount of bytes.
     ///
     /// Subsequent calls to any of the other output methods will operate on the subsequent output.
@@ -1843,7 +2473,7 @@ impl Default for CapturedOutput {
         Self {
             current_file: file.reopen().unwrap(),
             output: file,
-            reader_thread_handle: None,
+            forwarding: None,
         }
     }
 }
@@ -1978,6 +2608,13 @@ pub struct UChild {
     captured_stdout: Option<CapturedOutput>,
     captured_stderr: Option<CapturedOutput>,
     stdin_pty: Option<File>,
+    /// Clone of the stdin pty master kept for [`UChild::terminal_mode`], since
+    /// `stdin_pty` is consumed when piping input into the child.
+    stdin_pty_mode: Option<File>,
+    /// The pty masters of the simulated terminal, kept for
+    /// [`UChild::resize_terminal`].
+    #[cfg(unix)]
+    pty_masters: Vec<OwnedFd>,
     ignore_stdin_write_error: bool,
     stderr_to_stdout: bool,
     join_handle: Option<JoinHandle<io::Result<()>>>,
@@ -1999,7 +2636,14 @@ impl UChild {
             util_name: ucommand.util_name.clone(),
             captured_stdout,
             captured_stderr,
+            stdin_pty_mode: stdin_pty.as_ref().map(|f| f.try_clone().unwrap()),
             stdin_pty,
+            #[cfg(unix)]
+            pty_masters: ucommand
+                .pty_masters
+                .iter()
+                .map(|master| master.try_clone().unwrap())
+                .collect(),
             ignore_stdin_write_error: ucommand.ignore_stdin_write_error,
             stderr_to_stdout: ucommand.stderr_to_stdout,
             join_handle: None,
@@ -2184,15 +2828,11 @@ impl UChild {
         };
 
         if let Some(stdout) = self.captured_stdout.as_mut() {
-            if let Some(handle) = stdout.reader_thread_handle.take() {
-                handle.join().unwrap();
-            }
+            stdout.join_forwarding();
             output.stdout = stdout.output_bytes();
         }
         if let Some(stderr) = self.captured_stderr.as_mut() {
-            if let Some(handle) = stderr.reader_thread_handle.take() {
-                handle.join().unwrap();
-            }
+            stderr.join_forwarding();
             output.stderr = stderr.output_bytes();
         }
 
@@ -2354,6 +2994,88 @@ impl UChild {
         }
     }
 
+    /// Return whether the captured stdout was truncated because the capture limit given with
+    /// [`UCommand::capture_limit`] was reached.
+    pub fn stdout_is_truncated(&self) -> bool {
+        self.captured_stdout
+            .as_ref()
+            .is_some_and(CapturedOutput::is_truncated)
+    }
+
+    /// Return whether the captured stderr was truncated because the capture limit given with
+    /// [`UCommand::capture_limit`] was reached.
+    pub fn stderr_is_truncated(&self) -> bool {
+        self.captured_stderr
+            .as_ref()
+            .is_some_and(CapturedOutput::is_truncated)
+    }
+
+    /// Return a [`TerminalMode`] snapshot of the pseudo terminal connected to the child's
+    /// stdin.
+    ///
+    /// Works while the child is running and after it exited, so tests can verify that a
+    /// utility which changed terminal settings restored them on exit. Requires
+    /// [`UCommand::terminal_simulation`] and panics otherwise.
+    #[cfg(unix)]
+    pub fn terminal_mode(&self) -> TerminalMode {
+        let fd = self
+            .stdin_pty_mode
+            .as_ref()
+            .expect("terminal_mode requires UCommand::terminal_simulation");
+        let termios = tcgetattr(fd).expect("could not get terminal attributes of the pty");
+        TerminalMode {
+            echo: termios.local_flags.contains(LocalFlags::ECHO),
+            canonical: termios.local_flags.contains(LocalFlags::ICANON),
+            output_processing: termios.output_flags.contains(OutputFlags::OPOST),
+        }
+    }
+
+    /// Return a [`TerminalMode`] snapshot of the console connected to the child's stdin.
+    ///
+    /// Not yet available on this platform because the terminal simulation is pty-based.
+    #[cfg(not(unix))]
+    pub fn terminal_mode(&self) -> TerminalMode {
+        panic!("terminal_mode is not implemented for this platform");
+    }
+
+    /// Resize the simulated terminal while the child is running.
+    ///
+    /// Applies the new size to the ptys of the simulated terminal (`TIOCSWINSZ`) and notifies
+    /// the child with `SIGWINCH`, like a terminal emulator does when its window is resized.
+    /// The explicit signal is needed because the ptys are not the child's controlling
+    /// terminal, so the kernel does not deliver one. Requires
+    /// [`UCommand::terminal_simulation`] and panics otherwise.
+    #[cfg(unix)]
+    pub fn resize_terminal(&mut self, win_size: libc::winsize) -> &mut Self {
+        assert!(
+            !self.pty_masters.is_empty(),
+            "resize_terminal requires UCommand::terminal_simulation"
+        );
+        for master in &self.pty_masters {
+            // SAFETY: the fd is owned and valid, win_size outlives the call.
+            let result = unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &win_size) };
+            std::assert_eq!(
+                result,
+                0,
+                "could not resize the pty: {}",
+                io::Error::last_os_error()
+            );
+        }
+        // SAFETY: the pid is the one of our child and SIGWINCH is harmless for
+        // a child that does not handle it.
+        unsafe { libc::kill(self.raw.id() as libc::pid_t, libc::SIGWINCH) };
+        self
+    }
+
+    /// Resize the simulated console while the child is running.
+    ///
+    /// Not yet available on this platform: once the terminal simulation grows ConPTY
+    /// support, this is where `ResizePseudoConsole` belongs.
+    #[cfg(not(unix))]
+    pub fn resize_terminal(&mut self) -> &mut Self {
+        panic!("resize_terminal is not implemented for this platform");
+    }
+
     fn access_stdin_as_writer<'a>(&'a mut self) -> Box<dyn Write + Send + 'a> {
         if let Some(stdin_fd) = &self.stdin_pty {
             Box::new(BufWriter::new(stdin_fd.try_clone().unwrap()))
@@ -2476,6 +3198,50 @@ impl UChild {
         self
     }
 
+    /// Wait until the child's stdout contains `expected` and consume the
+    /// output up to and including the match.
+    ///
+    /// Together with [`UChild::send_line`] this allows scripted dialogues
+    /// with an interactive child in the style of `expect(1)`:
+    ///
+    /// ```no_run
+    /// child.expect_stdout("password: ").send_line("secret");
+    /// ```
+    ///
+    /// Repeated calls match against the output following the previous match,
+    /// so the same prompt can be expected several times. Works on a pipe as
+    /// well as with [`UCommand::terminal_simulation`]. The wait is bounded
+    /// by [`UCommand::timeout`] (default 30s); on expiry the call panics
+    /// with the output captured so far. Panics also if the stdout is not
+    /// captured, e.g. after [`UCommand::set_stdout`].
+    pub fn expect_stdout<T: AsRef<[u8]>>(&mut self, expected: T) -> &mut Self {
+        let timeout = self.timeout.unwrap_or(Duration::from_secs(30));
+        self.captured_stdout
+            .as_mut()
+            .expect("expect_stdout requires stdout to be captured")
+            .expect("stdout", expected.as_ref(), timeout);
+        self
+    }
+
+    /// Wait until the child's stderr contains `expected`, like
+    /// [`UChild::expect_stdout`] does for stdout.
+    pub fn expect_stderr<T: AsRef<[u8]>>(&mut self, expected: T) -> &mut Self {
+        let timeout = self.timeout.unwrap_or(Duration::from_secs(30));
+        self.captured_stderr
+            .as_mut()
+            .expect("expect_stderr requires stderr to be captured")
+            .expect("stderr", expected.as_ref(), timeout);
+        self
+    }
+
+    /// Write `line` followed by a newline to the child's stdin.
+    ///
+    /// The interactive counterpart of [`UChild::pipe_in`], meant to answer a
+    /// prompt awaited with [`UChild::expect_stdout`].
+    pub fn send_line(&mut self, line: &str) -> &mut Self {
+        self.write_in(format!("{line}\n"))
+    }
+
     /// Close the child process stdout.
     ///
     /// Note this will have no effect if the output was captured with [`CapturedOutput`] which is the
@@ -2506,6 +3272,22 @@ impl UChild {
         }
         self
     }
+
+    /// Signal end of input the way an interactive user would and close stdin.
+    ///
+    /// Merely closing the stdin handle is not enough to deliver EOF to a
+    /// console application: a unix pty stays open until `^D` is typed on an
+    /// empty line, and on windows closing the ConPTY input writer is not
+    /// reliably observed by the child at all — the console EOF convention is
+    /// `^Z` followed by `Enter`. This method types the platform's end of
+    /// transmission sequence before closing the handles, so tests relying on
+    /// stdin EOF behave identically on both platforms.
+    pub fn close_stdin_console(&mut self) -> &mut Self {
+        let _ = self.try_write_in(END_OF_TRANSMISSION_SEQUENCE);
+        self.raw.stdin.take();
+        self.stdin_pty.take();
+        self
+    }
 }
 
 pub fn vec_of_size(n: usize) -> Vec<u8> {
@@ -3135,6 +3917,31 @@ mod tests {
             .expect("Signal was none");
     }
 
+    #[cfg(feature = "echo")]
+    #[cfg(unix)]
+    #[test]
+    fn test_wrapper_env_var_prefixes_uutils_binary() {
+        // `/usr/bin/env` is a transparent wrapper, so a concurrently built
+        // command picking up the variable still behaves as without it.
+        std::env::set_var("UUTILS_TEST_WRAPPER", "/usr/bin/env");
+        let result = TestScenario::new("echo").ucmd().arg("wrapped").run();
+        std::env::remove_var("UUTILS_TEST_WRAPPER");
+        result.success().stdout_is("wrapped\n");
+    }
+
+    #[cfg(feature = "sleep")]
+    #[test]
+    fn test_cmd_result_code_or_signal_when_kill_then_passes_on_any_platform() {
+        let mut child = TestScenario::new("sleep").ucmd().arg("60").run_no_wait();
+
+        child.kill();
+        let result = child.wait().unwrap();
+        // signal on unix, mapped `TerminateProcess` exit code on windows
+        result
+            .code_or_signal_is("KILL")
+            .code_or_signal_is("SIGKILL");
+    }
+
     #[cfg(feature = "sleep")]
     #[cfg(unix)]
     #[rstest]
@@ -3669,6 +4476,143 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expect_stdout_scripted_dialogue() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        if cfg!(windows) {
+            cmd.arg("set /p pw=password: & echo got %pw% & set /p pw2=again: & echo got %pw2%");
+        } else {
+            cmd.arg(
+                "printf 'password: '; read pw; echo \"got $pw\"; \
+                 printf 'again: '; read pw; echo \"got $pw\"",
+            );
+        }
+        cmd.set_stdin(Stdio::piped());
+
+        let mut child = cmd.run_no_wait();
+        child.expect_stdout("password: ").send_line("secret");
+        child.expect_stdout("got secret");
+        child.expect_stdout("again: ").send_line("other");
+        child.expect_stdout("got other");
+        child.wait().unwrap().success();
+    }
+
+    #[test]
+    fn test_expect_stderr_waits_for_the_error_stream() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg(if cfg!(windows) {
+            "echo oops>&2"
+        } else {
+            "echo oops >&2"
+        });
+
+        let mut child = cmd.run_no_wait();
+        child.expect_stderr("oops");
+        child.wait().unwrap().success();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expect_stdout_works_in_a_simulated_terminal() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg("printf 'name: '; read name; echo \"hello $name\"");
+        cmd.terminal_simulation(true);
+
+        let mut child = cmd.run_no_wait();
+        child.expect_stdout("name: ").send_line("world");
+        child.expect_stdout("hello world");
+        child.wait().unwrap().success();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminal_simulation_sized() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg("stty size");
+        cmd.terminal_simulation_config(TerminalSimulation::sized(12, 40));
+
+        cmd.run().success().stdout_is("12 40\r\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminal_simulation_echo_off() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg("sleep 2");
+        cmd.terminal_simulation_config(TerminalSimulation::echo_off());
+
+        let mut child = cmd.run_no_wait();
+        assert!(!child.terminal_mode().echo);
+        child.kill();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminal_simulation_stdout_only() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg("[ -t 1 ] && [ ! -t 0 ] && [ ! -t 2 ] && echo only_stdout_is_a_tty");
+        cmd.terminal_simulation_config(TerminalSimulation::stdout_only());
+
+        cmd.run().success().stdout_is("only_stdout_is_a_tty\r\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminal_recording_writes_asciinema_cast() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::env::set_var("UUTILS_TEST_RECORD_TERMINAL", tmp.path());
+
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        cmd.arg("echo recorded");
+        cmd.terminal_simulation(true);
+        cmd.run().success();
+
+        std::env::remove_var("UUTILS_TEST_RECORD_TERMINAL");
+
+        let cast =
+            std::fs::read_to_string(tmp.path().join(
+                "common-util-tests-test_terminal_recording_writes_asciinema_cast.stdout.cast",
+            ))
+            .unwrap();
+        let mut lines = cast.lines();
+        let header = lines.next().unwrap();
+        assert!(header.contains("\"version\": 2"), "{header}");
+        assert!(header.contains("\"width\": 80"), "{header}");
+        assert!(
+            lines.any(|line| line.contains("\"o\", \"recorded\\r\\n\"")),
+            "{cast}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_simulation_of_terminal_resize_mid_run() {
+        let mut cmd = UCommand::new();
+        cmd.timeout(std::time::Duration::from_secs(10));
+        // reports the new terminal size once, on the SIGWINCH of the resize
+        cmd.arg("trap 'stty size; exit 0' WINCH; echo ready; while :; do sleep 0.1; done");
+        cmd.terminal_simulation(true);
+
+        let mut child = cmd.run_no_wait();
+        child.delay(500);
+        child.resize_terminal(libc::winsize {
+            ws_col: 40,
+            ws_row: 12,
+            ws_xpixel: 40 * 8,
+            ws_ypixel: 12 * 10,
+        });
+        let out = child.wait().unwrap();
+
+        std::assert_eq!(String::from_utf8_lossy(out.stdout()), "ready\r\n12 40\r\n");
+    }
+
     #[cfg(unix)]
     #[cfg(feature = "env")]
     #[test]
@@ -3728,6 +4672,100 @@ mod tests {
         std::assert_eq!(String::from_utf8_lossy(out.stderr()), "");
     }
 
+    #[cfg(unix)]
+    #[cfg(feature = "env")]
+    #[test]
+    fn test_simulation_of_terminal_with_capture_limit_truncates_output() {
+        let scene = TestScenario::new("util");
+
+        let mut cmd = scene.ccmd("env");
+        cmd.args(&["sh", "-c", "printf '%01000d' 0"]);
+        cmd.terminal_simulation(true);
+        cmd.capture_limit(100);
+        let child = cmd.run_no_wait();
+        let out = child.wait().unwrap();
+
+        std::assert_eq!(out.stdout().len(), 100 + CAPTURE_TRUNCATION_MARKER.len());
+    }
+
+    #[cfg(unix)]
+    #[cfg(feature = "env")]
+    #[test]
+    fn test_simulation_of_terminal_with_capture_limit_backpressure_blocks_child() {
+        let scene = TestScenario::new("util");
+
+        let mut cmd = scene.ccmd("env");
+        cmd.args(&["sh", "-c", "while :; do printf x; done"]);
+        cmd.terminal_simulation(true);
+        cmd.capture_limit(10);
+        cmd.capture_limit_policy(CaptureLimitPolicy::Backpressure);
+        let mut child = cmd.run_no_wait();
+
+        // the child keeps writing, but the reader thread stopped after 10 bytes
+        child.make_assertion_with_delay(500).is_alive();
+        assert!(child.stdout_is_truncated());
+        child.kill();
+    }
+
+    #[cfg(unix)]
+    #[cfg(feature = "env")]
+    #[test]
+    fn test_terminal_mode_default_settings() {
+        let scene = TestScenario::new("util");
+
+        let mut cmd = scene.ccmd("env");
+        cmd.args(&["sh", "-c", "exit 0"]);
+        cmd.terminal_simulation(true);
+        let mut child = cmd.run_no_wait();
+        child.make_assertion_with_delay(500).is_not_alive();
+
+        let mode = child.terminal_mode();
+        assert!(mode.echo);
+        assert!(mode.canonical);
+        assert!(mode.output_processing);
+        child.wait().unwrap().success();
+    }
+
+    #[cfg(unix)]
+    #[cfg(feature = "env")]
+    #[test]
+    fn test_terminal_mode_observes_changes_made_by_child() {
+        let scene = TestScenario::new("util");
+
+        // stty changes the settings of the pty and exits without restoring them
+        let mut cmd = scene.ccmd("env");
+        cmd.args(&["stty", "-echo", "-icanon"]);
+        cmd.terminal_simulation(true);
+        let mut child = cmd.run_no_wait();
+        child.make_assertion_with_delay(500).is_not_alive();
+
+        let mode = child.terminal_mode();
+        assert!(!mode.echo);
+        assert!(!mode.canonical);
+        assert!(mode.output_processing);
+        child.wait().unwrap().success();
+    }
+
+    #[cfg(feature = "env")]
+    #[test]
+    fn test_close_stdin_console_delivers_eof_to_terminal_child() {
+        let scene = TestScenario::new("util");
+
+        // cat only terminates when it sees EOF on its terminal stdin
+        let mut cmd = scene.ccmd("env");
+        cmd.args(&["cat"]);
+        cmd.terminal_simulation(true);
+        let mut child = cmd.run_no_wait();
+        child.write_in("hello\n");
+        child.make_assertion_with_delay(500).is_alive();
+
+        child.close_stdin_console();
+        child.delay(500);
+        let result = child.wait().unwrap();
+        result.success();
+        assert!(result.stdout_str().contains("hello"));
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_application_of_process_resource_limits_unlimited_file_size() {
@@ -3756,4 +4794,90 @@ mod tests {
             .no_stderr()
             .stdout_is("8\n16\n");
     }
+
+    #[test]
+    fn test_non_utf8_name_and_os_fixture_helpers() {
+        let ts = TestScenario::new("util");
+        let at = &ts.fixtures;
+        let name = non_utf8_name("fixture");
+        assert!(name.to_str().is_none());
+
+        at.write_bytes_os(&name, b"content");
+        assert!(at.file_exists(&name));
+        std::assert_eq!(at.read_bytes_os(&name), b"content");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_os_assertions_accept_non_utf8_names() {
+        let ts = TestScenario::new("util");
+        let name = non_utf8_name("data");
+        ts.fixtures.write_bytes_os(&name, b"x");
+
+        ts.cmd("ls").arg(&name).succeeds().stdout_contains_os(&name);
+
+        ts.cmd("sh")
+            .args(&["-c", "printf '%s\\n' \"$1\" >&2; exit 1", "sh"])
+            .arg(&name)
+            .fails()
+            .stderr_contains_os(&name);
+    }
+
+    #[cfg(all(unix, feature = "echo"))]
+    #[test]
+    fn test_stdout_is_os_with_non_utf8_env_var() {
+        let ts = TestScenario::new("util");
+        let value = non_utf8_name("value");
+        let mut expected = value.clone();
+        expected.push("\n");
+        ts.cmd("sh")
+            .args(&["-c", "printf '%s\\n' \"$WEIRD\""])
+            .env("WEIRD", &value)
+            .succeeds()
+            .stdout_is_os(expected);
+    }
+
+    #[cfg(feature = "echo")]
+    #[test]
+    fn test_ucmd_by_name_dispatches_on_argv0() {
+        let ts = TestScenario::new("echo");
+        let link = ts.link_named_like_util();
+        std::assert!(link.exists());
+        // a second call reuses the existing link
+        std::assert_eq!(link, ts.link_named_like_util());
+        let result = ts.ucmd_by_name().arg("hello").succeeds();
+        std::assert_eq!(result.stdout_str(), "hello\n");
+    }
+
+    #[cfg(feature = "echo")]
+    #[test]
+    fn test_stdout_json_helpers() {
+        let ts = TestScenario::new("echo");
+        let result = ts
+            .ucmd()
+            .arg(r#"{"rows": 24, "tty": {"name": "pts"}}"#)
+            .succeeds();
+        result.stdout_json_is(&serde_json::json!({"rows": 24, "tty": {"name": "pts"}}));
+        result.stdout_json_field_is("/rows", &serde_json::json!(24));
+        result.stdout_json_field_is("/tty/name", &serde_json::json!("pts"));
+    }
+
+    #[cfg(feature = "echo")]
+    #[test]
+    #[should_panic(expected = "stdout is not valid JSON")]
+    fn test_stdout_json_panics_on_invalid_json() {
+        let ts = TestScenario::new("echo");
+        ts.ucmd().arg("not json").succeeds().stdout_json();
+    }
+
+    #[cfg(feature = "echo")]
+    #[test]
+    #[should_panic(expected = "no JSON field at \"/missing\"")]
+    fn test_stdout_json_field_is_reports_missing_field() {
+        let ts = TestScenario::new("echo");
+        ts.ucmd()
+            .arg("{}")
+            .succeeds()
+            .stdout_json_field_is("/missing", &serde_json::json!(1));
+    }
 }